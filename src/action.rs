@@ -1,8 +1,13 @@
 use std::{fmt, str::FromStr};
 
+use serde::{Deserialize, Serialize};
 use strum::EnumIter;
 
-#[derive(Debug, EnumIter, PartialEq, Eq)]
+use crate::{error::Error, hand::Hand, rules::TableRules};
+
+#[derive(
+    Debug, EnumIter, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize, Deserialize,
+)]
 pub enum Action {
     Hit,
     Stand,
@@ -14,28 +19,32 @@ pub enum Action {
 impl Action {
     pub fn can_execute(
         &self,
-        hand_size: usize,
+        hand: &Hand,
         hands_count: usize,
         pot: u32,
-        bet: u32,
-        cards_are_equal: bool,
+        rules: &TableRules,
     ) -> bool {
+        let first_decision = hand.size() == 2;
+        let can_afford = pot >= hand.current_bet();
         match self {
-            Action::DoubleDown => hand_size == 2 && pot >= (bet * 2),
-            Action::Split => hand_size == 2 && cards_are_equal && pot >= (bet * 2),
-            Action::Surrender => hands_count == 1 && hand_size == 2,
+            Action::DoubleDown => {
+                first_decision
+                    && can_afford
+                    && rules.double_on.allows(hand.sum_value())
+                    && (!hand.is_from_split() || rules.double_after_split)
+            }
+            Action::Split => {
+                let split_aces = hand.is_from_split() && hand.cards()[0].is_ace();
+                first_decision
+                    && hand.cards_are_equal()
+                    && can_afford
+                    && hands_count < rules.max_hands as usize
+                    && (!split_aces || rules.resplit_aces)
+            }
+            Action::Surrender => rules.surrender && hands_count == 1 && first_decision,
             Action::Hit | Action::Stand => true,
         }
     }
-
-    pub fn print_actions_list(actions: &[Self]) {
-        let string_actions = actions
-            .iter()
-            .map(|a| a.to_string())
-            .collect::<Vec<String>>()
-            .join("\n");
-        println!("{}", string_actions);
-    }
 }
 
 impl fmt::Display for Action {
@@ -51,16 +60,16 @@ impl fmt::Display for Action {
 }
 
 impl FromStr for Action {
-    type Err = String;
+    type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let action = match s {
-            "s" | "S" => Action::Stand,
-            "h" | "H" => Action::Hit,
-            "d" | "D" => Action::DoubleDown,
-            "x" | "X" => Action::Split,
-            "q" | "Q" => Action::Surrender,
-            _ => return Err("Invalid input for action".into()),
+        let action = match s.to_lowercase().as_str() {
+            "s" | "stand" | "stay" => Action::Stand,
+            "h" | "hit" => Action::Hit,
+            "d" | "dd" | "double" | "double down" => Action::DoubleDown,
+            "x" | "split" => Action::Split,
+            "q" | "surrender" => Action::Surrender,
+            _ => return Err(Error::InvalidAction(s.into())),
         };
         Ok(action)
     }
@@ -69,6 +78,7 @@ impl FromStr for Action {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::rules::DoubleOn;
     use pretty_assertions::assert_eq;
     use rstest::*;
 
@@ -83,10 +93,121 @@ mod test {
     #[case::split_uppercase("X", Action::Split)]
     #[case::surrender_lowercase("q", Action::Surrender)]
     #[case::surrender_uppercase("Q", Action::Surrender)]
+    #[case::hit_word("hit", Action::Hit)]
+    #[case::stand_word("Stand", Action::Stand)]
+    #[case::stay_alias("stay", Action::Stand)]
+    #[case::double_word("double", Action::DoubleDown)]
+    #[case::double_down_words("double down", Action::DoubleDown)]
+    #[case::split_word("SPLIT", Action::Split)]
+    #[case::surrender_word("surrender", Action::Surrender)]
     fn from_str_conversion(#[case] input: &str, #[case] output: Action) {
         assert_eq!(Action::from_str(input).unwrap(), output);
     }
 
     #[rstest]
-    fn can_splt() {}
+    #[case::invalid_letter("z")]
+    #[case::empty("")]
+    fn from_str_rejects_invalid_input(#[case] input: &str) {
+        assert!(matches!(
+            Action::from_str(input),
+            Err(Error::InvalidAction(i)) if i == input
+        ));
+    }
+
+    fn hand(codes: &[&str], from_split: bool) -> Hand {
+        let mut hand = Hand::new(20, None);
+        for code in codes {
+            hand.add_card_to_hand(code.parse().unwrap());
+        }
+        if from_split {
+            let (card, _) = hand.split();
+            hand.add_card_to_hand(card.unwrap());
+        }
+        hand
+    }
+
+    #[rstest]
+    #[case::pair(&["8h", "8s"], 1, 80, true)]
+    #[case::not_a_pair(&["8h", "9s"], 1, 80, false)]
+    #[case::three_cards(&["4h", "4s", "4d"], 1, 80, false)]
+    #[case::not_enough_funds(&["8h", "8s"], 1, 10, false)]
+    #[case::exact_funds(&["8h", "8s"], 1, 20, true)]
+    #[case::max_hands(&["8h", "8s"], 4, 80, false)]
+    fn can_splt(
+        #[case] codes: &[&str],
+        #[case] hands_count: usize,
+        #[case] pot: u32,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(
+            Action::Split.can_execute(
+                &hand(codes, false),
+                hands_count,
+                pot,
+                &TableRules::default()
+            ),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case::resplit_aces(true, true)]
+    #[case::no_resplit_aces(false, false)]
+    fn can_resplit_aces(#[case] resplit_aces: bool, #[case] expected: bool) {
+        let rules = TableRules {
+            resplit_aces,
+            ..TableRules::default()
+        };
+        assert_eq!(
+            Action::Split.can_execute(&hand(&["Ah", "As"], true), 2, 80, &rules),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case::enough_funds(&["5h", "6s"], false, 20, true, DoubleOn::Any, true)]
+    #[case::not_enough_funds(&["5h", "6s"], false, 10, true, DoubleOn::Any, false)]
+    #[case::three_cards(&["2h", "3s", "6d"], false, 80, true, DoubleOn::Any, false)]
+    #[case::after_split(&["5h", "6s"], true, 80, true, DoubleOn::Any, true)]
+    #[case::after_split_without_das(&["5h", "6s"], true, 80, false, DoubleOn::Any, false)]
+    #[case::restricted_total(&["10h", "7s"], false, 80, true, DoubleOn::NineToEleven, false)]
+    fn can_double_down(
+        #[case] codes: &[&str],
+        #[case] from_split: bool,
+        #[case] pot: u32,
+        #[case] double_after_split: bool,
+        #[case] double_on: DoubleOn,
+        #[case] expected: bool,
+    ) {
+        let rules = TableRules {
+            double_after_split,
+            double_on,
+            ..TableRules::default()
+        };
+        assert_eq!(
+            Action::DoubleDown.can_execute(&hand(codes, from_split), 1, pot, &rules),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case::first_decision(&["10h", "6s"], 1, true, true)]
+    #[case::not_offered(&["10h", "6s"], 1, false, false)]
+    #[case::after_hit(&["10h", "2s", "4d"], 1, true, false)]
+    #[case::after_split(&["10h", "6s"], 2, true, false)]
+    fn can_surrender(
+        #[case] codes: &[&str],
+        #[case] hands_count: usize,
+        #[case] surrender: bool,
+        #[case] expected: bool,
+    ) {
+        let rules = TableRules {
+            surrender,
+            ..TableRules::default()
+        };
+        assert_eq!(
+            Action::Surrender.can_execute(&hand(codes, false), hands_count, 80, &rules),
+            expected
+        );
+    }
 }
@@ -0,0 +1,369 @@
+//! Betting progressions, which size each bet from the results of the
+//! previous rounds whatever strategy plays the hands.
+
+use std::{collections::VecDeque, fmt};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::controller::BettingView;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum BettingSystem {
+    /// One unit every round.
+    #[default]
+    Flat,
+    /// Doubles the bet after a loss and goes back to one unit after a win.
+    Martingale,
+    /// Doubles the bet after a win, up to three wins in a row.
+    Paroli,
+    /// One unit more after a loss and one less after a win.
+    #[serde(rename = "dalembert")]
+    #[value(name = "dalembert")]
+    DAlembert,
+    /// One step up the Fibonacci sequence after a loss, two down after a
+    /// win.
+    Fibonacci,
+    /// Bets the ends of a 1-2-3-4 line, crossing them off on a win and
+    /// adding the bet to the line on a loss.
+    Labouchere,
+}
+
+impl fmt::Display for BettingSystem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            BettingSystem::Flat => "flat betting",
+            BettingSystem::Martingale => "Martingale",
+            BettingSystem::Paroli => "Paroli",
+            BettingSystem::DAlembert => "D'Alembert",
+            BettingSystem::Fibonacci => "Fibonacci",
+            BettingSystem::Labouchere => "Labouchère",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How a seat sizes its bets, when not by its controller.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BettingConfig {
+    pub system: BettingSystem,
+    /// Chips in one betting unit, the table's usual bet if left out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<u32>,
+}
+
+/// A progression in betting units, moved along by the result of each round.
+pub trait BettingStrategy: fmt::Debug {
+    /// Units to bet on the next round.
+    fn units(&self) -> u32;
+
+    /// Moves the progression along after a round that was won or lost.
+    /// Pushes leave it where it is.
+    fn record(&mut self, won: bool);
+
+    /// Starts the progression over.
+    fn reset(&mut self);
+}
+
+/// A seat's betting system, kept within the table maximum and its bankroll.
+/// Saved with the session so a resumed progression picks up where it was.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Betting {
+    config: BettingConfig,
+    progression: Progression,
+}
+
+impl Betting {
+    pub fn new(config: BettingConfig) -> Self {
+        let progression = match config.system {
+            BettingSystem::Flat => Progression::Flat(Flat),
+            BettingSystem::Martingale => Progression::Martingale(Martingale::default()),
+            BettingSystem::Paroli => Progression::Paroli(Paroli::default()),
+            BettingSystem::DAlembert => Progression::DAlembert(DAlembert::default()),
+            BettingSystem::Fibonacci => Progression::Fibonacci(Fibonacci::default()),
+            BettingSystem::Labouchere => Progression::Labouchere(Labouchere::default()),
+        };
+        Self {
+            config,
+            progression,
+        }
+    }
+
+    pub fn config(&self) -> &BettingConfig {
+        &self.config
+    }
+
+    /// The next bet. A progression that has outgrown the table maximum or
+    /// the bankroll starts over, as it can no longer win back its losses.
+    pub fn bet(&mut self, table: &BettingView) -> u32 {
+        let unit = self.config.unit.unwrap_or(table.bet);
+        let most = table.limits.max_bet.min(table.bankroll);
+        if self.progression.strategy().units().saturating_mul(unit) > most {
+            self.progression.strategy_mut().reset();
+        }
+        self.progression
+            .strategy()
+            .units()
+            .saturating_mul(unit)
+            .min(most)
+    }
+
+    /// Moves the progression along by what the round won or lost.
+    pub fn record(&mut self, net: i64) {
+        if net != 0 {
+            self.progression.strategy_mut().record(net > 0);
+        }
+    }
+}
+
+/// Where each system's progression stands.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Progression {
+    Flat(Flat),
+    Martingale(Martingale),
+    Paroli(Paroli),
+    #[serde(rename = "dalembert")]
+    DAlembert(DAlembert),
+    Fibonacci(Fibonacci),
+    Labouchere(Labouchere),
+}
+
+impl Progression {
+    fn strategy(&self) -> &dyn BettingStrategy {
+        match self {
+            Progression::Flat(s) => s,
+            Progression::Martingale(s) => s,
+            Progression::Paroli(s) => s,
+            Progression::DAlembert(s) => s,
+            Progression::Fibonacci(s) => s,
+            Progression::Labouchere(s) => s,
+        }
+    }
+
+    fn strategy_mut(&mut self) -> &mut dyn BettingStrategy {
+        match self {
+            Progression::Flat(s) => s,
+            Progression::Martingale(s) => s,
+            Progression::Paroli(s) => s,
+            Progression::DAlembert(s) => s,
+            Progression::Fibonacci(s) => s,
+            Progression::Labouchere(s) => s,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Flat;
+
+impl BettingStrategy for Flat {
+    fn units(&self) -> u32 {
+        1
+    }
+
+    fn record(&mut self, _won: bool) {}
+
+    fn reset(&mut self) {}
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Martingale {
+    losses: u32,
+}
+
+impl BettingStrategy for Martingale {
+    fn units(&self) -> u32 {
+        1u32.checked_shl(self.losses).unwrap_or(u32::MAX)
+    }
+
+    fn record(&mut self, won: bool) {
+        self.losses = if won { 0 } else { self.losses + 1 };
+    }
+
+    fn reset(&mut self) {
+        self.losses = 0;
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Paroli {
+    wins: u32,
+}
+
+impl BettingStrategy for Paroli {
+    fn units(&self) -> u32 {
+        1 << self.wins
+    }
+
+    fn record(&mut self, won: bool) {
+        self.wins = if won { (self.wins + 1) % 3 } else { 0 };
+    }
+
+    fn reset(&mut self) {
+        self.wins = 0;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct DAlembert {
+    units: u32,
+}
+
+impl Default for DAlembert {
+    fn default() -> Self {
+        Self { units: 1 }
+    }
+}
+
+impl BettingStrategy for DAlembert {
+    fn units(&self) -> u32 {
+        self.units
+    }
+
+    fn record(&mut self, won: bool) {
+        self.units = if won {
+            (self.units - 1).max(1)
+        } else {
+            self.units + 1
+        };
+    }
+
+    fn reset(&mut self) {
+        self.units = 1;
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Fibonacci {
+    step: usize,
+}
+
+impl BettingStrategy for Fibonacci {
+    /// 1, 1, 2, 3, 5, 8, ...
+    fn units(&self) -> u32 {
+        let (mut a, mut b) = (1u32, 1u32);
+        for _ in 0..self.step {
+            (a, b) = (b, a.saturating_add(b));
+        }
+        a
+    }
+
+    fn record(&mut self, won: bool) {
+        self.step = if won {
+            self.step.saturating_sub(2)
+        } else {
+            self.step + 1
+        };
+    }
+
+    fn reset(&mut self) {
+        self.step = 0;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Labouchere {
+    line: VecDeque<u32>,
+}
+
+impl Labouchere {
+    const LINE: [u32; 4] = [1, 2, 3, 4];
+}
+
+impl Default for Labouchere {
+    fn default() -> Self {
+        Self {
+            line: Self::LINE.into(),
+        }
+    }
+}
+
+impl BettingStrategy for Labouchere {
+    fn units(&self) -> u32 {
+        match (self.line.front(), self.line.back()) {
+            (Some(first), Some(last)) if self.line.len() > 1 => first.saturating_add(*last),
+            (Some(only), _) => *only,
+            _ => 1,
+        }
+    }
+
+    fn record(&mut self, won: bool) {
+        if won {
+            self.line.pop_front();
+            self.line.pop_back();
+            if self.line.is_empty() {
+                self.reset();
+            }
+        } else {
+            self.line.push_back(self.units());
+        }
+    }
+
+    fn reset(&mut self) {
+        self.line = Self::LINE.into();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{dealer::Composition, rules::TableRules, stats::Statistics, table::Limits};
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    /// The bets a system places over a run of rounds, one unit being 10
+    /// chips at a 5 to 100 table.
+    fn bets(system: BettingSystem, bankroll: u32, results: &[i64]) -> Vec<u32> {
+        let mut betting = Betting::new(BettingConfig {
+            system,
+            unit: Some(10),
+        });
+        let limits = Limits {
+            min_bet: 5,
+            max_bet: 100,
+        };
+        let mut bets = vec![];
+        for &net in results {
+            let table = BettingView {
+                rules: &TableRules::default(),
+                limits: &limits,
+                shoe: Composition::full(1),
+                decks: 1,
+                bankroll,
+                bet: 20,
+                counting: None,
+                stats: &Statistics::default(),
+            };
+            bets.push(betting.bet(&table));
+            betting.record(net);
+        }
+        bets
+    }
+
+    const RUN: [i64; 7] = [-1, -1, -1, 1, 1, 0, 1];
+
+    #[rstest]
+    #[case::flat(BettingSystem::Flat, vec![10, 10, 10, 10, 10, 10, 10])]
+    #[case::martingale(BettingSystem::Martingale, vec![10, 20, 40, 80, 10, 10, 10])]
+    #[case::paroli(BettingSystem::Paroli, vec![10, 10, 10, 10, 20, 40, 40])]
+    #[case::dalembert(BettingSystem::DAlembert, vec![10, 20, 30, 40, 30, 20, 20])]
+    #[case::fibonacci(BettingSystem::Fibonacci, vec![10, 10, 20, 30, 10, 10, 10])]
+    #[case::labouchere(BettingSystem::Labouchere, vec![50, 60, 70, 80, 80, 80, 80])]
+    fn progresses_with_the_results(#[case] system: BettingSystem, #[case] expected: Vec<u32>) {
+        assert_eq!(bets(system, 10_000, &RUN), expected);
+    }
+
+    #[rstest]
+    #[case::table_max(BettingSystem::Martingale, 10_000, vec![10, 20, 40, 80, 10, 20, 40, 80])]
+    #[case::bankroll(BettingSystem::Martingale, 50, vec![10, 20, 40, 10, 20, 40, 10, 20])]
+    #[case::labouchere(BettingSystem::Labouchere, 10_000, vec![50, 60, 70, 80, 90, 100, 50, 60])]
+    fn starts_over_at_the_limits(
+        #[case] system: BettingSystem,
+        #[case] bankroll: u32,
+        #[case] expected: Vec<u32>,
+    ) {
+        assert_eq!(bets(system, bankroll, &[-1; 8]), expected);
+    }
+}
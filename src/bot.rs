@@ -0,0 +1,502 @@
+//! Seats played by an external program, in any language, over a line-based
+//! text protocol on its stdin and stdout, in the manner of UCI for chess
+//! engines. Every line is words separated by spaces; the game writes:
+//!
+//! - `blackjack 1` once at start, the protocol version. The bot may answer
+//!   `id name <name>`, then answers `ready`.
+//! - `rules <key>=<value> ...` once before the first bet: the table rules
+//!   under their table file names, and `decks`.
+//! - `bet bankroll <chips> min <chips> max <chips> default <chips> count
+//!   <running> <true>`, answered by the chips to bet.
+//! - `insurance hand <cards> upcard <card> count <running> <true>`, answered
+//!   by `yes` or `no`.
+//! - `play hand <cards> total <total> soft <0|1> upcard <card> legal
+//!   <actions> count <running> <true>`, answered by one of the legal
+//!   actions: `hit`, `stand`, `double`, `split` or `surrender`.
+//! - `error <reason>` after an answer it could not use, then the question
+//!   again.
+//! - `quit` when the session is over.
+//!
+//! Cards are written like `10h` or `As`, and the counts are Hi-Lo over every
+//! card the seat has seen. The bot may write `info <anything>` lines at any
+//! time, which are ignored. A bot that takes too long to answer, exits or
+//! gives three unusable answers in a row is dropped, and the seat plays
+//! basic strategy for the rest of the session.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command as Process, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    action::Action,
+    card::Card,
+    command::Command,
+    controller::{BettingView, Controller, ControllerKind, TableView},
+    counting::CountingSystem,
+    dealer::Composition,
+    error::Error,
+    hand::Hand,
+    strategy::basic_strategy,
+    table::SeatConfig,
+};
+
+/// The protocol version sent in the greeting.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Unusable answers in a row after which a bot is dropped.
+const STRIKES: u32 = 3;
+
+/// The program a bot seat runs and how long it may think.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BotConfig {
+    /// The program and its arguments.
+    pub command: Vec<String>,
+    /// Milliseconds to wait for each answer.
+    #[serde(default = "BotConfig::default_timeout")]
+    pub timeout_ms: u64,
+}
+
+impl BotConfig {
+    pub fn new(command: Vec<String>) -> Self {
+        Self {
+            command,
+            timeout_ms: Self::default_timeout(),
+        }
+    }
+
+    fn default_timeout() -> u64 {
+        1000
+    }
+
+    /// Names the first setting that cannot be played, with the reason.
+    pub fn validate(&self) -> Result<(), (&'static str, &'static str)> {
+        if self.command.is_empty() {
+            return Err(("command", "must name a program"));
+        }
+        if self.timeout_ms == 0 {
+            return Err(("timeout_ms", "must be at least 1"));
+        }
+        Ok(())
+    }
+}
+
+/// Asks an external program for every decision.
+#[derive(Debug)]
+pub struct BotController {
+    config: BotConfig,
+    /// What the bot called itself, its command otherwise.
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    rules_sent: bool,
+    /// Why the bot was dropped, once it has been.
+    failure: Option<String>,
+}
+
+impl BotController {
+    /// Starts the program and waits for it to be ready.
+    pub fn spawn(config: BotConfig) -> Result<Self, Error> {
+        let command = config.command.join(" ");
+        let error = |reason: String| Error::Bot {
+            name: command.clone(),
+            reason,
+        };
+        let (program, args) = config
+            .command
+            .split_first()
+            .ok_or_else(|| error("no program to run".into()))?;
+        let mut child = Process::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| error(format!("could not be started: {}", e)))?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+        let mut bot = Self {
+            config,
+            name: command.clone(),
+            child,
+            stdin,
+            lines,
+            rules_sent: false,
+            failure: None,
+        };
+        bot.send(&format!("blackjack {}", PROTOCOL_VERSION))
+            .and_then(|_| loop {
+                let line = bot.receive()?;
+                match line.split_once(' ') {
+                    Some(("id", id)) => {
+                        if let Some(name) = id.strip_prefix("name ") {
+                            bot.name = name.trim().to_string();
+                        }
+                    }
+                    _ if line == "ready" => return Ok(()),
+                    _ => return Err(format!("answered {:?} instead of ready", line)),
+                }
+            })
+            .map_err(|reason| {
+                let _ = bot.child.kill();
+                error(reason)
+            })?;
+        Ok(bot)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{}", line)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| format!("could not be written to: {}", e))
+    }
+
+    /// The next line that is not `info`, within the timeout.
+    fn receive(&mut self) -> Result<String, String> {
+        let timeout = Duration::from_millis(self.config.timeout_ms);
+        loop {
+            let line = self.lines.recv_timeout(timeout).map_err(|e| match e {
+                RecvTimeoutError::Timeout => {
+                    format!("gave no answer within {} ms", self.config.timeout_ms)
+                }
+                RecvTimeoutError::Disconnected => "exited".to_string(),
+            })?;
+            let line = line.trim();
+            if !line.is_empty() && line != "info" && !line.starts_with("info ") {
+                return Ok(line.to_string());
+            }
+        }
+    }
+
+    /// Asks `question` until `parse` accepts the answer, telling the bot
+    /// what was wrong with each one it does not. None once the bot has been
+    /// dropped, which it is for going quiet or striking out.
+    fn ask<T>(&mut self, question: &str, parse: impl Fn(&str) -> Result<T, String>) -> Option<T> {
+        if self.failure.is_some() {
+            return None;
+        }
+        let mut strikes = 0;
+        let failure = loop {
+            if let Err(reason) = self.send(question) {
+                break reason;
+            }
+            let answer = match self.receive() {
+                Ok(answer) => answer,
+                Err(reason) => break reason,
+            };
+            match parse(&answer) {
+                Ok(value) => return Some(value),
+                Err(reason) => {
+                    strikes += 1;
+                    if strikes == STRIKES {
+                        break format!("gave {} unusable answers, the last {:?}", STRIKES, answer);
+                    }
+                    if let Err(reason) = self.send(&format!("error {}", reason)) {
+                        break reason;
+                    }
+                }
+            }
+        };
+        self.failure = Some(failure);
+        let _ = self.child.kill();
+        None
+    }
+
+    /// The table rules, sent before the first bet.
+    fn send_rules(&mut self, table: &BettingView) {
+        if self.rules_sent || self.failure.is_some() {
+            return;
+        }
+        self.rules_sent = true;
+        let mut words = vec!["rules".to_string()];
+        if let Ok(serde_json::Value::Object(rules)) = serde_json::to_value(table.rules) {
+            for (key, value) in rules {
+                let value = match value {
+                    serde_json::Value::String(s) => s,
+                    value => value.to_string(),
+                };
+                words.push(format!("{}={}", key, value));
+            }
+        }
+        words.push(format!("decks={}", table.decks));
+        if let Err(reason) = self.send(&words.join(" ")) {
+            self.failure = Some(reason);
+        }
+    }
+}
+
+/// The Hi-Lo running and true counts of everything gone from a shoe of
+/// `decks` decks that still holds `unseen`.
+fn counts(unseen: &Composition, decks: u8) -> String {
+    let running = CountingSystem::HiLo.running_count_of(unseen, decks);
+    let true_count = CountingSystem::HiLo.true_count(running, unseen.total() as usize);
+    format!("count {} {:.2}", running, true_count)
+}
+
+fn cards(cards: &[Card]) -> String {
+    cards.iter().map(Card::code).collect::<Vec<_>>().join(" ")
+}
+
+fn action_word(action: Action) -> &'static str {
+    match action {
+        Action::Hit => "hit",
+        Action::Stand => "stand",
+        Action::DoubleDown => "double",
+        Action::Split => "split",
+        Action::Surrender => "surrender",
+    }
+}
+
+impl Controller for BotController {
+    fn choose_command(
+        &mut self,
+        hand: &Hand,
+        table: &TableView,
+        possible_actions: &[Action],
+    ) -> Result<Command, Error> {
+        let legal: Vec<&str> = possible_actions.iter().map(|&a| action_word(a)).collect();
+        let question = format!(
+            "play hand {} total {} soft {} upcard {} legal {} {}",
+            cards(hand.cards()),
+            hand.sum_value(),
+            hand.is_soft() as u8,
+            table.dealer_upcard.code(),
+            legal.join(" "),
+            counts(&table.shoe, table.decks)
+        );
+        let action = self
+            .ask(&question, |answer| {
+                let action: Action = answer.parse().map_err(|e: Error| e.to_string())?;
+                if possible_actions.contains(&action) {
+                    Ok(action)
+                } else {
+                    Err(Error::IllegalAction(action).to_string())
+                }
+            })
+            .unwrap_or_else(|| {
+                basic_strategy(hand, table.dealer_upcard, possible_actions, table.rules)
+            });
+        Ok(Command::Action(action))
+    }
+
+    fn bet(&mut self, table: &BettingView) -> Result<Command, Error> {
+        self.send_rules(table);
+        let question = format!(
+            "bet bankroll {} min {} max {} default {} {}",
+            table.bankroll,
+            table.limits.min_bet,
+            table.limits.max_bet,
+            table.bet,
+            counts(&table.shoe, table.decks)
+        );
+        let bet = self
+            .ask(&question, |answer| {
+                answer
+                    .parse::<u32>()
+                    .map_err(|_| format!("{:?} is not a number of chips", answer))
+            })
+            .unwrap_or(table.bet);
+        Ok(Command::Bet(bet))
+    }
+
+    fn insure(&mut self, hand: &Hand, table: &TableView) -> bool {
+        let question = format!(
+            "insurance hand {} upcard {} {}",
+            cards(hand.cards()),
+            table.dealer_upcard.code(),
+            counts(&table.shoe, table.decks)
+        );
+        self.ask(&question, |answer| match answer.to_lowercase().as_str() {
+            "yes" => Ok(true),
+            "no" => Ok(false),
+            _ => Err(format!("{:?} is neither yes nor no", answer)),
+        })
+        .unwrap_or(false)
+    }
+
+    fn kind(&self) -> Option<ControllerKind> {
+        Some(ControllerKind::Bot)
+    }
+
+    fn configure(&self, seat: &mut SeatConfig) {
+        seat.bot = Some(self.config.clone());
+    }
+
+    fn failure(&self) -> Option<String> {
+        let reason = self.failure.clone()?;
+        Some(
+            Error::Bot {
+                name: self.name.clone(),
+                reason,
+            }
+            .to_string(),
+        )
+    }
+}
+
+/// Says goodbye, then makes sure the program does not outlive the seat.
+impl Drop for BotController {
+    fn drop(&mut self) {
+        if self.failure.is_none() {
+            let _ = self.send("quit");
+            let deadline = Duration::from_millis(self.config.timeout_ms);
+            let start = std::time::Instant::now();
+            while start.elapsed() < deadline {
+                if let Ok(Some(_)) = self.child.try_wait() {
+                    return;
+                }
+                thread::sleep(Duration::from_millis(5));
+            }
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{rules::TableRules, stats::Statistics, table::Limits};
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    /// Stands on every hand, bets 10 and never insures, thinking aloud
+    /// before it plays.
+    const STANDER: &str = r#"
+        echo "id name Stander"
+        echo ready
+        while read question rest; do
+            case $question in
+                bet) echo 10 ;;
+                insurance) echo no ;;
+                play) echo "info thinking"; echo stand ;;
+                quit) exit ;;
+            esac
+        done
+    "#;
+
+    /// Answers every question with nonsense.
+    const BABBLER: &str = r#"
+        echo ready
+        while read question rest; do
+            case $question in
+                bet|insurance|play) echo fly ;;
+            esac
+        done
+    "#;
+
+    fn bot(script: &str, timeout_ms: u64) -> Result<BotController, Error> {
+        BotController::spawn(BotConfig {
+            command: vec!["sh".into(), "-c".into(), script.into()],
+            timeout_ms,
+        })
+    }
+
+    fn hand(cards: &[&str]) -> Hand {
+        let mut hand = Hand::new(10, None);
+        for card in cards {
+            hand.add_card_to_hand(card.parse().unwrap());
+        }
+        hand
+    }
+
+    /// Plays 6-5 against a 6, asked for once a bet has been placed.
+    fn play(bot: &mut BotController) -> Command {
+        let rules = TableRules::default();
+        let limits = Limits::default();
+        let stats = Statistics::default();
+        let betting = BettingView {
+            rules: &rules,
+            limits: &limits,
+            shoe: Composition::full(1),
+            decks: 1,
+            bankroll: 100,
+            bet: 20,
+            counting: None,
+            stats: &stats,
+        };
+        assert!(bot.bet(&betting).is_ok());
+        let upcard: Card = "6c".parse().unwrap();
+        let table = TableView {
+            rules: &rules,
+            dealer_upcard: &upcard,
+            decisions: &[],
+            count: None,
+            shoe: Composition::full(1),
+            decks: 1,
+            stats: &stats,
+            train: false,
+        };
+        let actions = [Action::Hit, Action::Stand, Action::DoubleDown];
+        bot.choose_command(&hand(&["6h", "5d"]), &table, &actions)
+            .unwrap()
+    }
+
+    #[rstest]
+    fn answers_every_question() {
+        let mut bot = bot(STANDER, 2000).unwrap();
+        assert_eq!(bot.name(), "Stander");
+        assert_eq!(play(&mut bot), Command::Action(Action::Stand));
+        let upcard: Card = "Ad".parse().unwrap();
+        let table = TableView {
+            rules: &TableRules::default(),
+            dealer_upcard: &upcard,
+            decisions: &[],
+            count: None,
+            shoe: Composition::full(1),
+            decks: 1,
+            stats: &Statistics::default(),
+            train: false,
+        };
+        assert!(!bot.insure(&hand(&["10h", "9d"]), &table));
+        assert_eq!(bot.failure(), None);
+    }
+
+    #[rstest]
+    fn plays_basic_strategy_once_the_bot_strikes_out() {
+        let mut bot = bot(BABBLER, 2000).unwrap();
+        assert_eq!(play(&mut bot), Command::Action(Action::DoubleDown));
+        assert!(bot.failure().unwrap().contains("3 unusable answers"));
+    }
+
+    #[rstest]
+    fn plays_basic_strategy_once_the_bot_goes_quiet() {
+        let mut bot = bot("echo ready; sleep 5", 50).unwrap();
+        assert_eq!(play(&mut bot), Command::Action(Action::DoubleDown));
+        assert!(bot.failure().unwrap().contains("no answer within 50 ms"));
+    }
+
+    #[rstest]
+    #[case::no_handshake("echo hello")]
+    #[case::exits("exit 0")]
+    #[case::quiet("sleep 5")]
+    fn fails_to_start_without_a_handshake(#[case] script: &str) {
+        assert!(matches!(bot(script, 100), Err(Error::Bot { .. })));
+    }
+
+    #[rstest]
+    fn fails_to_start_a_missing_program() {
+        let config = BotConfig::new(vec!["/no/such/bot".into()]);
+        assert!(matches!(
+            BotController::spawn(config),
+            Err(Error::Bot { .. })
+        ));
+    }
+}
@@ -1,6 +1,9 @@
-use std::fmt;
+use std::{fmt, str::FromStr};
 
-use strum::EnumIter;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use strum::{EnumIter, IntoEnumIterator};
+
+use crate::error::Error;
 
 #[derive(Debug, EnumIter, PartialEq, Clone, Copy)]
 pub enum Suit {
@@ -21,6 +24,17 @@ impl fmt::Display for Suit {
     }
 }
 
+impl Suit {
+    fn code(&self) -> char {
+        match self {
+            Suit::Hearts => 'h',
+            Suit::Spades => 's',
+            Suit::Clubs => 'c',
+            Suit::Diamods => 'd',
+        }
+    }
+}
+
 #[derive(Debug, EnumIter, PartialEq, Clone, Copy)]
 pub enum Figure {
     Two,
@@ -95,6 +109,47 @@ impl Card {
     pub fn value(&self, hand_sum: u8) -> u8 {
         self.figure.value(hand_sum)
     }
+
+    pub fn is_ace(&self) -> bool {
+        self.figure == Figure::Ace
+    }
+
+    /// Compact notation used by the hand history, e.g. `Ah` or `10d`.
+    pub fn code(&self) -> String {
+        format!("{}{}", self.figure, self.suit.code())
+    }
+}
+
+impl FromStr for Card {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidCard(s.into());
+        let mut chars = s.chars();
+        let suit_code = chars.next_back().ok_or_else(invalid)?;
+        let figure_code = chars.as_str();
+
+        let suit = Suit::iter()
+            .find(|suit| suit.code() == suit_code.to_ascii_lowercase())
+            .ok_or_else(invalid)?;
+        let figure = Figure::iter()
+            .find(|figure| figure.to_string().eq_ignore_ascii_case(figure_code))
+            .ok_or_else(invalid)?;
+        Ok(Card::new(suit, figure))
+    }
+}
+
+impl Serialize for Card {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        code.parse().map_err(de::Error::custom)
+    }
 }
 
 impl fmt::Display for Card {
@@ -110,3 +165,41 @@ impl PartialEq for Card {
 }
 
 impl Eq for Card {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case::ace_of_hearts("Ah", Suit::Hearts, Figure::Ace)]
+    #[case::ten_of_diamonds("10d", Suit::Diamods, Figure::Ten)]
+    #[case::king_of_spades("Ks", Suit::Spades, Figure::King)]
+    #[case::two_of_clubs("2C", Suit::Clubs, Figure::Two)]
+    fn from_str_conversion(#[case] input: &str, #[case] suit: Suit, #[case] figure: Figure) {
+        let card = Card::from_str(input).unwrap();
+        assert_eq!(card.suit, suit);
+        assert_eq!(card.figure, figure);
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::missing_figure("h")]
+    #[case::unknown_suit("Ax")]
+    #[case::unknown_figure("1h")]
+    fn from_str_rejects_invalid_codes(#[case] input: &str) {
+        assert!(Card::from_str(input).is_err());
+    }
+
+    #[rstest]
+    fn code_round_trips() {
+        for suit in Suit::iter() {
+            for figure in Figure::iter() {
+                let card = Card::new(suit, figure);
+                let parsed = Card::from_str(&card.code()).unwrap();
+                assert_eq!((parsed.suit, parsed.figure), (suit, figure));
+            }
+        }
+    }
+}
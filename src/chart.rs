@@ -0,0 +1,645 @@
+//! Strategy charts derived from the exact expected value of every play, so
+//! any ruleset and shoe gets its own chart instead of a hardcoded one.
+
+use std::{collections::BTreeMap, fmt, fs, ops::RangeInclusive, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    action::Action,
+    card::Card,
+    dealer::{card, index, Composition, DealerTable, Shoe, ACE, TEN},
+    error::Error,
+    ev::{expected_values_with, first_actions, ActionValue, ExpectedValues},
+    hand::Hand,
+    rules::TableRules,
+    strategy::Play,
+};
+
+/// Dealer upcards in column order, aces counted as 11.
+pub const UPCARDS: [u8; 10] = [2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+
+/// A complete strategy chart: a row per hard total, soft total and pair
+/// rank, each holding the play against every upcard in [`UPCARDS`] order.
+/// Pair ranks count aces as 11.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StrategyChart {
+    #[serde(default)]
+    pub hard: BTreeMap<u8, [Play; 10]>,
+    #[serde(default)]
+    pub soft: BTreeMap<u8, [Play; 10]>,
+    #[serde(default)]
+    pub pairs: BTreeMap<u8, [Play; 10]>,
+}
+
+impl StrategyChart {
+    /// Derives the chart for `rules` from a full shoe of `decks` decks, or
+    /// an infinite deck. A total made by several two-card hands takes the
+    /// play with the best value averaged over how likely each hand is.
+    pub fn generate(rules: &TableRules, decks: Option<u8>) -> Self {
+        let row = |hands: &[[Card; 2]]| {
+            UPCARDS.map(|upcard| best_play(rules, decks, hands, index_of(upcard)))
+        };
+        let dealt = |[first, second]: [usize; 2]| [card(first, 'h'), card(second, 's')];
+        Self {
+            hard: (5..=20)
+                .map(|total| {
+                    let hands: Vec<_> = hard_hands(total).into_iter().map(dealt).collect();
+                    (total, row(&hands))
+                })
+                .collect(),
+            soft: (13..=20)
+                .map(|total| (total, row(&[dealt([ACE, index_of(total - 11)])])))
+                .collect(),
+            pairs: (2..=11).map(|rank| (rank, row(&[pair_of(rank)]))).collect(),
+        }
+    }
+
+    /// Reads a chart file, as JSON when it ends in `.json` and as a CSV grid
+    /// otherwise, and checks that it covers every hand.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path).map_err(|source| Error::File {
+            path: path.into(),
+            source,
+        })?;
+        let parse_error = |line, reason| Error::Parse {
+            path: path.into(),
+            line,
+            reason,
+        };
+        let chart = if path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("json"))
+        {
+            serde_json::from_str(&contents).map_err(|e| parse_error(e.line(), e.to_string()))?
+        } else {
+            Self::from_csv(&contents).map_err(|(line, reason)| parse_error(line, reason))?
+        };
+        chart.check().map_err(|(key, reason)| Error::Config {
+            path: Some(path.into()),
+            key,
+            reason,
+        })?;
+        Ok(chart)
+    }
+
+    /// Writes the chart as JSON when `path` ends in `.json` and as a CSV grid
+    /// otherwise, the way [`Self::load`] reads it back.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let contents = if path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("json"))
+        {
+            serde_json::to_string_pretty(self).expect("a chart serializes") + "\n"
+        } else {
+            self.to_csv()
+        };
+        fs::write(path, contents).map_err(|source| Error::File {
+            path: path.into(),
+            source,
+        })
+    }
+
+    /// Parses the grid written by [`Self::to_csv`], skipping blank lines and
+    /// `#` comments. Errors carry the line they were found on.
+    fn from_csv(contents: &str) -> Result<Self, (usize, String)> {
+        let mut chart = Self {
+            hard: BTreeMap::new(),
+            soft: BTreeMap::new(),
+            pairs: BTreeMap::new(),
+        };
+        let mut header_seen = false;
+        for (i, line) in contents.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<_> = line.split(',').map(str::trim).collect();
+            if !header_seen {
+                let expected = header();
+                if !fields[0].eq_ignore_ascii_case("hand") || fields[1..] != expected {
+                    let reason = format!("expected the header hand,{}", expected.join(","));
+                    return Err((line_number, reason));
+                }
+                header_seen = true;
+                continue;
+            }
+            let error = |reason: String| (line_number, reason);
+            let (prefix, value) = parse_label(fields[0]).ok_or_else(|| {
+                error(format!("{:?} is not a hand like H16, S18 or P8", fields[0]))
+            })?;
+            if fields.len() != UPCARDS.len() + 1 {
+                return Err(error(format!(
+                    "expected a play for each of the {} upcards",
+                    UPCARDS.len()
+                )));
+            }
+            let mut plays = [Play::Hit; 10];
+            for (play, field) in plays.iter_mut().zip(&fields[1..]) {
+                *play = field.parse().map_err(error)?;
+            }
+            let rows = match prefix {
+                'H' => &mut chart.hard,
+                'S' => &mut chart.soft,
+                _ => &mut chart.pairs,
+            };
+            if rows.insert(value, plays).is_some() {
+                return Err(error(format!("{} appears twice", fields[0])));
+            }
+        }
+        Ok(chart)
+    }
+
+    /// Makes sure every hand has exactly one row and that only pairs split.
+    fn check(&self) -> Result<(), (String, String)> {
+        for (prefix, rows) in self.sections() {
+            let hands = covered(prefix);
+            let error = |value, reason: &str| Err((label(prefix, value), reason.to_string()));
+            if let Some(&value) = rows.keys().find(|value| !hands.contains(value)) {
+                return error(value, "is not a hand on the chart");
+            }
+            if let Some(value) = hands.clone().find(|value| !rows.contains_key(value)) {
+                return error(value, "is missing");
+            }
+            let splits = |plays: &[Play; 10]| plays.contains(&Play::Split);
+            if let Some((&value, _)) = rows
+                .iter()
+                .find(|(_, plays)| prefix != "P" && splits(plays))
+            {
+                return error(value, "splits a hand that is not a pair");
+            }
+        }
+        Ok(())
+    }
+
+    /// The chart's play for `hand` against `upcard`. A pair is read from the
+    /// pair rows only while it can still be split, and from its total after.
+    pub fn play(&self, hand: &Hand, upcard: &Card, can_split: bool) -> Play {
+        let column = upcard.value(0) as usize - 2;
+        let cards = hand.cards();
+        if can_split && cards.len() == 2 && hand.cards_are_equal() {
+            return self.pairs[&cards[0].value(0)][column];
+        }
+        let total = hand.sum_value();
+        if total >= 21 {
+            Play::Stand
+        } else if hand.is_soft() {
+            self.soft[&total.max(13)][column]
+        } else {
+            self.hard[&total.clamp(5, 20)][column]
+        }
+    }
+
+    /// The chart as a CSV grid, one row per hand labelled `H16`, `S18` or
+    /// `P8`, with a column per upcard.
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("hand,{}\n", header().join(","));
+        for (prefix, rows) in self.sections() {
+            for (value, plays) in rows {
+                let label = match prefix {
+                    "P" => rank(*value),
+                    _ => value.to_string(),
+                };
+                let cells: Vec<_> = plays.iter().map(Play::to_string).collect();
+                csv += &format!("{}{},{}\n", prefix, label, cells.join(","));
+            }
+        }
+        csv
+    }
+
+    /// How many cells play the same in both charts under `rules`: the same
+    /// first decision, and the same once only hitting and standing are
+    /// left. A double and a hit are the same where doubling is not allowed.
+    pub fn agreement(&self, other: &StrategyChart, rules: &TableRules) -> usize {
+        let mut agreeing = 0;
+        for ((prefix, rows), (_, other_rows)) in self.sections().into_iter().zip(other.sections()) {
+            for (value, plays) in rows {
+                let Some(other_plays) = other_rows.get(value) else {
+                    continue;
+                };
+                let total = match prefix {
+                    "P" if *value == 11 => 12,
+                    "P" => 2 * value,
+                    _ => *value,
+                };
+                let mut first = vec![Action::Hit, Action::Stand];
+                if rules.double_on.allows(total) {
+                    first.push(Action::DoubleDown);
+                }
+                if prefix == "P" && rules.max_hands > 1 {
+                    first.push(Action::Split);
+                }
+                if rules.surrender {
+                    first.push(Action::Surrender);
+                }
+                let later = [Action::Hit, Action::Stand];
+                agreeing += plays
+                    .iter()
+                    .zip(other_plays)
+                    .filter(|(a, b)| {
+                        a.resolve(&first) == b.resolve(&first)
+                            && a.resolve(&later) == b.resolve(&later)
+                    })
+                    .count();
+            }
+        }
+        agreeing
+    }
+
+    /// Cells on the chart, a play per hand and upcard.
+    pub fn cells(&self) -> usize {
+        self.sections()
+            .iter()
+            .map(|(_, rows)| rows.len() * UPCARDS.len())
+            .sum()
+    }
+
+    fn sections(&self) -> [(&'static str, &BTreeMap<u8, [Play; 10]>); 3] {
+        [("H", &self.hard), ("S", &self.soft), ("P", &self.pairs)]
+    }
+}
+
+/// The hands a chart has a row for in the section written `prefix`.
+fn covered(prefix: &str) -> RangeInclusive<u8> {
+    match prefix {
+        "H" => 5..=20,
+        "S" => 13..=20,
+        _ => 2..=11,
+    }
+}
+
+/// How a row is named in errors, e.g. "hard 16" or "pair A".
+fn label(prefix: &str, value: u8) -> String {
+    match prefix {
+        "H" => format!("hard {}", value),
+        "S" => format!("soft {}", value),
+        _ => format!("pair {}", rank(value)),
+    }
+}
+
+/// Reads a CSV row label such as `H16`, `s18`, `P8` or `PA`.
+fn parse_label(label: &str) -> Option<(char, u8)> {
+    let mut chars = label.chars();
+    let prefix = chars.next()?.to_ascii_uppercase();
+    let value = match (prefix, chars.as_str()) {
+        ('H' | 'S' | 'P', value) if value.parse::<u8>().is_ok() => value.parse().ok()?,
+        ('P', "A" | "a") => 11,
+        _ => return None,
+    };
+    Some((prefix, value))
+}
+
+/// A printable chart with a table per section.
+impl fmt::Display for StrategyChart {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let header = header().map(|upcard| format!("{:>4}", upcard)).concat();
+        for (prefix, rows) in self.sections() {
+            let title = match prefix {
+                "H" => "Hard",
+                "S" => "Soft",
+                _ => "Pairs",
+            };
+            writeln!(f, "{:<6}{}", title, header)?;
+            for (value, plays) in rows {
+                let label = match prefix {
+                    "H" => value.to_string(),
+                    "S" => format!("A,{}", value - 11),
+                    _ => format!("{0},{0}", rank(*value)),
+                };
+                let cells = plays.map(|play| format!("{:>4}", play.to_string()));
+                writeln!(f, "{:>5} {}", label, cells.concat())?;
+            }
+            writeln!(f)?;
+        }
+        write!(
+            f,
+            "H hit, S stand, Dh/Ds double or hit/stand, P split, Rh/Rs surrender or hit/stand"
+        )
+    }
+}
+
+fn header() -> [String; 10] {
+    UPCARDS.map(rank)
+}
+
+fn rank(value: u8) -> String {
+    match value {
+        11 => "A".to_string(),
+        value => value.to_string(),
+    }
+}
+
+/// The shoe index of a card worth `value`, aces counted as 11.
+fn index_of(value: u8) -> usize {
+    match value {
+        11 => ACE,
+        value => value as usize - 1,
+    }
+}
+
+/// Two cards of the same rank worth `value`, such as two tens rather than
+/// the ten and king a hard 20 is dealt as.
+fn pair_of(value: u8) -> [Card; 2] {
+    let i = index_of(value);
+    [card(i, 'h'), card(i, 'c')]
+}
+
+/// Two-card hands without an ace making a hard `total`. Pairs are played
+/// from their own rows, except two different tens such as 10 and K.
+fn hard_hands(total: u8) -> Vec<[usize; 2]> {
+    let total = total as usize;
+    (1..=TEN)
+        .filter_map(|low| {
+            let high = total.checked_sub(low + 2)?;
+            let distinct = low < high || low == TEN && high == TEN;
+            (distinct && high <= TEN).then_some([low, high])
+        })
+        .collect()
+}
+
+/// The best play for any of `hands` against the upcard of index `upcard`,
+/// weighing each hand by its chance of being dealt.
+fn best_play(rules: &TableRules, decks: Option<u8>, hands: &[[Card; 2]], upcard: usize) -> Play {
+    let upcard = card(upcard, 'd');
+    let full = match decks {
+        Some(decks) => Shoe::Finite(Composition::full(decks).without([&upcard])),
+        None => Shoe::Infinite,
+    };
+    let chances: BTreeMap<usize, f64> = full.draws().collect();
+
+    let mut dealer = DealerTable::new(rules);
+    let mut totals: Vec<ActionValue> = Vec::new();
+    let mut allowed = Vec::new();
+    for cards in hands {
+        let mut hand = Hand::new(1, None);
+        for card in cards {
+            hand.add_card_to_hand(card.clone());
+        }
+        allowed = first_actions(&hand, rules);
+        let [first, second] = cards.each_ref().map(index);
+        let shoe = match full {
+            Shoe::Finite(composition) => Shoe::Finite(composition.without(hand.cards())),
+            Shoe::Infinite => Shoe::Infinite,
+        };
+        let order = if first == second { 1.0 } else { 2.0 };
+        let weight = order * chances[&first] * chances[&second];
+        let values = expected_values_with(&mut dealer, &hand, &upcard, &shoe, rules);
+        for value in values.values {
+            match totals.iter_mut().find(|v| v.action == value.action) {
+                Some(total) => total.ev += weight * value.ev,
+                None => totals.push(ActionValue {
+                    ev: weight * value.ev,
+                    ..value
+                }),
+            }
+        }
+    }
+
+    let values = ExpectedValues { values: totals };
+    let hit = values.ranked(&[Action::Hit, Action::Stand])[0].action == Action::Hit;
+    match (values.ranked(&allowed)[0].action, hit) {
+        (Action::DoubleDown, true) => Play::DoubleOrHit,
+        (Action::DoubleDown, false) => Play::DoubleOrStand,
+        (Action::Split, _) => Play::Split,
+        (Action::Surrender, true) => Play::SurrenderOrHit,
+        (Action::Surrender, false) => Play::SurrenderOrStand,
+        (_, true) => Play::Hit,
+        (_, false) => Play::Stand,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        drill::Flashcard,
+        rules::DoubleOn,
+        strategy::{basic_strategy, HandCategory},
+        table::TableConfig,
+    };
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    fn cell(chart: &StrategyChart, card: &Flashcard) -> Play {
+        let rows = match card.category {
+            HandCategory::Hard => &chart.hard,
+            HandCategory::Soft => &chart.soft,
+            HandCategory::Pair => &chart.pairs,
+        };
+        rows[&card.value][card.upcard as usize - 2]
+    }
+
+    fn play(chart: &StrategyChart, category: HandCategory, value: u8, upcard: u8) -> Play {
+        let card = Flashcard {
+            category,
+            value,
+            upcard,
+        };
+        cell(chart, &card)
+    }
+
+    #[rstest]
+    fn infinite_deck_chart_matches_basic_strategy() {
+        let rules = TableRules::default();
+        let chart = StrategyChart::generate(&rules, None);
+        let all = [
+            Action::Hit,
+            Action::Stand,
+            Action::DoubleDown,
+            Action::Split,
+            Action::Surrender,
+        ];
+        let differences: Vec<_> = Flashcard::all()
+            .iter()
+            .filter(|card| {
+                let action = cell(&chart, card).resolve(&all);
+                action != basic_strategy(&card.hand(), &card.upcard(), &all, &rules)
+            })
+            .map(Flashcard::to_string)
+            .collect();
+        // Both soft doubles win by less than 0.01% in a shoe and lose by as
+        // little in an infinite deck.
+        assert_eq!(differences, vec!["soft 13 against 5", "soft 15 against 4"]);
+    }
+
+    #[rstest]
+    #[case::european_no_hole_card(TableConfig::preset("european-no-hole-card").unwrap().rules)]
+    #[case::no_hole_card_with_surrender(TableRules {
+        dealer_peeks: false,
+        ..TableRules::default()
+    })]
+    fn basic_strategy_follows_the_chart_without_a_hole_card(#[case] rules: TableRules) {
+        let chart = StrategyChart::generate(&rules, None);
+        let differences: Vec<_> = Flashcard::all()
+            .iter()
+            .filter(|card| {
+                let hand = card.hand();
+                let possible: Vec<_> = [
+                    Action::Hit,
+                    Action::Stand,
+                    Action::DoubleDown,
+                    Action::Split,
+                    Action::Surrender,
+                ]
+                .into_iter()
+                .filter(|a| a.can_execute(&hand, 1, 1000, &rules))
+                .collect();
+                let action = cell(&chart, card).resolve(&possible);
+                action != basic_strategy(&hand, &card.upcard(), &possible, &rules)
+            })
+            .map(Flashcard::to_string)
+            .collect();
+        let expected: &[&str] = if rules.double_on.allows(13) {
+            &["soft 13 against 5", "soft 15 against 4"]
+        } else {
+            &[]
+        };
+        assert_eq!(differences, expected);
+    }
+
+    #[rstest]
+    fn chart_follows_the_rules() {
+        let h17 = TableRules {
+            dealer_hits_soft_17: true,
+            ..TableRules::default()
+        };
+        let no_surrender = TableRules {
+            surrender: false,
+            ..TableRules::default()
+        };
+        let chart = StrategyChart::generate(&h17, None);
+        assert_eq!(play(&chart, HandCategory::Hard, 11, 11), Play::DoubleOrHit);
+        let chart = StrategyChart::generate(&no_surrender, None);
+        assert_eq!(play(&chart, HandCategory::Hard, 16, 10), Play::Hit);
+    }
+
+    #[rstest]
+    fn csv_has_a_row_per_hand() {
+        let chart = StrategyChart::generate(&TableRules::default(), None);
+        let csv = chart.to_csv();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 1 + 16 + 8 + 10);
+        assert_eq!(lines[0], "hand,2,3,4,5,6,7,8,9,10,A");
+        assert_eq!(lines[12], "H16,S,S,S,S,S,H,H,Rh,Rh,Rh");
+        assert_eq!(lines[33], "P10,S,S,S,S,S,S,S,S,S,S");
+        assert_eq!(lines[34], "PA,P,P,P,P,P,P,P,P,P,P");
+    }
+
+    #[rstest]
+    fn agreement_counts_cells_that_play_the_same() {
+        let rules = TableRules {
+            double_on: DoubleOn::TenToEleven,
+            ..TableRules::default()
+        };
+        let chart = StrategyChart::generate(&rules, None);
+        assert_eq!(chart.agreement(&chart, &rules), chart.cells());
+        assert_eq!(chart.cells(), 340);
+
+        let mut other = chart.clone();
+        // Doubling a hard 5 is not allowed, so it still only hits.
+        other.hard.get_mut(&5).unwrap()[0] = Play::DoubleOrHit;
+        assert_eq!(chart.agreement(&other, &rules), 340);
+        other.hard.get_mut(&5).unwrap()[0] = Play::DoubleOrStand;
+        assert_eq!(chart.agreement(&other, &rules), 339);
+        other.hard.get_mut(&11).unwrap()[0] = Play::Hit;
+        assert_eq!(chart.agreement(&other, &rules), 338);
+    }
+
+    #[rstest]
+    #[case::hard_five(5, vec![[1, 2]])]
+    #[case::hard_twelve(12, vec![[1, 9], [2, 8], [3, 7], [4, 6]])]
+    #[case::hard_twenty(20, vec![[TEN, TEN]])]
+    fn hard_totals_come_from_every_non_pair(#[case] total: u8, #[case] expected: Vec<[usize; 2]>) {
+        assert_eq!(hard_hands(total), expected);
+    }
+
+    #[rstest]
+    #[case::twos(2)]
+    #[case::tens(10)]
+    #[case::aces(11)]
+    fn pair_rows_weigh_splitting(#[case] value: u8) {
+        let mut hand = Hand::new(1, None);
+        for card in pair_of(value) {
+            hand.add_card_to_hand(card);
+        }
+        assert_eq!(hand.sum_value(), if value == 11 { 12 } else { 2 * value });
+        assert!(first_actions(&hand, &TableRules::default()).contains(&Action::Split));
+    }
+
+    fn hand(codes: &[&str]) -> Hand {
+        let mut hand = Hand::new(1, None);
+        for code in codes {
+            hand.add_card_to_hand(code.parse().unwrap());
+        }
+        hand
+    }
+
+    #[rstest]
+    fn csv_and_json_read_back_the_same_chart() {
+        let chart = StrategyChart::generate(&TableRules::default(), None);
+        assert_eq!(StrategyChart::from_csv(&chart.to_csv()), Ok(chart.clone()));
+        let json = serde_json::to_string(&chart).unwrap();
+        assert_eq!(serde_json::from_str::<StrategyChart>(&json).unwrap(), chart);
+    }
+
+    #[rstest]
+    #[case::bad_header("hand,2,3\n", 1)]
+    #[case::unknown_play("# basic\nhand,2,3,4,5,6,7,8,9,10,A\nH5,H,H,H,H,H,H,H,H,H,X\n", 3)]
+    #[case::short_row("hand,2,3,4,5,6,7,8,9,10,A\nH5,H,H\n", 2)]
+    #[case::bad_label("hand,2,3,4,5,6,7,8,9,10,A\nA7,S,S,S,S,S,S,S,S,S,S\n", 2)]
+    #[case::twice(
+        "hand,2,3,4,5,6,7,8,9,10,A\n\nH5,H,H,H,H,H,H,H,H,H,H\nh5,H,H,H,H,H,H,H,H,H,H\n",
+        4
+    )]
+    fn csv_errors_name_the_line(#[case] csv: &str, #[case] line: usize) {
+        assert_eq!(StrategyChart::from_csv(csv).unwrap_err().0, line);
+    }
+
+    #[rstest]
+    fn check_requires_every_hand() {
+        let chart = StrategyChart::generate(&TableRules::default(), None);
+        assert_eq!(chart.check(), Ok(()));
+
+        let mut missing = chart.clone();
+        missing.soft.remove(&18);
+        let error = ("soft 18".to_string(), "is missing".to_string());
+        assert_eq!(missing.check(), Err(error));
+
+        let mut extra = chart.clone();
+        extra.pairs.insert(12, [Play::Split; 10]);
+        let error = (
+            "pair 12".to_string(),
+            "is not a hand on the chart".to_string(),
+        );
+        assert_eq!(extra.check(), Err(error));
+
+        let mut splits = chart;
+        splits.hard.get_mut(&16).unwrap()[0] = Play::Split;
+        let error = (
+            "hard 16".to_string(),
+            "splits a hand that is not a pair".to_string(),
+        );
+        assert_eq!(splits.check(), Err(error));
+    }
+
+    #[rstest]
+    #[case::pair(&["8h", "8s"], "10d", true, Play::Split)]
+    #[case::pair_that_cannot_split(&["8h", "8s"], "10d", false, Play::SurrenderOrHit)]
+    #[case::soft(&["Ah", "7s"], "9d", true, Play::Hit)]
+    #[case::three_card_soft(&["Ah", "2s", "4c"], "6d", false, Play::DoubleOrHit)]
+    #[case::hard(&["10h", "2s"], "4d", false, Play::Stand)]
+    #[case::low_hard(&["2h", "2s"], "4d", false, Play::Hit)]
+    #[case::twenty_one(&["7h", "7s", "7c"], "Ad", false, Play::Stand)]
+    fn play_reads_the_right_row(
+        #[case] codes: &[&str],
+        #[case] upcard: &str,
+        #[case] can_split: bool,
+        #[case] expected: Play,
+    ) {
+        let chart = StrategyChart::generate(&TableRules::default(), None);
+        let upcard: Card = upcard.parse().unwrap();
+        assert_eq!(chart.play(&hand(codes), &upcard, can_split), expected);
+    }
+}
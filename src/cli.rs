@@ -0,0 +1,661 @@
+use std::{collections::HashSet, path::PathBuf};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use blackjack::{
+    betting::BettingSystem,
+    controller::ControllerKind,
+    counting::CountingSystem,
+    table::{SeatConfig, TableConfig},
+};
+
+pub const DEFAULT_BANKROLL: u32 = 100;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Play, train and study blackjack from the terminal")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CliCommand {
+    /// Play at the table, every seat deciding from the keyboard
+    Play(TableArgs),
+    /// Let basic strategy bots play and report the results
+    Simulate(TableArgs),
+    /// Play with trainer helpers such as the running count
+    Train(TableArgs),
+    /// Continue a session saved with --save
+    Resume(ResumeArgs),
+    /// List the saved player profiles, or show one of them
+    Profile(ProfileArgs),
+    /// Re-run a hand history and check it reaches the same outcomes
+    Replay(ReplayArgs),
+    /// Show the basic strategy play for a hand against a dealer upcard
+    Analyze(AnalyzeArgs),
+    /// Drill basic strategy on flashcards, with missed hands coming back sooner
+    Drill(DrillArgs),
+    /// Practise the running and true count on cards dealt from a shoe
+    Count(CountArgs),
+    /// Show the chances of each final dealer total for every upcard
+    Dealer(DealerArgs),
+    /// Derive the strategy chart for a table from the value of every play
+    Chart(ChartArgs),
+    /// Work out the house edge of a table and what each rule costs
+    Edge(EdgeArgs),
+    /// Play many sessions from one bankroll and report the risk of ruin
+    Ruin(RuinArgs),
+    /// Learn a policy by playing, and compare it with basic strategy
+    Learn(LearnArgs),
+    /// Evolve strategy charts by simulated play, reporting the best of each
+    /// generation
+    Evolve(EvolveArgs),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Formats for tables meant to be read elsewhere.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    #[default]
+    Text,
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Args)]
+pub struct TableArgs {
+    /// Number of rounds to play
+    #[arg(short, long, default_value_t = 1)]
+    pub rounds: u32,
+
+    /// Number of seats at the table, replacing the table file's seats
+    #[arg(short, long)]
+    pub players: Option<u8>,
+
+    /// Comma separated seat names, unnamed seats are called "Player N"
+    #[arg(short, long, value_delimiter = ',')]
+    pub names: Vec<String>,
+
+    /// Comma separated profiles to seat first, created on first use and
+    /// updated with their winnings when the session ends
+    #[arg(long = "profile", value_delimiter = ',')]
+    pub profiles: Vec<String>,
+
+    /// Chips each seat starts with [default: 100]
+    #[arg(short, long)]
+    pub bankroll: Option<u32>,
+
+    /// Seed for the shoe, to make a session reproducible
+    #[arg(short, long)]
+    pub seed: Option<u64>,
+
+    /// Table preset name or path of a TOML table file, defaulting to the
+    /// first profile's preferred table or "standard"
+    #[arg(long, visible_alias = "rules")]
+    pub table: Option<String>,
+
+    /// Record every round to this hand-history file
+    #[arg(long)]
+    pub history: Option<PathBuf>,
+
+    /// Also record every round to this SQLite database, created if missing
+    #[arg(long)]
+    pub database: Option<PathBuf>,
+
+    /// Save the session to this file when it ends, to continue it later
+    #[arg(long)]
+    pub save: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub strategy: StrategyArgs,
+
+    #[arg(short, long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+}
+
+/// How simulated seats play their hands and size their bets.
+#[derive(Debug, Default, Args)]
+pub struct StrategyArgs {
+    /// Strategy chart file, CSV or JSON, that simulated seats play by
+    /// instead of basic strategy
+    #[arg(long)]
+    pub chart: Option<PathBuf>,
+
+    /// Simulated seats count cards with Hi-Lo, spread their bets from 1 to
+    /// 12 units of 10 by the true count and play the Illustrious 18 and
+    /// Fab 4
+    #[arg(long, conflicts_with = "chart")]
+    pub counter: bool,
+
+    /// Q-table file, written by `learn`, that simulated seats play by
+    /// instead of basic strategy
+    #[arg(long, conflicts_with_all = ["chart", "counter"])]
+    pub q_table: Option<PathBuf>,
+
+    /// Program, with its arguments, that simulated seats ask for every
+    /// decision over the bot protocol
+    #[arg(long, value_name = "COMMAND", conflicts_with_all = ["chart", "counter", "q_table"])]
+    pub bot: Option<String>,
+
+    /// Milliseconds a --bot program has for each answer
+    #[arg(long, value_name = "MS", requires = "bot", default_value_t = 1000, value_parser = clap::value_parser!(u64).range(1..))]
+    pub bot_timeout: u64,
+
+    /// Counting seats bet this percent of the Kelly bet for the true count
+    /// and their bankroll instead of following the ramp
+    #[arg(long, value_name = "PERCENT", requires = "counter", value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub kelly: Option<u8>,
+
+    /// Betting system every seat sizes its bets by, replacing the table
+    /// file's
+    #[arg(long, value_enum)]
+    pub betting: Option<BettingSystem>,
+
+    /// Chips in one betting unit of --betting [default: 20]
+    #[arg(long, requires = "betting", value_parser = clap::value_parser!(u32).range(1..))]
+    pub unit: Option<u32>,
+}
+
+impl Default for TableArgs {
+    fn default() -> Self {
+        Self {
+            rounds: 1,
+            players: None,
+            names: vec![],
+            profiles: vec![],
+            bankroll: None,
+            seed: None,
+            table: None,
+            history: None,
+            database: None,
+            save: None,
+            strategy: StrategyArgs::default(),
+            format: OutputFormat::default(),
+        }
+    }
+}
+
+impl TableArgs {
+    /// Names for every seat, profiles first, padding the given ones with
+    /// "Player N".
+    pub fn seat_names(&self) -> Vec<String> {
+        let names: Vec<&String> = self.profiles.iter().chain(&self.names).collect();
+        let seats = names.len().max(self.players.unwrap_or(1) as usize);
+        (0..seats)
+            .map(|i| {
+                names
+                    .get(i)
+                    .map(|name| name.to_string())
+                    .unwrap_or_else(|| format!("Player {}", i + 1))
+            })
+            .collect()
+    }
+
+    /// The seats to sit down: the table file's own unless seats are given on
+    /// the command line, with `--bankroll` overriding every bankroll. Every
+    /// profile gets a human seat of its own, ahead of the table file's.
+    /// Fails when two seats would share a name.
+    pub fn seats(&self, config: &TableConfig) -> Result<Vec<SeatConfig>, String> {
+        let human = |name: String| SeatConfig {
+            name,
+            bankroll: DEFAULT_BANKROLL,
+            controller: ControllerKind::Human,
+            chart: None,
+            counter: None,
+            q_table: None,
+            bot: None,
+            betting: None,
+        };
+        let mut seats: Vec<SeatConfig> =
+            if config.seats.is_empty() || self.players.is_some() || !self.names.is_empty() {
+                self.seat_names().into_iter().map(human).collect()
+            } else {
+                self.profiles
+                    .iter()
+                    .cloned()
+                    .map(human)
+                    .chain(config.seats.iter().cloned())
+                    .collect()
+            };
+        if let Some(bankroll) = self.bankroll {
+            for seat in seats.iter_mut() {
+                seat.bankroll = bankroll;
+            }
+        }
+        let mut names = HashSet::new();
+        if let Some(seat) = seats.iter().find(|s| !names.insert(&s.name)) {
+            return Err(format!("Two seats are called {:?}", seat.name));
+        }
+        Ok(seats)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ResumeArgs {
+    /// Save file written with --save, updated again when the session ends
+    pub file: PathBuf,
+
+    /// Number of further rounds to play
+    #[arg(short, long, default_value_t = 1)]
+    pub rounds: u32,
+
+    /// Record every round to this hand-history file
+    #[arg(long)]
+    pub history: Option<PathBuf>,
+
+    /// Also record every round to this SQLite database, created if missing
+    #[arg(long)]
+    pub database: Option<PathBuf>,
+
+    #[arg(short, long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct ProfileArgs {
+    /// Profile to show, every profile is listed when left out
+    pub name: Option<String>,
+
+    #[arg(short, long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct ReplayArgs {
+    /// Hand-history file written with --history
+    pub file: PathBuf,
+
+    #[arg(short, long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct AnalyzeArgs {
+    /// Comma separated player cards, e.g. "Ah,7s"
+    #[arg(long, value_delimiter = ',', required = true)]
+    pub hand: Vec<String>,
+
+    /// Dealer upcard, e.g. "9d"
+    #[arg(long)]
+    pub upcard: String,
+
+    /// Table preset name or path of a TOML table file
+    #[arg(long, visible_alias = "rules", default_value = "standard")]
+    pub table: String,
+
+    /// Decks in the shoe, the table's by default
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=8))]
+    pub decks: Option<u8>,
+
+    /// Comma separated cards already dealt from the shoe, besides the hand
+    /// and the upcard
+    #[arg(long, value_delimiter = ',')]
+    pub seen: Vec<String>,
+
+    #[arg(short, long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct DrillArgs {
+    /// Number of flashcards to answer
+    #[arg(short = 'n', long, default_value_t = 20)]
+    pub cards: u16,
+
+    /// Table preset name or path of a TOML table file whose rules are drilled
+    #[arg(long, visible_alias = "rules", default_value = "standard")]
+    pub table: String,
+
+    /// Seed for drawing the cards
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Progress file, `drill.json` in the blackjack home directory by default
+    #[arg(long)]
+    pub progress: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct CountArgs {
+    #[arg(long, value_enum, default_value_t)]
+    pub system: CountingSystem,
+
+    /// Decks in the shoe
+    #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u8).range(1..=8))]
+    pub decks: u8,
+
+    /// Seconds each card is shown for
+    #[arg(long, default_value_t = 1.0)]
+    pub speed: f64,
+
+    /// Number of times the count is asked for
+    #[arg(short = 'n', long, default_value_t = 5)]
+    pub questions: u16,
+
+    /// Cards dealt before each question
+    #[arg(long, default_value_t = 12)]
+    pub cards: u16,
+
+    /// Ask for the true count instead of the running count
+    #[arg(long)]
+    pub true_count: bool,
+
+    /// Count down a single deck at your own pace against the clock instead
+    #[arg(long, conflicts_with = "true_count")]
+    pub deck: bool,
+
+    /// Seed for shuffling the shoe
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Progress file, `counting.json` in the blackjack home directory by default
+    #[arg(long)]
+    pub progress: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct DealerArgs {
+    /// Table preset name or path of a TOML table file
+    #[arg(long, visible_alias = "rules", default_value = "standard")]
+    pub table: String,
+
+    /// Decks in the shoe, the table's by default
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=8))]
+    pub decks: Option<u8>,
+
+    /// Draw from an infinite deck instead of a real shoe
+    #[arg(long, conflicts_with = "decks")]
+    pub infinite: bool,
+
+    #[arg(short, long, value_enum, default_value_t)]
+    pub format: ExportFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct ChartArgs {
+    /// Table preset name or path of a TOML table file
+    #[arg(long, visible_alias = "rules", default_value = "standard")]
+    pub table: String,
+
+    /// Decks in the shoe, the table's by default
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=8))]
+    pub decks: Option<u8>,
+
+    /// Derive the chart for an infinite deck instead of a real shoe
+    #[arg(long, conflicts_with = "decks")]
+    pub infinite: bool,
+
+    /// Check a chart file, CSV or JSON, covers every hand and show it
+    /// instead of deriving one
+    #[arg(long, conflicts_with_all = ["table", "decks", "infinite"])]
+    pub load: Option<PathBuf>,
+
+    #[arg(short, long, value_enum, default_value_t)]
+    pub format: ExportFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct EdgeArgs {
+    /// Table preset name or path of a TOML table file
+    #[arg(long, visible_alias = "rules", default_value = "standard")]
+    pub table: String,
+
+    /// Decks in the shoe, the table's by default
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=8))]
+    pub decks: Option<u8>,
+
+    /// Work out the edge for an infinite deck, which is much faster
+    #[arg(long, conflicts_with = "decks")]
+    pub infinite: bool,
+
+    /// Compare every preset instead of one table
+    #[arg(long, conflicts_with = "table")]
+    pub presets: bool,
+
+    #[arg(short, long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct RuinArgs {
+    /// Number of independent sessions
+    #[arg(long, default_value_t = 1000, value_parser = clap::value_parser!(u32).range(1..))]
+    pub sessions: u32,
+
+    /// Rounds in each session
+    #[arg(long, default_value_t = 1000)]
+    pub hands: u32,
+
+    /// Chips each session starts with
+    #[arg(short, long, default_value_t = 1000)]
+    pub bankroll: u32,
+
+    /// Table preset name or path of a TOML table file, whose first seat is
+    /// the one simulated if it has any
+    #[arg(long, visible_alias = "rules", default_value = "standard")]
+    pub table: String,
+
+    /// Seed for the first session's shoe, the next sessions counting up
+    /// from it
+    #[arg(short, long)]
+    pub seed: Option<u64>,
+
+    #[command(flatten)]
+    pub strategy: StrategyArgs,
+
+    /// Number of bars in the histogram of final bankrolls
+    #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u16).range(1..))]
+    pub bins: u16,
+
+    /// Write every session's bankroll after each round to this CSV file
+    #[arg(long)]
+    pub trajectories: Option<PathBuf>,
+
+    #[arg(short, long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct LearnArgs {
+    /// Rounds to learn from
+    #[arg(long, default_value_t = 1_000_000)]
+    pub episodes: u64,
+
+    /// Table preset name or path of a TOML table file
+    #[arg(long, visible_alias = "rules", default_value = "standard")]
+    pub table: String,
+
+    /// Seed for the shoe and the exploration, to make training reproducible
+    #[arg(short, long)]
+    pub seed: Option<u64>,
+
+    /// Save the learned Q-table to this JSON file
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Compare a Q-table file saved earlier instead of learning one
+    #[arg(long, conflicts_with_all = ["episodes", "table", "seed", "output"])]
+    pub load: Option<PathBuf>,
+
+    #[arg(short, long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct EvolveArgs {
+    /// Generations to breed
+    #[arg(long, default_value_t = 50, value_parser = clap::value_parser!(u32).range(1..))]
+    pub generations: u32,
+
+    /// Charts in each generation
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u16).range(3..))]
+    pub population: u16,
+
+    /// Rounds each chart plays to score it
+    #[arg(long, default_value_t = 10_000, value_parser = clap::value_parser!(u32).range(1..))]
+    pub rounds: u32,
+
+    /// Chance of each cell of a new chart changing to a random play, from 0
+    /// to 1
+    #[arg(long, default_value_t = 0.02)]
+    pub mutation: f64,
+
+    /// Table preset name or path of a TOML table file
+    #[arg(long, visible_alias = "rules", default_value = "standard")]
+    pub table: String,
+
+    /// Seed for the charts and shoes, to make a search reproducible
+    #[arg(short, long)]
+    pub seed: Option<u64>,
+
+    /// Write the best chart to this file, JSON when it ends in .json and CSV
+    /// otherwise
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    #[arg(short, long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case::defaults(&[], 1, vec!["Player 1"])]
+    #[case::more_seats_than_names(&["Ana"], 3, vec!["Ana", "Player 2", "Player 3"])]
+    #[case::more_names_than_seats(&["Ana", "Bo"], 1, vec!["Ana", "Bo"])]
+    fn seat_names(#[case] names: &[&str], #[case] players: u8, #[case] expected: Vec<&str>) {
+        let args = TableArgs {
+            names: names.iter().map(|n| n.to_string()).collect(),
+            players: Some(players),
+            ..TableArgs::default()
+        };
+        assert_eq!(args.seat_names(), expected);
+    }
+
+    #[rstest]
+    fn parses_simulate_options() {
+        let cli = Cli::try_parse_from([
+            "blackjack",
+            "simulate",
+            "-r",
+            "50",
+            "--names",
+            "Ana,Bo",
+            "--seed",
+            "7",
+            "-f",
+            "json",
+        ])
+        .unwrap();
+        let Some(CliCommand::Simulate(args)) = cli.command else {
+            panic!("Expected the simulate subcommand");
+        };
+        assert_eq!(args.rounds, 50);
+        assert_eq!(args.seat_names(), vec!["Ana", "Bo"]);
+        assert_eq!(args.seed, Some(7));
+        assert_eq!(args.format, OutputFormat::Json);
+    }
+
+    fn configured() -> TableConfig {
+        TableConfig {
+            seats: vec![SeatConfig {
+                name: "Bot".into(),
+                bankroll: 1000,
+                controller: ControllerKind::BasicStrategy,
+                chart: None,
+                counter: None,
+                q_table: None,
+                bot: None,
+                betting: None,
+            }],
+            ..TableConfig::default()
+        }
+    }
+
+    #[rstest]
+    fn profiles_sit_before_named_seats() {
+        let args = TableArgs {
+            names: vec!["Bo".into()],
+            profiles: vec!["Ana".into()],
+            players: Some(3),
+            ..TableArgs::default()
+        };
+        assert_eq!(args.seat_names(), vec!["Ana", "Bo", "Player 3"]);
+    }
+
+    #[rstest]
+    fn seats_come_from_the_table_file() {
+        let args = TableArgs::default();
+        assert_eq!(args.seats(&configured()), Ok(configured().seats));
+    }
+
+    #[rstest]
+    fn profiles_sit_ahead_of_the_table_file_seats() {
+        let args = TableArgs {
+            profiles: vec!["Ana".into(), "Bo".into()],
+            ..TableArgs::default()
+        };
+        let seats = args.seats(&configured()).unwrap();
+        let names: Vec<&str> = seats.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["Ana", "Bo", "Bot"]);
+        assert!(seats[..2]
+            .iter()
+            .all(|s| s.controller == ControllerKind::Human));
+        assert_eq!(seats[2], configured().seats[0]);
+    }
+
+    #[rstest]
+    fn seats_on_the_command_line_replace_the_table_file() {
+        let args = TableArgs {
+            names: vec!["Ana".into()],
+            bankroll: Some(50),
+            ..TableArgs::default()
+        };
+        assert_eq!(
+            args.seats(&configured()),
+            Ok(vec![SeatConfig {
+                name: "Ana".into(),
+                bankroll: 50,
+                controller: ControllerKind::Human,
+                chart: None,
+                counter: None,
+                q_table: None,
+                bot: None,
+                betting: None,
+            }])
+        );
+    }
+
+    #[rstest]
+    #[case::profile_and_table_seat(&["Bot"], &[])]
+    #[case::profile_and_name(&["Ana"], &["Ana"])]
+    #[case::two_names(&[], &["Ana", "Ana"])]
+    fn seats_must_have_their_own_names(#[case] profiles: &[&str], #[case] names: &[&str]) {
+        let args = TableArgs {
+            profiles: profiles.iter().map(|p| p.to_string()).collect(),
+            names: names.iter().map(|n| n.to_string()).collect(),
+            ..TableArgs::default()
+        };
+        assert!(args.seats(&configured()).is_err());
+    }
+
+    #[rstest]
+    fn rules_is_an_alias_of_table() {
+        let cli = Cli::try_parse_from(["blackjack", "play", "--rules", "atlantic-city"]).unwrap();
+        let Some(CliCommand::Play(args)) = cli.command else {
+            panic!("Expected the play subcommand");
+        };
+        assert_eq!(args.table.as_deref(), Some("atlantic-city"));
+    }
+}
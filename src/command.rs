@@ -0,0 +1,78 @@
+use std::str::FromStr;
+
+use crate::{action::Action, error::Error};
+
+/// Everything that can be typed at the betting and decision prompts.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Command {
+    Action(Action),
+    /// A number of chips to bet, at the betting prompt.
+    Bet(u32),
+    Help,
+    Rules,
+    Bankroll,
+    Count,
+    Hint,
+    History,
+    Stats,
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(action) = Action::from_str(s) {
+            return Ok(Command::Action(action));
+        }
+        if let Ok(bet) = s.parse() {
+            return Ok(Command::Bet(bet));
+        }
+        let command = match s.to_lowercase().as_str() {
+            "help" | "?" => Command::Help,
+            "rules" => Command::Rules,
+            "bankroll" | "bank" => Command::Bankroll,
+            "count" => Command::Count,
+            "hint" => Command::Hint,
+            "history" => Command::History,
+            "stats" | "statistics" => Command::Stats,
+            "quit" | "exit" => Command::Quit,
+            _ => return Err(Error::InvalidAction(s.into())),
+        };
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case::action_letter("h", Command::Action(Action::Hit))]
+    #[case::action_word("double", Command::Action(Action::DoubleDown))]
+    #[case::surrender_letter_is_not_quit("q", Command::Action(Action::Surrender))]
+    #[case::help("help", Command::Help)]
+    #[case::help_alias("?", Command::Help)]
+    #[case::stats("stats", Command::Stats)]
+    #[case::rules("rules", Command::Rules)]
+    #[case::bankroll("Bankroll", Command::Bankroll)]
+    #[case::count("count", Command::Count)]
+    #[case::hint("hint", Command::Hint)]
+    #[case::bet("50", Command::Bet(50))]
+    #[case::history("history", Command::History)]
+    #[case::quit("quit", Command::Quit)]
+    #[case::exit("EXIT", Command::Quit)]
+    fn from_str_conversion(#[case] input: &str, #[case] output: Command) {
+        assert_eq!(Command::from_str(input).unwrap(), output);
+    }
+
+    #[rstest]
+    fn from_str_rejects_unknown_commands() {
+        assert!(matches!(
+            Command::from_str("fold"),
+            Err(Error::InvalidAction(i)) if i == "fold"
+        ));
+    }
+}
@@ -1,25 +1,71 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{
     action::Action,
-    player::Player,
-    printer::{Generic, Message, Printer, Result},
+    card::Card,
+    hand::Hand,
+    player::{Player, PlayerKind},
+    printer::{Generic, Info, Message, Printer, Result},
+    stats::PlayerStats,
 };
 
+/// How cards and dividers are drawn on the terminal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    /// Suit symbols and starred dividers.
+    #[default]
+    Classic,
+    /// Plain card codes such as "10h", for terminals without the symbols.
+    Ascii,
+}
+
 pub struct ConsolePrinter {
-    round: u16,
+    round: u32,
+    theme: Theme,
 }
 
 impl ConsolePrinter {
-    pub fn new(round: u16) -> Self {
-        Self { round }
+    pub fn new(round: u32) -> Self {
+        Self {
+            round,
+            theme: Theme::default(),
+        }
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
     }
 
     pub fn print_divider(&self) {
-        println!("{}", "*".repeat(90));
+        let divider = match self.theme {
+            Theme::Classic => "*",
+            Theme::Ascii => "-",
+        };
+        println!("{}", divider.repeat(90));
+    }
+
+    fn card(&self, card: &Card) -> String {
+        match self.theme {
+            Theme::Classic => card.to_string(),
+            Theme::Ascii => card.code(),
+        }
+    }
+
+    fn hand(&self, hand: &Hand) -> String {
+        match self.theme {
+            Theme::Classic => hand.to_string(),
+            Theme::Ascii => {
+                let cards: Vec<String> = hand.cards().iter().map(Card::code).collect();
+                format!("cards: {}", cards.join(", "))
+            }
+        }
     }
 }
 
 impl Printer for ConsolePrinter {
-    fn set_round_settings(&mut self, round: u16) {
+    fn set_round_settings(&mut self, round: u32) {
         self.round = round;
     }
 
@@ -28,27 +74,224 @@ impl Printer for ConsolePrinter {
             Message::PlayerStatus => {
                 let player = player.expect("Did not pass a valid player ref to Printer");
                 match player.kind {
-                    crate::player::PlayerKind::Dealer => {
+                    PlayerKind::Dealer => {
                         self.print_divider();
-                        println!("The dealer has the following hand:\n{}", player.hands[0]);
-                        println!("{}", "*".repeat(90));
+                        println!(
+                            "The dealer has the following hand:\n{}",
+                            self.hand(&player.hands[0])
+                        );
+                        self.print_divider();
+                    }
+                    PlayerKind::Player => {
+                        println!("{} has {} chips and these hands:", player.name, player.pot);
+                        for hand in &player.hands {
+                            println!("{}", self.hand(hand));
+                        }
                     }
-                    crate::player::PlayerKind::Player => todo!(),
                 }
             }
-            Message::Action(a) => match a {
-                Action::Hit => todo!(),
-                Action::Stand => todo!(),
-                Action::DoubleDown => todo!(),
-                Action::Split => todo!(),
-                Action::Surrender => todo!(),
-            },
-            Message::Result(r) => match r {
-                Result::Tie => todo!(),
-                Result::DealerWon => todo!(),
-                Result::PlayerWon => todo!(),
-                Result::Blackjack => todo!(),
+            Message::DealerUpcard(card) => {
+                self.print_divider();
+                println!("The dealer shows {}", self.card(&card));
+                self.print_divider();
+            }
+            Message::HandStatus(current_hand) => {
+                let player = player.expect("Did not pass a valid player ref to Printer");
+                self.print_divider();
+                println!(
+                    "{}, this is your status for your {} hand:\n{}\n",
+                    player.name,
+                    current_hand + 1,
+                    self.hand(&player.hands[current_hand])
+                );
+            }
+            Message::BetPrompt(bet) => {
+                let player = player.expect("Did not pass a valid player ref to Printer");
+                println!(
+                    "{}, you have {} chips. How much will you bet? Press Enter for {}.",
+                    player.name, player.pot, bet
+                );
+            }
+            Message::Prompt(actions) => {
+                println!("What will you do?");
+                for action in actions {
+                    println!("{}", action);
+                }
+            }
+            Message::CardDealt(card, current_hand) => {
+                let player = player.expect("Did not pass a valid player ref to Printer");
+                if player.hands.len() == 1 {
+                    println!("{} got the card {}", player.name, self.card(&card));
+                } else {
+                    println!(
+                        "{} got the card {} for their {} hand",
+                        player.name,
+                        self.card(&card),
+                        current_hand + 1
+                    );
+                }
+            }
+            Message::Action(a) => {
+                let player = player.expect("Did not pass a valid player ref to Printer");
+                match a {
+                    Action::Hit => println!("{}: Hit!", player.name),
+                    Action::Stand => println!("{}: Stand.", player.name),
+                    Action::DoubleDown => println!("{}: DOUBLE DOWN!", player.name),
+                    Action::Split => println!("{}: Split!", player.name),
+                    Action::Surrender => println!("{}: I surrender!", player.name),
+                }
+            }
+            Message::Result(r, current_hand) => {
+                let player = player.expect("Did not pass a valid player ref to Printer");
+                if player.hands.len() == 1 {
+                    println!("Comparing {}'s hand with the Dealers'", player.name);
+                } else {
+                    println!(
+                        "Comparing {}'s {} hand with the Dealers'",
+                        player.name,
+                        current_hand + 1
+                    );
+                }
+                match r {
+                    Result::Tie => println!("Tie!"),
+                    Result::DealerWon => println!("Dealer won"),
+                    Result::PlayerWon => println!("{} won", player.name),
+                    Result::Blackjack => println!("{} won with a Blackjack!", player.name),
+                    Result::Surrendered => println!("{} surrendered half the bet", player.name),
+                }
+            }
+            Message::Insurance(stake) => {
+                let player = player.expect("Did not pass a valid player ref to Printer");
+                println!("{} takes insurance for {} chips", player.name, stake);
+            }
+            Message::InsurancePaid(payout) => {
+                let player = player.expect("Did not pass a valid player ref to Printer");
+                if payout > 0 {
+                    println!("{}'s insurance pays {} chips", player.name, payout);
+                } else {
+                    println!("{}'s insurance is lost", player.name);
+                }
+            }
+            Message::InvalidInput(e) => {
+                println!("{}, type help to see every command.", e);
+            }
+            Message::Info(info) => match info {
+                Info::Help => {
+                    println!("Actions:");
+                    println!(
+                        "  hit (h), stand (s, stay), double (d, dd), split (x), surrender (q)"
+                    );
+                    println!("Betting:");
+                    println!("  a number of chips, or Enter for the usual bet");
+                    println!("Commands:");
+                    println!("  help (?)   show this list");
+                    println!("  rules      show the table rules");
+                    println!("  bankroll   show your chips");
+                    println!("  count      show the running count, in trainer mode");
+                    println!("  hint       suggest the best play, or a Kelly bet in trainer mode");
+                    println!("  history    show this round's decisions so far");
+                    println!("  stats      show your statistics for this session");
+                    println!("  quit       end the session");
+                }
+                Info::Rules(rules) => {
+                    if rules.dealer_hits_soft_17 {
+                        println!("Dealer hits soft 17.");
+                    } else {
+                        println!("Dealer stands on all 17s.");
+                    }
+                    if !rules.dealer_peeks {
+                        println!("The dealer takes no hole card until the players are done.");
+                    }
+                    println!("Blackjack pays {}.", rules.blackjack_payout);
+                    println!("Double down on {}.", rules.double_on);
+                    if rules.double_after_split {
+                        println!("Doubling after a split is allowed.");
+                    }
+                    println!("Split up to {} hands.", rules.max_hands);
+                    if rules.surrender {
+                        println!("Surrender is only offered as the first decision.");
+                    }
+                }
+                Info::Bankroll => {
+                    let player = player.expect("Did not pass a valid player ref to Printer");
+                    let on_table: u32 = player.hands.iter().map(|h| h.current_bet()).sum();
+                    println!(
+                        "{} has {} chips, with {} more on the table.",
+                        player.name, player.pot, on_table
+                    );
+                }
+                Info::Count(Some(count)) => println!("The running count is {}.", count),
+                Info::Count(None) => println!("The count is only available in trainer mode."),
+                Info::Hint(values) => {
+                    let values: Vec<_> = values
+                        .iter()
+                        .map(|v| format!("{} {:+.3}", v.action, v.ev))
+                        .collect();
+                    println!("Expected value per chip bet: {}", values.join(", "));
+                }
+                Info::Kelly(Some(advice)) => {
+                    println!(
+                        "At a true count of {:+.1} your edge is {:+.2}%: full Kelly bets {} chips, {:.0}% Kelly {}.",
+                        advice.true_count,
+                        advice.edge * 100.0,
+                        advice.optimal,
+                        advice.fraction * 100.0,
+                        advice.fractional
+                    );
+                    if advice.optimal == 0 {
+                        println!("Without an edge, bet the table minimum.");
+                    }
+                }
+                Info::Kelly(None) => {
+                    println!("Bet advice needs the count, which is only shown in trainer mode.")
+                }
+                Info::Stats(stats) => {
+                    let player = player.expect("Did not pass a valid player ref to Printer");
+                    print_stats(&player.name, &stats);
+                }
+                Info::History(decisions) => {
+                    if decisions.is_empty() {
+                        println!("No decisions were made yet this round.");
+                    }
+                    for d in decisions {
+                        println!("{} ({} hand): {:?}", d.player, d.hand + 1, d.action);
+                    }
+                }
             },
+            Message::Summary(stats) => {
+                self.print_divider();
+                println!("Session statistics");
+                for (name, stats) in stats.players() {
+                    print_stats(name, stats);
+                }
+            }
+            Message::Feedback(chosen, advice) => {
+                if chosen == advice.action {
+                    println!(
+                        "Correct: {} on {} {}.",
+                        chosen, advice.category, advice.total
+                    );
+                } else {
+                    println!(
+                        "Mistake: basic strategy says {} on {} {}, not {}.",
+                        advice.action, advice.category, advice.total, chosen
+                    );
+                }
+                println!("{}.", advice.reason);
+            }
+            Message::Training(training) => {
+                let player = player.expect("Did not pass a valid player ref to Printer");
+                println!(
+                    "{}: {} of {} decisions by the book ({}), mistakes: {} hard, {} soft, {} pair",
+                    player.name,
+                    training.decisions - training.mistakes(),
+                    training.decisions,
+                    percent(training.accuracy()),
+                    training.hard_mistakes,
+                    training.soft_mistakes,
+                    training.pair_mistakes
+                );
+            }
             Message::Generic(m) => match m {
                 Generic::VerifyResults => {
                     println!("Verifying results now");
@@ -60,9 +303,47 @@ impl Printer for ConsolePrinter {
                     println!("Starting now round {}!\n\n", self.round);
                 }
                 Generic::Exiting => {
-                    println!("All rounds were played, exiting...");
+                    println!("The session is over, exiting...");
+                }
+                Generic::DealerTurn => {
+                    println!("The Dealer will play now");
+                }
+                Generic::DealerBlackjack => {
+                    println!("The Dealer has a Blackjack!");
                 }
             },
         }
     }
 }
+
+pub fn percent(rate: Option<f64>) -> String {
+    rate.map_or("-".into(), |rate| format!("{:.1}%", rate * 100.0))
+}
+
+pub fn print_stats(name: &str, stats: &PlayerStats) {
+    println!(
+        "{}: {} hands in {} rounds, {} won, {} lost, {} pushed ({} won)",
+        name,
+        stats.hands,
+        stats.rounds,
+        stats.wins,
+        stats.losses,
+        stats.pushes,
+        percent(stats.win_rate())
+    );
+    println!(
+        "  naturals {}, busts {}, surrenders {}",
+        stats.naturals, stats.busts, stats.surrenders
+    );
+    println!(
+        "  doubles {} ({} won), splits {} ({} of split hands won)",
+        stats.doubles,
+        percent(stats.double_win_rate()),
+        stats.splits,
+        percent(stats.split_win_rate())
+    );
+    println!(
+        "  net {:+} chips, biggest win {:+}, biggest loss {:+}",
+        stats.net, stats.biggest_win, stats.biggest_loss
+    );
+}
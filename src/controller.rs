@@ -0,0 +1,259 @@
+use std::{
+    collections::VecDeque,
+    fmt, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    action::Action,
+    card::Card,
+    chart::StrategyChart,
+    command::Command,
+    counting::CountingSystem,
+    dealer::Composition,
+    error::Error,
+    hand::Hand,
+    history::DecisionRecord,
+    rules::TableRules,
+    stats::Statistics,
+    strategy::basic_strategy,
+    table::{Limits, SeatConfig},
+};
+
+/// What a seat can see of the table while it decides.
+pub struct TableView<'a> {
+    pub rules: &'a TableRules,
+    pub dealer_upcard: &'a Card,
+    pub decisions: &'a [DecisionRecord],
+    pub count: Option<i32>,
+    /// The cards the seat has not seen, hole card included.
+    pub shoe: Composition,
+    /// Decks the shoe started with.
+    pub decks: u8,
+    pub stats: &'a Statistics,
+    /// Whether each decision is checked against basic strategy.
+    pub train: bool,
+}
+
+/// What a seat can see of the table when it places its bet.
+pub struct BettingView<'a> {
+    pub rules: &'a TableRules,
+    pub limits: &'a Limits,
+    /// The cards left in the shoe, every one dealt so far having been seen.
+    pub shoe: Composition,
+    /// Decks the shoe started with.
+    pub decks: u8,
+    pub bankroll: u32,
+    /// What the table bets for a seat that does not choose.
+    pub bet: u32,
+    /// The counting system shown to the seat, only in trainer mode.
+    pub counting: Option<CountingSystem>,
+    pub stats: &'a Statistics,
+}
+
+impl BettingView<'_> {
+    /// The true count by the counting system shown to the seat, if any.
+    pub fn true_count(&self) -> Option<f64> {
+        self.counting.map(|counting| {
+            let running = counting.running_count_of(&self.shoe, self.decks);
+            counting.true_count(running, self.shoe.total() as usize)
+        })
+    }
+}
+
+/// Source of the decisions taken for a seat.
+pub trait Controller: fmt::Debug {
+    fn choose_command(
+        &mut self,
+        hand: &Hand,
+        table: &TableView,
+        possible_actions: &[Action],
+    ) -> Result<Command, Error>;
+
+    /// How much to bet on the next round, as a [`Command::Bet`] or a
+    /// command answered before asking again. The table keeps the bet within
+    /// its limits and the seat's bankroll.
+    fn bet(&mut self, table: &BettingView) -> Result<Command, Error> {
+        Ok(Command::Bet(table.bet))
+    }
+
+    /// Whether to insure `hand` for half its bet when the dealer shows an
+    /// ace.
+    fn insure(&mut self, _hand: &Hand, _table: &TableView) -> bool {
+        false
+    }
+
+    /// How the seat is described in a table or save file, if it can be.
+    fn kind(&self) -> Option<ControllerKind> {
+        None
+    }
+
+    /// Writes whatever else the controller is built from, such as a chart
+    /// file, into the seat it is saved as.
+    fn configure(&self, _seat: &mut SeatConfig) {}
+
+    /// Why the controller gave up deciding for itself and fell back on
+    /// basic strategy, if it did.
+    fn failure(&self) -> Option<String> {
+        None
+    }
+}
+
+/// The controllers a table file can put in a seat.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ControllerKind {
+    #[default]
+    Human,
+    BasicStrategy,
+    /// Plays by a strategy chart file, given as the seat's `chart`.
+    Chart,
+    /// Counts cards to size its bets and deviate from basic strategy, set
+    /// up by the seat's `counter`.
+    Counter,
+    /// Plays by a Q-table learned in training, given as the seat's
+    /// `q_table`.
+    QTable,
+    /// Asks an external program for every decision, run as the seat's
+    /// `bot`.
+    Bot,
+}
+
+#[derive(Debug)]
+pub struct ConsoleController;
+
+impl Controller for ConsoleController {
+    fn choose_command(
+        &mut self,
+        _hand: &Hand,
+        _table: &TableView,
+        _possible_actions: &[Action],
+    ) -> Result<Command, Error> {
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        Command::from_str(input.trim())
+    }
+
+    /// An empty line keeps the table's usual bet.
+    fn bet(&mut self, table: &BettingView) -> Result<Command, Error> {
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        match input.trim() {
+            "" => Ok(Command::Bet(table.bet)),
+            input => Command::from_str(input),
+        }
+    }
+
+    fn kind(&self) -> Option<ControllerKind> {
+        Some(ControllerKind::Human)
+    }
+}
+
+/// Plays every hand by the basic strategy chart.
+#[derive(Debug)]
+pub struct BasicStrategyController;
+
+impl Controller for BasicStrategyController {
+    fn choose_command(
+        &mut self,
+        hand: &Hand,
+        table: &TableView,
+        possible_actions: &[Action],
+    ) -> Result<Command, Error> {
+        let action = basic_strategy(hand, table.dealer_upcard, possible_actions, table.rules);
+        Ok(Command::Action(action))
+    }
+
+    fn kind(&self) -> Option<ControllerKind> {
+        Some(ControllerKind::BasicStrategy)
+    }
+}
+
+/// Plays every hand by a strategy chart, usually read from a file.
+#[derive(Debug)]
+pub struct ChartController {
+    chart: StrategyChart,
+    path: Option<PathBuf>,
+}
+
+impl ChartController {
+    /// Plays by a chart that has no file, so the seat cannot be saved.
+    pub fn new(chart: StrategyChart) -> Self {
+        Self { chart, path: None }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        Ok(Self {
+            chart: StrategyChart::load(path)?,
+            path: Some(path.into()),
+        })
+    }
+}
+
+impl Controller for ChartController {
+    fn choose_command(
+        &mut self,
+        hand: &Hand,
+        table: &TableView,
+        possible_actions: &[Action],
+    ) -> Result<Command, Error> {
+        let can_split = possible_actions.contains(&Action::Split);
+        let play = self.chart.play(hand, table.dealer_upcard, can_split);
+        Ok(Command::Action(play.resolve(possible_actions)))
+    }
+
+    fn kind(&self) -> Option<ControllerKind> {
+        self.path.as_ref().map(|_| ControllerKind::Chart)
+    }
+
+    fn configure(&self, seat: &mut SeatConfig) {
+        seat.chart = self.path.clone();
+    }
+}
+
+/// Feeds back the decisions recorded in a hand history.
+#[derive(Debug)]
+pub struct ReplayController {
+    actions: VecDeque<Action>,
+    insure: bool,
+}
+
+impl ReplayController {
+    pub fn new(actions: impl IntoIterator<Item = Action>) -> Self {
+        Self {
+            actions: actions.into_iter().collect(),
+            insure: false,
+        }
+    }
+
+    /// Takes insurance when it is offered, as the recorded seat did.
+    pub fn with_insurance(mut self, insure: bool) -> Self {
+        self.insure = insure;
+        self
+    }
+}
+
+impl Controller for ReplayController {
+    fn choose_command(
+        &mut self,
+        _hand: &Hand,
+        _table: &TableView,
+        _possible_actions: &[Action],
+    ) -> Result<Command, Error> {
+        self.actions
+            .pop_front()
+            .map(Command::Action)
+            .ok_or(Error::MissingDecision)
+    }
+
+    fn insure(&mut self, _hand: &Hand, _table: &TableView) -> bool {
+        self.insure
+    }
+}
@@ -0,0 +1,140 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    counting::CountingSystem,
+    error::Error,
+    profile::{read_json, write_json},
+};
+
+/// Results of the counting drills with one system.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SystemRecord {
+    pub system: CountingSystem,
+    pub answers: u32,
+    pub correct: u32,
+    /// Time spent answering, summed over every answer.
+    pub answer_seconds: f64,
+    pub deck_runs: u32,
+    pub deck_runs_correct: u32,
+    /// Fastest correct count down a deck.
+    pub best_deck_seconds: Option<f64>,
+}
+
+impl SystemRecord {
+    fn new(system: CountingSystem) -> Self {
+        Self {
+            system,
+            answers: 0,
+            correct: 0,
+            answer_seconds: 0.0,
+            deck_runs: 0,
+            deck_runs_correct: 0,
+            best_deck_seconds: None,
+        }
+    }
+
+    pub fn accuracy(&self) -> Option<f64> {
+        (self.answers > 0).then(|| self.correct as f64 / self.answers as f64)
+    }
+
+    pub fn seconds_per_answer(&self) -> Option<f64> {
+        (self.answers > 0).then(|| self.answer_seconds / self.answers as f64)
+    }
+}
+
+/// Counting drill results for every system practised, kept between
+/// sessions.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CountingProgress {
+    pub systems: Vec<SystemRecord>,
+}
+
+impl CountingProgress {
+    /// Loads the progress at `path`, starting afresh when there is none.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        Ok(read_json(path)?.unwrap_or_default())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        write_json(self, path)
+    }
+
+    pub fn get(&self, system: CountingSystem) -> Option<&SystemRecord> {
+        self.systems.iter().find(|r| r.system == system)
+    }
+
+    fn entry(&mut self, system: CountingSystem) -> &mut SystemRecord {
+        let i = match self.systems.iter().position(|r| r.system == system) {
+            Some(i) => i,
+            None => {
+                self.systems.push(SystemRecord::new(system));
+                self.systems.len() - 1
+            }
+        };
+        &mut self.systems[i]
+    }
+
+    pub fn record_answer(&mut self, system: CountingSystem, correct: bool, seconds: f64) {
+        let record = self.entry(system);
+        record.answers += 1;
+        record.correct += correct as u32;
+        record.answer_seconds += seconds;
+    }
+
+    pub fn record_deck_run(&mut self, system: CountingSystem, correct: bool, seconds: f64) {
+        let record = self.entry(system);
+        record.deck_runs += 1;
+        if correct {
+            record.deck_runs_correct += 1;
+            record.best_deck_seconds = Some(
+                record
+                    .best_deck_seconds
+                    .map_or(seconds, |best| best.min(seconds)),
+            );
+        }
+    }
+}
+
+/// Whether a count given as a whole number matches `count`. Fractional true
+/// counts accept either neighbour, so flooring and rounding both pass.
+pub fn is_correct(answer: i32, count: f64) -> bool {
+    (answer as f64 - count).abs() < 1.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case::exact(3, 3.0, true)]
+    #[case::floored(2, 2.6, true)]
+    #[case::rounded(3, 2.6, true)]
+    #[case::off_by_one(4, 3.0, false)]
+    #[case::negative(-2, -1.5, true)]
+    fn answers(#[case] answer: i32, #[case] count: f64, #[case] expected: bool) {
+        assert_eq!(is_correct(answer, count), expected);
+    }
+
+    #[rstest]
+    fn records_each_system_apart() {
+        let mut progress = CountingProgress::default();
+        progress.record_answer(CountingSystem::HiLo, true, 2.0);
+        progress.record_answer(CountingSystem::HiLo, false, 4.0);
+        progress.record_answer(CountingSystem::Ko, true, 1.0);
+        progress.record_deck_run(CountingSystem::HiLo, true, 40.0);
+        progress.record_deck_run(CountingSystem::HiLo, true, 35.0);
+        progress.record_deck_run(CountingSystem::HiLo, false, 20.0);
+
+        let hi_lo = progress.get(CountingSystem::HiLo).unwrap();
+        assert_eq!(hi_lo.accuracy(), Some(0.5));
+        assert_eq!(hi_lo.seconds_per_answer(), Some(3.0));
+        assert_eq!((hi_lo.deck_runs, hi_lo.deck_runs_correct), (3, 2));
+        assert_eq!(hi_lo.best_deck_seconds, Some(35.0));
+        assert_eq!(progress.get(CountingSystem::Ko).unwrap().answers, 1);
+    }
+}
@@ -0,0 +1,384 @@
+//! A card-counting seat: it sizes its bets by the true count and departs
+//! from basic strategy on the Illustrious 18 and Fab 4 index plays.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    action::Action,
+    command::Command,
+    controller::{BettingView, Controller, ControllerKind, TableView},
+    counting::CountingSystem,
+    dealer::Composition,
+    error::Error,
+    hand::Hand,
+    kelly::KellyAdvisor,
+    strategy::{basic_strategy, Play},
+    table::SeatConfig,
+};
+
+/// How many betting units to bet from a true count on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RampStep {
+    pub count: i32,
+    pub units: u32,
+}
+
+/// What a counting seat counts with and how it bets.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CounterConfig {
+    pub system: CountingSystem,
+    /// Chips in one betting unit.
+    pub unit: u32,
+    /// Steps in rising count order. Below the first step the seat bets one
+    /// unit.
+    pub ramp: Vec<RampStep>,
+    /// Whether to play the index plays and take insurance, or only vary
+    /// the bet.
+    pub deviations: bool,
+    /// Bets this percent of the Kelly bet for the count and bankroll
+    /// instead of following the ramp.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kelly: Option<u8>,
+}
+
+impl Default for CounterConfig {
+    /// Hi-Lo with a 1-12 spread, ramping up from a true count of +2.
+    fn default() -> Self {
+        let step = |count, units| RampStep { count, units };
+        Self {
+            system: CountingSystem::HiLo,
+            unit: 10,
+            ramp: vec![step(2, 2), step(3, 4), step(4, 8), step(5, 12)],
+            deviations: true,
+            kelly: None,
+        }
+    }
+}
+
+impl CounterConfig {
+    /// Names the first setting that cannot be played, with the reason.
+    pub fn validate(&self) -> Result<(), (&'static str, &'static str)> {
+        if self.unit == 0 {
+            return Err(("unit", "must be at least 1"));
+        }
+        if self.ramp.windows(2).any(|w| w[0].count >= w[1].count) {
+            return Err(("ramp", "must list each count once, in rising order"));
+        }
+        if matches!(self.kelly, Some(0 | 101..)) {
+            return Err(("kelly", "must be a percent between 1 and 100"));
+        }
+        Ok(())
+    }
+}
+
+/// A play that depends on the true count, for hard totals or pairs against
+/// one upcard.
+struct IndexPlay {
+    total: u8,
+    pair: bool,
+    upcard: u8,
+    index: i32,
+    at_or_above: Play,
+    below: Play,
+}
+
+const fn index(total: u8, upcard: u8, index: i32, at_or_above: Play, below: Play) -> IndexPlay {
+    IndexPlay {
+        total,
+        pair: false,
+        upcard,
+        index,
+        at_or_above,
+        below,
+    }
+}
+
+const fn pair_index(total: u8, upcard: u8, index: i32, at_or_above: Play) -> IndexPlay {
+    IndexPlay {
+        total,
+        pair: true,
+        upcard,
+        index,
+        at_or_above,
+        below: Play::Stand,
+    }
+}
+
+/// The Illustrious 18 for a multi-deck game where the dealer stands on
+/// soft 17, insurance aside.
+const ILLUSTRIOUS_18: [IndexPlay; 17] = [
+    index(16, 10, 0, Play::Stand, Play::Hit),
+    index(15, 10, 4, Play::Stand, Play::Hit),
+    pair_index(20, 5, 5, Play::Split),
+    pair_index(20, 6, 4, Play::Split),
+    index(10, 10, 4, Play::DoubleOrHit, Play::Hit),
+    index(12, 3, 2, Play::Stand, Play::Hit),
+    index(12, 2, 3, Play::Stand, Play::Hit),
+    index(11, 11, 1, Play::DoubleOrHit, Play::Hit),
+    index(9, 2, 1, Play::DoubleOrHit, Play::Hit),
+    index(10, 11, 4, Play::DoubleOrHit, Play::Hit),
+    index(9, 7, 3, Play::DoubleOrHit, Play::Hit),
+    index(16, 9, 5, Play::Stand, Play::Hit),
+    index(13, 2, -1, Play::Stand, Play::Hit),
+    index(12, 4, 0, Play::Stand, Play::Hit),
+    index(12, 5, -2, Play::Stand, Play::Hit),
+    index(12, 6, -1, Play::Stand, Play::Hit),
+    index(13, 3, -2, Play::Stand, Play::Hit),
+];
+
+/// The Fab 4 surrenders: hard total, upcard and the count to surrender
+/// from.
+const FAB_4: [(u8, u8, i32); 4] = [(14, 10, 3), (15, 10, 0), (15, 9, 2), (15, 11, 1)];
+
+/// Insurance pays from this true count on.
+const INSURANCE_INDEX: f64 = 3.0;
+
+/// Counts every card it sees to size its bets and adjust its play.
+#[derive(Debug)]
+pub struct CountingController {
+    config: CounterConfig,
+    /// Built from the table's rules on the first bet, when betting by
+    /// Kelly.
+    kelly: Option<KellyAdvisor>,
+}
+
+impl CountingController {
+    pub fn new(config: CounterConfig) -> Self {
+        Self {
+            config,
+            kelly: None,
+        }
+    }
+
+    /// The running count of the cards gone from the shoe, per deck left.
+    fn true_count(&self, unseen: &Composition, decks: u8) -> f64 {
+        let running = self.config.system.running_count_of(unseen, decks);
+        self.config
+            .system
+            .true_count(running, unseen.total() as usize)
+    }
+
+    /// Betting units for a true count, by the highest ramp step it reaches.
+    fn units(&self, true_count: f64) -> u32 {
+        self.config
+            .ramp
+            .iter()
+            .rev()
+            .find(|step| true_count >= step.count as f64)
+            .map_or(1, |step| step.units)
+    }
+
+    /// The index play for the hand at this count, or basic strategy when
+    /// the count does not change it. A Fab 4 hand below its index is played
+    /// without surrendering.
+    fn play(&self, hand: &Hand, table: &TableView, possible_actions: &[Action]) -> Action {
+        let basic = basic_strategy(hand, table.dealer_upcard, possible_actions, table.rules);
+        if !self.config.deviations || basic == Action::Split {
+            return basic;
+        }
+        let true_count = self.true_count(&table.shoe, table.decks);
+        let total = hand.sum_value();
+        let upcard = table.dealer_upcard.value(0);
+        let hard = !hand.is_soft();
+        let can_split = possible_actions.contains(&Action::Split);
+
+        let mut allowed = possible_actions.to_vec();
+        let fab_4 = FAB_4
+            .iter()
+            .find(|&&(t, u, _)| (t, u) == (total, upcard))
+            .filter(|_| hard && allowed.contains(&Action::Surrender));
+        match fab_4 {
+            Some(&(_, _, index)) if true_count >= index as f64 => return Action::Surrender,
+            Some(_) => allowed.retain(|&action| action != Action::Surrender),
+            None if basic == Action::Surrender => return basic,
+            None => (),
+        }
+        let index_play = ILLUSTRIOUS_18.iter().find(|play| {
+            (play.total, play.upcard) == (total, upcard) && if play.pair { can_split } else { hard }
+        });
+        match index_play {
+            Some(play) if true_count >= play.index as f64 => play.at_or_above.resolve(&allowed),
+            Some(play) => play.below.resolve(&allowed),
+            None => basic_strategy(hand, table.dealer_upcard, &allowed, table.rules),
+        }
+    }
+}
+
+impl Controller for CountingController {
+    fn choose_command(
+        &mut self,
+        hand: &Hand,
+        table: &TableView,
+        possible_actions: &[Action],
+    ) -> Result<Command, Error> {
+        Ok(Command::Action(self.play(hand, table, possible_actions)))
+    }
+
+    fn bet(&mut self, table: &BettingView) -> Result<Command, Error> {
+        let true_count = self.true_count(&table.shoe, table.decks);
+        let bet = match self.config.kelly {
+            Some(percent) => {
+                let fraction = percent as f64 / 100.0;
+                let kelly = self
+                    .kelly
+                    .get_or_insert_with(|| KellyAdvisor::new(table.rules, fraction));
+                kelly.advise(table.bankroll, true_count).fractional
+            }
+            None => self.units(true_count).saturating_mul(self.config.unit),
+        };
+        Ok(Command::Bet(bet))
+    }
+
+    fn insure(&mut self, _hand: &Hand, table: &TableView) -> bool {
+        self.config.deviations && self.true_count(&table.shoe, table.decks) >= INSURANCE_INDEX
+    }
+
+    fn kind(&self) -> Option<ControllerKind> {
+        Some(ControllerKind::Counter)
+    }
+
+    fn configure(&self, seat: &mut SeatConfig) {
+        seat.counter = Some(self.config.clone());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{card::Card, dealer::card, rules::TableRules, stats::Statistics, table::Limits};
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+    use strum::IntoEnumIterator;
+
+    /// A six-deck shoe short of enough low or high cards to put the Hi-Lo
+    /// true count just past `count`.
+    fn shoe_at(count: i32) -> Composition {
+        let removed: Vec<Card> = (0..count.unsigned_abs() as usize * 6)
+            .map(|n| match count {
+                ..0 => card(9, 'h'),
+                _ => card(1 + n % 5, 'h'),
+            })
+            .collect();
+        Composition::full(6).without(&removed)
+    }
+
+    fn hand(codes: &[&str]) -> Hand {
+        let mut hand = Hand::new(20, None);
+        for code in codes {
+            hand.add_card_to_hand(code.parse().unwrap());
+        }
+        hand
+    }
+
+    fn counter() -> CountingController {
+        CountingController::new(CounterConfig::default())
+    }
+
+    #[rstest]
+    #[case::negative(-2, 10)]
+    #[case::below_the_ramp(1, 10)]
+    #[case::first_step(2, 20)]
+    #[case::between_steps(3, 40)]
+    #[case::top(5, 120)]
+    #[case::past_the_top(9, 120)]
+    fn bets_up_the_ramp(#[case] count: i32, #[case] bet: u32) {
+        assert_eq!(bet_at(&mut counter(), count), bet);
+    }
+
+    fn bet_at(counter: &mut CountingController, count: i32) -> u32 {
+        let table = BettingView {
+            rules: &TableRules::default(),
+            limits: &Limits::default(),
+            shoe: shoe_at(count),
+            decks: 6,
+            bankroll: 10_000,
+            bet: 25,
+            counting: None,
+            stats: &Statistics::default(),
+        };
+        match counter.bet(&table).unwrap() {
+            Command::Bet(bet) => bet,
+            command => panic!("bet with {:?}", command),
+        }
+    }
+
+    #[rstest]
+    fn bets_a_fraction_of_kelly() {
+        let mut counter = CountingController::new(CounterConfig {
+            kelly: Some(50),
+            ..CounterConfig::default()
+        });
+        let bets: Vec<u32> = [-2, 0, 3, 5]
+            .iter()
+            .map(|&count| bet_at(&mut counter, count))
+            .collect();
+        assert_eq!(&bets[..2], &[0, 0]);
+        assert!(0 < bets[2] && bets[2] < bets[3], "{:?}", bets);
+    }
+
+    #[rstest]
+    #[case::stand_16_v_10(&["10h", "4d", "2s"], "10c", 0, Action::Stand)]
+    #[case::hit_16_v_10(&["10h", "4d", "2s"], "10c", -1, Action::Hit)]
+    #[case::surrender_16_v_10(&["10h", "6d"], "10c", -1, Action::Surrender)]
+    #[case::fab_4_surrender(&["10h", "4d"], "10c", 3, Action::Surrender)]
+    #[case::fab_4_below(&["10h", "4d"], "10c", 2, Action::Hit)]
+    #[case::fab_4_keeps_15_v_10(&["9h", "6d"], "10c", -1, Action::Hit)]
+    #[case::stand_15_v_10(&["9h", "4d", "2s"], "10c", 4, Action::Stand)]
+    #[case::split_tens(&["10h", "10d"], "6c", 4, Action::Split)]
+    #[case::stand_on_tens(&["10h", "10d"], "6c", 3, Action::Stand)]
+    #[case::double_11_v_ace(&["6h", "5d"], "Ac", 1, Action::DoubleDown)]
+    #[case::hit_12_v_4(&["10h", "2d"], "4c", -1, Action::Hit)]
+    #[case::stand_12_v_3(&["10h", "2d"], "3c", 2, Action::Stand)]
+    #[case::basic_split(&["8h", "8d"], "10c", 5, Action::Split)]
+    #[case::basic_otherwise(&["Ah", "7d"], "9c", 5, Action::Hit)]
+    fn plays_the_index_plays(
+        #[case] cards: &[&str],
+        #[case] upcard: &str,
+        #[case] count: i32,
+        #[case] action: Action,
+    ) {
+        let hand = hand(cards);
+        let upcard: Card = upcard.parse().unwrap();
+        let rules = TableRules::default();
+        let table = TableView {
+            rules: &rules,
+            dealer_upcard: &upcard,
+            decisions: &[],
+            count: None,
+            shoe: shoe_at(count),
+            decks: 6,
+            stats: &Statistics::default(),
+            train: false,
+        };
+        let possible_actions: Vec<Action> = Action::iter()
+            .filter(|a| a.can_execute(&hand, 1, 1000, &rules))
+            .collect();
+        assert_eq!(
+            counter()
+                .choose_command(&hand, &table, &possible_actions)
+                .unwrap(),
+            Command::Action(action)
+        );
+    }
+
+    #[rstest]
+    #[case::at_the_index(3, true)]
+    #[case::below_it(2, false)]
+    fn insures_from_plus_three(#[case] count: i32, #[case] insure: bool) {
+        let upcard: Card = "Ac".parse().unwrap();
+        let rules = TableRules::default();
+        let table = TableView {
+            rules: &rules,
+            dealer_upcard: &upcard,
+            decisions: &[],
+            count: None,
+            shoe: shoe_at(count),
+            decks: 6,
+            stats: &Statistics::default(),
+            train: false,
+        };
+        assert_eq!(counter().insure(&hand(&["10h", "9d"]), &table), insure);
+    }
+}
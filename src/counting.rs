@@ -0,0 +1,145 @@
+use std::fmt;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::Card,
+    dealer::{card, Composition},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+pub enum CountingSystem {
+    #[default]
+    HiLo,
+    /// Knock-Out, unbalanced: a full deck counts +4.
+    Ko,
+    HiOptI,
+    OmegaII,
+    ZenCount,
+}
+
+impl CountingSystem {
+    pub fn tag(&self, card: &Card) -> i32 {
+        let value = card.value(0);
+        match self {
+            CountingSystem::HiLo => match value {
+                2..=6 => 1,
+                7..=9 => 0,
+                _ => -1,
+            },
+            CountingSystem::Ko => match value {
+                2..=7 => 1,
+                8 | 9 => 0,
+                _ => -1,
+            },
+            CountingSystem::HiOptI => match value {
+                3..=6 => 1,
+                10 => -1,
+                _ => 0,
+            },
+            CountingSystem::OmegaII => match value {
+                2 | 3 | 7 => 1,
+                4..=6 => 2,
+                9 => -1,
+                10 => -2,
+                _ => 0,
+            },
+            CountingSystem::ZenCount => match value {
+                2 | 3 | 7 => 1,
+                4..=6 => 2,
+                8 | 9 => 0,
+                10 => -2,
+                _ => -1,
+            },
+        }
+    }
+
+    pub fn running_count<'a>(&self, cards: impl IntoIterator<Item = &'a Card>) -> i32 {
+        cards.into_iter().map(|card| self.tag(card)).sum()
+    }
+
+    /// The running count of every card gone from a shoe of `decks` decks
+    /// that still holds `unseen`.
+    pub fn running_count_of(&self, unseen: &Composition, decks: u8) -> i32 {
+        let full = Composition::full(decks).counts();
+        let unseen = unseen.counts();
+        (0..10)
+            .map(|i| self.tag(&card(i, 'h')) * (full[i] as i32 - unseen[i] as i32))
+            .sum()
+    }
+
+    /// The running count per deck still to be dealt.
+    pub fn true_count(&self, running_count: i32, cards_remaining: usize) -> f64 {
+        let decks = (cards_remaining as f64 / 52.0).max(0.5);
+        running_count as f64 / decks
+    }
+}
+
+impl fmt::Display for CountingSystem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            CountingSystem::HiLo => "Hi-Lo",
+            CountingSystem::Ko => "KO",
+            CountingSystem::HiOptI => "Hi-Opt I",
+            CountingSystem::OmegaII => "Omega II",
+            CountingSystem::ZenCount => "Zen Count",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::deck::Deck;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case::low_cards(&["2h", "6s"], 2)]
+    #[case::neutral_cards(&["7h", "9s"], 0)]
+    #[case::high_cards(&["10h", "As", "Kd"], -3)]
+    #[case::mixed(&["5h", "Qs", "3d", "8c"], 1)]
+    fn hi_lo_running_count(#[case] codes: &[&str], #[case] expected: i32) {
+        let cards: Vec<Card> = codes.iter().map(|c| c.parse().unwrap()).collect();
+        assert_eq!(CountingSystem::HiLo.running_count(&cards), expected);
+    }
+
+    #[rstest]
+    #[case::hi_lo(CountingSystem::HiLo, 0)]
+    #[case::ko(CountingSystem::Ko, 4)]
+    #[case::hi_opt_i(CountingSystem::HiOptI, 0)]
+    #[case::omega_ii(CountingSystem::OmegaII, 0)]
+    #[case::zen_count(CountingSystem::ZenCount, 0)]
+    fn full_deck_count(#[case] system: CountingSystem, #[case] expected: i32) {
+        let mut deck = Deck::with_seed(1);
+        deck.shuffle();
+        let cards: Vec<Card> = (0..52).map(|_| deck.deal_card()).collect();
+        assert_eq!(system.running_count(&cards), expected);
+    }
+
+    #[rstest]
+    fn running_count_of_the_cards_gone() {
+        let dealt: Vec<Card> = ["2h", "5s", "Kd", "7c", "3h"]
+            .iter()
+            .map(|c| c.parse().unwrap())
+            .collect();
+        let unseen = Composition::full(2).without(&dealt);
+        for system in [CountingSystem::HiLo, CountingSystem::OmegaII] {
+            let expected = system.running_count(&dealt);
+            assert_eq!(system.running_count_of(&unseen, 2), expected);
+        }
+    }
+
+    #[rstest]
+    #[case::three_decks_left(6, 156, 2.0)]
+    #[case::half_a_deck_left(3, 26, 6.0)]
+    #[case::nearly_empty_shoe(3, 5, 6.0)]
+    fn true_count(#[case] running: i32, #[case] remaining: usize, #[case] expected: f64) {
+        assert_eq!(
+            CountingSystem::HiLo.true_count(running, remaining),
+            expected
+        );
+    }
+}
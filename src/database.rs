@@ -0,0 +1,292 @@
+//! Long-term round store in a local SQLite file, for ad-hoc SQL such as the
+//! win rate of hitting hard 16 against a 10:
+//!
+//! ```sql
+//! SELECT avg(h.result IN ('PlayerWon', 'Blackjack'))
+//! FROM decisions d
+//! JOIN hands h ON h.round_id = d.round_id AND h.player = d.player AND h.hand = d.hand
+//! JOIN rounds r ON r.id = d.round_id
+//! WHERE d.total = 16 AND NOT d.soft AND d.action = 'Hit' AND r.dealer_upcard_value = 10;
+//! ```
+
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rusqlite::{params, Connection};
+
+use crate::{card::Card, error::Error, hand::Hand, history::RoundRecord};
+
+/// Bumped, and stored as `PRAGMA user_version`, whenever the schema changes.
+pub const SCHEMA_VERSION: i64 = 1;
+
+/// Every table the store writes. Card lists are space separated card codes
+/// such as `"Ah 10d"`, and enums are stored by their variant name.
+pub const SCHEMA: &str = "
+-- One row per session that wrote to the database.
+CREATE TABLE IF NOT EXISTS sessions (
+    id          INTEGER PRIMARY KEY,
+    started_at  INTEGER NOT NULL,   -- seconds since the Unix epoch
+    rules       TEXT NOT NULL       -- the table rules as JSON
+);
+
+-- One row per played round.
+CREATE TABLE IF NOT EXISTS rounds (
+    id                  INTEGER PRIMARY KEY,
+    session_id          INTEGER NOT NULL REFERENCES sessions(id),
+    round               INTEGER NOT NULL,   -- round number within the session
+    decks               INTEGER NOT NULL,
+    shoe_seed           INTEGER NOT NULL,   -- the u64 seed, bit cast to i64
+    shoe_position       INTEGER NOT NULL,   -- cards dealt from the shoe before the round
+    dealer_upcard       TEXT,
+    dealer_upcard_value INTEGER,            -- 2 to 10, 11 for an Ace
+    dealer_cards        TEXT NOT NULL,
+    dealer_total        INTEGER NOT NULL
+);
+
+-- One row per settled player hand, split hands included.
+CREATE TABLE IF NOT EXISTS hands (
+    id        INTEGER PRIMARY KEY,
+    round_id  INTEGER NOT NULL REFERENCES rounds(id),
+    player    TEXT NOT NULL,
+    hand      INTEGER NOT NULL,   -- 0 for the first hand, then one per split
+    cards     TEXT NOT NULL,
+    total     INTEGER NOT NULL,
+    natural   INTEGER NOT NULL,   -- 1 for a two card 21 that was not split
+    bet       INTEGER NOT NULL,   -- after doubling
+    result    TEXT NOT NULL,      -- PlayerWon, DealerWon, Tie, Blackjack or Surrendered
+    payout    INTEGER NOT NULL,   -- chips paid back, the bet included
+    net       INTEGER NOT NULL    -- payout - bet
+);
+
+-- One row per decision, the dealer's included, in the order they were taken.
+CREATE TABLE IF NOT EXISTS decisions (
+    id        INTEGER PRIMARY KEY,
+    round_id  INTEGER NOT NULL REFERENCES rounds(id),
+    seq       INTEGER NOT NULL,
+    player    TEXT NOT NULL,
+    hand      INTEGER NOT NULL,
+    total     INTEGER NOT NULL,   -- hand total when deciding
+    soft      INTEGER NOT NULL,
+    action    TEXT NOT NULL       -- Hit, Stand, DoubleDown, Split or Surrender
+);
+";
+
+/// Writes every recorded round of a session to a SQLite database.
+#[derive(Debug)]
+pub struct Database {
+    path: PathBuf,
+    connection: Connection,
+    session_id: Option<i64>,
+}
+
+impl Database {
+    /// Opens or creates the database at `path`, creating missing tables.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let error = |source| Error::Database {
+            path: path.into(),
+            source,
+        };
+        let connection = Connection::open(path).map_err(error)?;
+        let version: i64 = connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(error)?;
+        if version > SCHEMA_VERSION {
+            return Err(Error::UnsupportedVersion {
+                path: path.into(),
+                version,
+                supported: SCHEMA_VERSION,
+            });
+        }
+        connection.execute_batch(SCHEMA).map_err(error)?;
+        connection
+            .pragma_update(None, "user_version", SCHEMA_VERSION)
+            .map_err(error)?;
+        Ok(Self {
+            path: path.into(),
+            connection,
+            session_id: None,
+        })
+    }
+
+    pub fn record(&mut self, round: &RoundRecord) -> Result<(), Error> {
+        self.insert(round).map_err(|source| Error::Database {
+            path: self.path.clone(),
+            source,
+        })
+    }
+
+    fn insert(&mut self, round: &RoundRecord) -> rusqlite::Result<()> {
+        let transaction = self.connection.transaction()?;
+        let session_id = match self.session_id {
+            Some(id) => id,
+            None => {
+                let started_at = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs() as i64);
+                let rules = serde_json::to_string(&round.rules).unwrap_or_default();
+                transaction.execute(
+                    "INSERT INTO sessions (started_at, rules) VALUES (?1, ?2)",
+                    params![started_at, rules],
+                )?;
+                transaction.last_insert_rowid()
+            }
+        };
+
+        let upcard = round.dealer_cards.first();
+        transaction.execute(
+            "INSERT INTO rounds (session_id, round, decks, shoe_seed, shoe_position,
+                dealer_upcard, dealer_upcard_value, dealer_cards, dealer_total)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                session_id,
+                round.round,
+                round.decks,
+                round.shoe_seed as i64,
+                round.shoe_position as i64,
+                upcard.map(Card::code),
+                upcard.map(|card| card.value(0)),
+                codes(&round.dealer_cards),
+                total(&round.dealer_cards),
+            ],
+        )?;
+        let round_id = transaction.last_insert_rowid();
+
+        for payout in &round.payouts {
+            transaction.execute(
+                "INSERT INTO hands (round_id, player, hand, cards, total, natural, bet, result,
+                    payout, net)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    round_id,
+                    payout.player,
+                    payout.hand,
+                    codes(&payout.cards),
+                    payout.total,
+                    payout.natural,
+                    payout.bet,
+                    format!("{:?}", payout.result),
+                    payout.payout,
+                    payout.payout as i64 - payout.bet as i64,
+                ],
+            )?;
+        }
+        for (seq, decision) in round.decisions.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO decisions (round_id, seq, player, hand, total, soft, action)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    round_id,
+                    seq,
+                    decision.player,
+                    decision.hand,
+                    decision.total,
+                    decision.soft,
+                    format!("{:?}", decision.action),
+                ],
+            )?;
+        }
+        transaction.commit()?;
+        self.session_id = Some(session_id);
+        Ok(())
+    }
+}
+
+fn codes(cards: &[Card]) -> String {
+    cards.iter().map(Card::code).collect::<Vec<_>>().join(" ")
+}
+
+fn total(cards: &[Card]) -> u8 {
+    let mut hand = Hand::new(0, None);
+    for card in cards {
+        hand.add_card_to_hand(card.clone());
+    }
+    hand.sum_value()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        action::Action,
+        history::{DecisionRecord, PayoutRecord, SeatRecord},
+        printer::Result as RoundResult,
+        rules::TableRules,
+    };
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    fn cards(codes: &[&str]) -> Vec<Card> {
+        codes.iter().map(|code| code.parse().unwrap()).collect()
+    }
+
+    fn round(number: u32, hit_card: &str, result: RoundResult, payout: u32) -> RoundRecord {
+        let mut player_cards = cards(&["10h", "6s"]);
+        player_cards.extend(cards(&[hit_card]));
+        RoundRecord {
+            round: number,
+            shoe_seed: u64::MAX,
+            shoe_position: 0,
+            decks: 6,
+            rules: TableRules::default(),
+            seats: vec![SeatRecord {
+                name: "Ana".into(),
+                bankroll: 100,
+                bet: 10,
+                insurance: 0,
+            }],
+            cards: vec![],
+            dealer_cards: cards(&["Kd", "8c"]),
+            decisions: vec![DecisionRecord {
+                player: "Ana".into(),
+                hand: 0,
+                action: Action::Hit,
+                total: 16,
+                soft: false,
+            }],
+            payouts: vec![PayoutRecord {
+                player: "Ana".into(),
+                hand: 0,
+                bet: 10,
+                result,
+                payout,
+                cards: player_cards,
+                total: 0,
+                natural: false,
+            }],
+        }
+    }
+
+    #[rstest]
+    fn answers_the_documented_query() {
+        let mut database = Database::open(Path::new(":memory:")).unwrap();
+        database
+            .record(&round(1, "4c", RoundResult::PlayerWon, 20))
+            .unwrap();
+        database
+            .record(&round(2, "9c", RoundResult::DealerWon, 0))
+            .unwrap();
+
+        let query = "SELECT avg(h.result IN ('PlayerWon', 'Blackjack'))
+            FROM decisions d
+            JOIN hands h ON h.round_id = d.round_id AND h.player = d.player AND h.hand = d.hand
+            JOIN rounds r ON r.id = d.round_id
+            WHERE d.total = 16 AND NOT d.soft AND d.action = 'Hit' AND r.dealer_upcard_value = 10";
+        let win_rate: f64 = database
+            .connection
+            .query_row(query, [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(win_rate, 0.5);
+
+        let (sessions, seed): (i64, i64) = database
+            .connection
+            .query_row(
+                "SELECT count(DISTINCT session_id), max(shoe_seed) FROM rounds",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((sessions, seed as u64), (1, u64::MAX));
+    }
+}
@@ -0,0 +1,346 @@
+//! Where the dealer ends up from each upcard, computed exactly from the
+//! cards that can still come out of the shoe.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::{card::Card, rules::TableRules};
+
+pub(crate) const ACE: usize = 0;
+pub(crate) const TEN: usize = 9;
+
+/// Cards left in the shoe by point value: index 0 holds the aces, then the
+/// twos to nines, and index 9 every ten-valued card.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Composition {
+    counts: [u16; 10],
+}
+
+impl Composition {
+    pub fn full(decks: u8) -> Self {
+        let decks = decks as u16;
+        let mut counts = [4 * decks; 10];
+        counts[TEN] = 16 * decks;
+        Self { counts }
+    }
+
+    /// The shoe after `cards` have been dealt from it.
+    pub fn without<'a>(mut self, cards: impl IntoIterator<Item = &'a Card>) -> Self {
+        for card in cards {
+            let count = &mut self.counts[index(card)];
+            *count = count.saturating_sub(1);
+        }
+        self
+    }
+
+    /// Puts a card back, such as a hole card the players have not seen.
+    pub fn with(mut self, card: &Card) -> Self {
+        self.counts[index(card)] += 1;
+        self
+    }
+
+    pub fn total(&self) -> u32 {
+        self.counts.iter().map(|&n| n as u32).sum()
+    }
+
+    /// Cards of each point value, indexed like [`Self::full`]'s.
+    pub(crate) fn counts(&self) -> [u16; 10] {
+        self.counts
+    }
+}
+
+/// What the next card is drawn from: the cards left in a real shoe, or an
+/// infinite deck where every draw has the odds of a fresh one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Shoe {
+    Infinite,
+    Finite(Composition),
+}
+
+impl Shoe {
+    /// The chance of drawing each point value next, indexed like
+    /// [`Composition`].
+    fn probabilities(&self) -> [f64; 10] {
+        match self {
+            Shoe::Infinite => {
+                let mut probabilities = [1.0 / 13.0; 10];
+                probabilities[TEN] = 4.0 / 13.0;
+                probabilities
+            }
+            Shoe::Finite(composition) => {
+                let total = composition.total() as f64;
+                composition.counts.map(|n| n as f64 / total)
+            }
+        }
+    }
+
+    /// Every point value that can come next, with its chance.
+    pub(crate) fn draws(&self) -> impl Iterator<Item = (usize, f64)> {
+        let probabilities = self.probabilities();
+        (0..10)
+            .map(move |i| (i, probabilities[i]))
+            .filter(|&(_, p)| p > 0.0)
+    }
+
+    pub(crate) fn remove(self, i: usize) -> Self {
+        match self {
+            Shoe::Infinite => Shoe::Infinite,
+            Shoe::Finite(mut composition) => {
+                composition.counts[i] -= 1;
+                Shoe::Finite(composition)
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        matches!(self, Shoe::Finite(composition) if composition.total() == 0)
+    }
+}
+
+pub(crate) fn index(card: &Card) -> usize {
+    match card.value(0) {
+        11 => ACE,
+        value => value as usize - 1,
+    }
+}
+
+/// A card worth the point value of index `i`, of the suit with code `suit`.
+pub(crate) fn card(i: usize, suit: char) -> Card {
+    const RANKS: [&str; 10] = ["A", "2", "3", "4", "5", "6", "7", "8", "9", "10"];
+    // Spades get a king for a ten, so a hand of two tens is never a pair.
+    let rank = if i == TEN && suit == 's' {
+        "K"
+    } else {
+        RANKS[i]
+    };
+    format!("{}{}", rank, suit)
+        .parse()
+        .expect("every index has a card")
+}
+
+/// A hand total with aces counted as one, and whether one of them can
+/// still count as eleven.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct Total {
+    hard: u8,
+    ace: bool,
+}
+
+impl Total {
+    pub(crate) fn of(indexes: impl IntoIterator<Item = usize>) -> Self {
+        let empty = Self {
+            hard: 0,
+            ace: false,
+        };
+        indexes.into_iter().fold(empty, Self::add)
+    }
+
+    pub(crate) fn add(self, i: usize) -> Self {
+        Self {
+            hard: self.hard + i as u8 + 1,
+            ace: self.ace || i == ACE,
+        }
+    }
+
+    pub(crate) fn value(&self) -> u8 {
+        if self.is_soft() {
+            self.hard + 10
+        } else {
+            self.hard
+        }
+    }
+
+    pub(crate) fn is_soft(&self) -> bool {
+        self.ace && self.hard + 10 <= 21
+    }
+}
+
+/// The chances of the dealer's final result. When the dealer peeks, a
+/// natural has already been ruled out and `natural` is zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct DealerOutcomes {
+    /// Final totals of 17 to 21.
+    pub totals: [f64; 5],
+    pub natural: f64,
+    pub bust: f64,
+}
+
+impl DealerOutcomes {
+    fn add_scaled(&mut self, other: &DealerOutcomes, p: f64) {
+        for (total, q) in self.totals.iter_mut().zip(other.totals) {
+            *total += p * q;
+        }
+        self.natural += p * other.natural;
+        self.bust += p * other.bust;
+    }
+
+    fn scale(&mut self, factor: f64) {
+        for total in &mut self.totals {
+            *total *= factor;
+        }
+        self.natural *= factor;
+        self.bust *= factor;
+    }
+
+    /// Every outcome in table order: 17 to 21, natural, bust.
+    pub fn columns(&self) -> [f64; 7] {
+        let [t17, t18, t19, t20, t21] = self.totals;
+        [t17, t18, t19, t20, t21, self.natural, self.bust]
+    }
+}
+
+/// Memoized dealer probabilities for one ruleset, shared across upcards
+/// and shoes so repeated lookups cost nothing.
+#[derive(Debug)]
+pub struct DealerTable {
+    rules: TableRules,
+    outcomes: HashMap<(usize, Shoe), DealerOutcomes>,
+    draws: HashMap<(Shoe, Total), DealerOutcomes>,
+}
+
+impl DealerTable {
+    pub fn new(rules: &TableRules) -> Self {
+        Self {
+            rules: rules.clone(),
+            outcomes: HashMap::new(),
+            draws: HashMap::new(),
+        }
+    }
+
+    /// One row per upcard, 2 to ace, for a full shoe of `decks` decks or an
+    /// infinite deck.
+    pub fn table(&mut self, decks: Option<u8>) -> Vec<(u8, DealerOutcomes)> {
+        (2..=11u8)
+            .map(|upcard| {
+                let i = if upcard == 11 {
+                    ACE
+                } else {
+                    upcard as usize - 1
+                };
+                let shoe = match decks {
+                    Some(decks) => {
+                        let mut composition = Composition::full(decks);
+                        composition.counts[i] -= 1;
+                        Shoe::Finite(composition)
+                    }
+                    None => Shoe::Infinite,
+                };
+                (upcard, self.outcomes_for(i, shoe))
+            })
+            .collect()
+    }
+
+    /// The dealer's final results with the upcard of index `upcard`
+    /// showing, drawing the hole card and the rest from `shoe`, which no
+    /// longer holds the upcard.
+    pub(crate) fn outcomes_for(&mut self, upcard: usize, shoe: Shoe) -> DealerOutcomes {
+        if let Some(outcomes) = self.outcomes.get(&(upcard, shoe)) {
+            return *outcomes;
+        }
+        let mut outcomes = DealerOutcomes::default();
+        let mut weight = 0.0;
+        for (i, p) in shoe.draws() {
+            let natural = matches!((upcard, i), (ACE, TEN) | (TEN, ACE));
+            if natural && self.rules.dealer_peeks {
+                continue;
+            }
+            weight += p;
+            if natural {
+                outcomes.natural += p;
+                continue;
+            }
+            let drawn = self.draw(shoe.remove(i), Total::of([upcard, i]));
+            outcomes.add_scaled(&drawn, p);
+        }
+        outcomes.scale(1.0 / weight);
+        self.outcomes.insert((upcard, shoe), outcomes);
+        outcomes
+    }
+
+    fn draw(&mut self, shoe: Shoe, total: Total) -> DealerOutcomes {
+        let mut outcomes = DealerOutcomes::default();
+        let value = total.value();
+        if value > 21 {
+            outcomes.bust = 1.0;
+            return outcomes;
+        }
+        let hits_soft_17 = value == 17 && total.is_soft() && self.rules.dealer_hits_soft_17;
+        if value >= 17 && !hits_soft_17 || shoe.is_empty() {
+            outcomes.totals[value.max(17) as usize - 17] = 1.0;
+            return outcomes;
+        }
+        if let Some(outcomes) = self.draws.get(&(shoe, total)) {
+            return *outcomes;
+        }
+        for (i, p) in shoe.draws() {
+            let drawn = self.draw(shoe.remove(i), total.add(i));
+            outcomes.add_scaled(&drawn, p);
+        }
+        self.draws.insert((shoe, total), outcomes);
+        outcomes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    fn bust(rules: &TableRules, upcard: u8, decks: Option<u8>) -> f64 {
+        let table = DealerTable::new(rules).table(decks);
+        table[upcard as usize - 2].1.bust
+    }
+
+    #[rstest]
+    fn composition_removes_dealt_cards() {
+        let cards: Vec<Card> = ["Ah", "Kd", "10s", "5c"]
+            .iter()
+            .map(|code| code.parse().unwrap())
+            .collect();
+        let shoe = Composition::full(1).without(&cards);
+        assert_eq!(shoe.total(), 48);
+        assert_eq!((shoe.counts[ACE], shoe.counts[TEN]), (3, 14));
+    }
+
+    #[rstest]
+    #[case::infinite(None)]
+    #[case::single_deck(Some(1))]
+    #[case::six_decks(Some(6))]
+    fn every_row_adds_up_to_one(#[case] decks: Option<u8>) {
+        for (upcard, outcomes) in DealerTable::new(&TableRules::default()).table(decks) {
+            let sum: f64 = outcomes.columns().iter().sum();
+            assert!((sum - 1.0).abs() < 1e-9, "{}: {}", upcard, sum);
+        }
+    }
+
+    #[rstest]
+    fn infinite_deck_matches_published_bust_rates() {
+        let rules = TableRules::default();
+        assert!((bust(&rules, 6, None) - 0.4232).abs() < 0.0005);
+        assert!((bust(&rules, 10, None) - 0.2298).abs() < 0.0005);
+    }
+
+    #[rstest]
+    fn hitting_soft_17_busts_more_often() {
+        let h17 = TableRules {
+            dealer_hits_soft_17: true,
+            ..TableRules::default()
+        };
+        let s17 = TableRules::default();
+        assert!(bust(&h17, 6, Some(6)) > bust(&s17, 6, Some(6)));
+        assert_eq!(bust(&h17, 10, Some(6)), bust(&s17, 10, Some(6)));
+    }
+
+    #[rstest]
+    fn natural_only_shows_without_peek() {
+        let no_peek = TableRules {
+            dealer_peeks: false,
+            ..TableRules::default()
+        };
+        let ace = |rules: &TableRules| DealerTable::new(rules).table(None)[9].1.natural;
+        assert_eq!(ace(&TableRules::default()), 0.0);
+        assert!((ace(&no_peek) - 4.0 / 13.0).abs() < 1e-12);
+    }
+}
@@ -1,48 +1,242 @@
-use std::collections::VecDeque;
-
 use crate::card::{Card, Figure, Suit};
-use rand::{seq::SliceRandom, thread_rng};
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 
+/// A shoe frozen mid-way: the cards dealt since the last shuffle, the ones
+/// still to come and the generator that seeds the next shuffles.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShoeState {
+    pub shoe_seed: u64,
+    pub discards: Vec<Card>,
+    pub cards: Vec<Card>,
+    pub rng: ChaCha8Rng,
+}
+
 #[derive(Debug)]
 pub struct Deck {
     cards: Vec<Card>,
-    shuffled_cards: VecDeque<Card>,
+    shuffled_cards: Vec<Card>,
+    position: usize,
+    shoe_seed: u64,
+    rng: ChaCha8Rng,
+}
+
+impl Default for Deck {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Deck {
     pub fn new() -> Self {
-        let mut cards = Vec::with_capacity(52);
-        for s in Suit::iter() {
-            for f in Figure::iter() {
-                cards.push(Card::new(s, f));
-            }
+        Self::with_rng(ChaCha8Rng::from_entropy())
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    fn with_rng(rng: ChaCha8Rng) -> Self {
+        let mut deck = Self {
+            cards: vec![],
+            shuffled_cards: vec![],
+            position: 0,
+            shoe_seed: 0,
+            rng,
+        };
+        deck.set_decks(1);
+        deck
+    }
+
+    /// Rebuilds a shoe of `decks` decks exactly as it was saved.
+    pub fn from_state(decks: u8, state: ShoeState) -> Self {
+        let mut deck = Self::with_rng(state.rng);
+        deck.set_decks(decks);
+        deck.position = state.discards.len();
+        deck.shuffled_cards = state.discards;
+        deck.shuffled_cards.extend(state.cards);
+        deck.shoe_seed = state.shoe_seed;
+        deck
+    }
+
+    pub fn state(&self) -> ShoeState {
+        ShoeState {
+            shoe_seed: self.shoe_seed,
+            discards: self.dealt_since(0).to_vec(),
+            cards: self.shuffled_cards[self.position..].to_vec(),
+            rng: self.rng.clone(),
         }
+    }
 
-        Self {
-            cards,
-            shuffled_cards: VecDeque::new(),
+    /// Builds the shoe from `decks` full decks. Takes effect on the next
+    /// shuffle.
+    pub fn set_decks(&mut self, decks: u8) {
+        self.cards = Vec::with_capacity(52 * decks as usize);
+        for _ in 0..decks {
+            for s in Suit::iter() {
+                for f in Figure::iter() {
+                    self.cards.push(Card::new(s, f));
+                }
+            }
         }
     }
 
+    pub fn decks(&self) -> u8 {
+        (self.cards.len() / 52) as u8
+    }
+
+    /// Whether the cut card has been reached, given the fraction of the
+    /// shoe dealt before reshuffling.
+    pub fn needs_shuffle(&self, penetration: f64) -> bool {
+        self.shuffled_cards.is_empty()
+            || self.position >= (penetration * self.shuffled_cards.len() as f64) as usize
+    }
+
     pub fn shuffle(&mut self) {
-        let mut rng = thread_rng();
-        self.cards.shuffle(&mut rng);
+        let shoe_seed = self.rng.gen();
+        self.shuffle_with_seed(shoe_seed);
+    }
+
+    /// Shuffles a fresh shoe whose order depends only on `shoe_seed`, so a
+    /// recorded seed is enough to rebuild the exact same shoe.
+    pub fn shuffle_with_seed(&mut self, shoe_seed: u64) {
+        let mut shoe_rng = ChaCha8Rng::seed_from_u64(shoe_seed);
+        self.shuffled_cards = self.cards.clone();
+        self.shuffled_cards.shuffle(&mut shoe_rng);
+        self.shoe_seed = shoe_seed;
+        self.position = 0;
+    }
+
+    /// Deals the next card. A shoe that runs out mid-round is topped up
+    /// with another full shoe whose order is derived from `shoe_seed`.
+    pub fn deal_card(&mut self) -> Card {
+        if self.position == self.shuffled_cards.len() {
+            let seed = self.shoe_seed.wrapping_add(self.position as u64);
+            let mut shoe_rng = ChaCha8Rng::seed_from_u64(seed);
+            let mut top_up = self.cards.clone();
+            top_up.shuffle(&mut shoe_rng);
+            self.shuffled_cards.extend(top_up);
+        }
+        self.position += 1;
+        self.shuffled_cards[self.position - 1].clone()
+    }
+
+    pub fn shoe_seed(&self) -> u64 {
+        self.shoe_seed
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Cards left before the shoe has to be topped up.
+    pub fn remaining(&self) -> usize {
+        self.shuffled_cards.len() - self.position
+    }
+
+    /// Cards dealt since `position`, in the order they left the shoe.
+    pub fn dealt_since(&self, position: usize) -> &[Card] {
+        &self.shuffled_cards[position..self.position]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    fn codes(cards: &[Card]) -> Vec<String> {
+        cards.iter().map(Card::code).collect()
+    }
+
+    #[rstest]
+    fn same_shoe_seed_deals_same_cards() {
+        let mut first = Deck::with_seed(1);
+        let mut second = Deck::with_seed(2);
+        first.shuffle_with_seed(42);
+        second.shuffle();
+        second.shuffle_with_seed(42);
 
-        if !self.shuffled_cards.is_empty() {
-            self.shuffled_cards = VecDeque::new();
+        for _ in 0..10 {
+            first.deal_card();
+            second.deal_card();
         }
+        assert_eq!(codes(first.dealt_since(0)), codes(second.dealt_since(0)));
+    }
+
+    #[rstest]
+    fn dealt_since_tracks_position() {
+        let mut deck = Deck::with_seed(7);
+        deck.shuffle();
+        let first = deck.deal_card();
+        let start = deck.position();
+        let second = deck.deal_card();
+
+        assert_eq!(start, 1);
+        assert_eq!(
+            codes(deck.dealt_since(0)),
+            vec![first.code(), second.code()]
+        );
+        assert_eq!(codes(deck.dealt_since(start)), vec![second.code()]);
+    }
 
-        for card in &self.cards {
-            self.shuffled_cards.push_back(card.clone());
+    #[rstest]
+    fn shoe_holds_every_deck() {
+        let mut deck = Deck::with_seed(3);
+        deck.set_decks(6);
+        deck.shuffle();
+
+        assert_eq!(deck.decks(), 6);
+        for _ in 0..6 * 52 {
+            deck.deal_card();
         }
+        let aces = deck.dealt_since(0).iter().filter(|c| c.is_ace()).count();
+        assert_eq!(aces, 24);
     }
 
-    pub fn deal_card(&mut self) -> Card {
-        if let Some(card) = self.shuffled_cards.pop_back() {
-            card
-        } else {
-            unreachable!()
+    #[rstest]
+    #[case::fresh_shoe(0.75, 0, false)]
+    #[case::before_cut_card(0.75, 38, false)]
+    #[case::at_cut_card(0.75, 39, true)]
+    #[case::every_round(0.0, 0, true)]
+    fn needs_shuffle(#[case] penetration: f64, #[case] dealt: usize, #[case] expected: bool) {
+        let mut deck = Deck::with_seed(3);
+        deck.shuffle();
+        for _ in 0..dealt {
+            deck.deal_card();
+        }
+        assert_eq!(deck.needs_shuffle(penetration), expected);
+    }
+
+    #[rstest]
+    fn restored_shoe_deals_the_same_cards() {
+        let mut deck = Deck::with_seed(3);
+        deck.set_decks(2);
+        deck.shuffle();
+        for _ in 0..30 {
+            deck.deal_card();
+        }
+        let state = serde_json::to_string(&deck.state()).unwrap();
+        let mut restored = Deck::from_state(2, serde_json::from_str(&state).unwrap());
+
+        assert_eq!(codes(restored.dealt_since(0)), codes(deck.dealt_since(0)));
+        assert_eq!(restored.deal_card().code(), deck.deal_card().code());
+        deck.shuffle();
+        restored.shuffle();
+        assert_eq!(restored.shoe_seed(), deck.shoe_seed());
+    }
+
+    #[rstest]
+    fn exhausted_shoe_is_topped_up() {
+        let mut deck = Deck::with_seed(3);
+        deck.shuffle();
+        for _ in 0..60 {
+            deck.deal_card();
         }
+        assert_eq!(deck.position(), 60);
+        assert_eq!(deck.dealt_since(50).len(), 10);
     }
 }
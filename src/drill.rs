@@ -0,0 +1,239 @@
+use std::{fmt, path::Path};
+
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::Card,
+    error::Error,
+    hand::Hand,
+    profile::{read_json, write_json},
+    strategy::HandCategory,
+};
+
+/// Highest box a flashcard climbs to. Each box up makes a card four times
+/// less likely to be drawn, and a miss sends it back to box 0.
+const TOP_BOX: u8 = 3;
+/// Box of a card that was never answered, between a missed card and one
+/// that was answered right once.
+const NEW_BOX: u8 = 1;
+
+/// One cell of the strategy chart: a hard or soft total, or a pair rank,
+/// against a dealer upcard. Aces count as 11.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Flashcard {
+    pub category: HandCategory,
+    pub value: u8,
+    pub upcard: u8,
+}
+
+impl Flashcard {
+    /// Every two-card hand against every upcard.
+    pub fn all() -> Vec<Self> {
+        let hands = (5..=20)
+            .map(|total| (HandCategory::Hard, total))
+            .chain((13..=20).map(|total| (HandCategory::Soft, total)))
+            .chain((2..=11).map(|rank| (HandCategory::Pair, rank)));
+        hands
+            .flat_map(|(category, value)| {
+                (2..=11).map(move |upcard| Self {
+                    category,
+                    value,
+                    upcard,
+                })
+            })
+            .collect()
+    }
+
+    /// Two cards making this hand, suited so that they read naturally.
+    pub fn hand(&self) -> Hand {
+        let (first, second) = match self.category {
+            HandCategory::Hard => {
+                let high = (self.value - 2).min(10);
+                let low = self.value - high;
+                // A hard 20 drawn as two tens would be a pair.
+                let second = if low == high { "K" } else { rank(high) };
+                (rank(low), second)
+            }
+            HandCategory::Soft => ("A", rank(self.value - 11)),
+            HandCategory::Pair => (rank(self.value), rank(self.value)),
+        };
+        let mut hand = Hand::new(1, None);
+        for code in [format!("{}h", first), format!("{}s", second)] {
+            hand.add_card_to_hand(code.parse().expect("flashcard cards are valid"));
+        }
+        hand
+    }
+
+    pub fn upcard(&self) -> Card {
+        format!("{}d", rank(self.upcard))
+            .parse()
+            .expect("flashcard cards are valid")
+    }
+}
+
+fn rank(value: u8) -> &'static str {
+    const RANKS: [&str; 10] = ["2", "3", "4", "5", "6", "7", "8", "9", "10", "A"];
+    RANKS[value as usize - 2]
+}
+
+impl fmt::Display for Flashcard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.category {
+            HandCategory::Pair => write!(f, "pair of {}s", rank(self.value))?,
+            category => write!(f, "{} {}", category, self.value)?,
+        }
+        write!(f, " against {}", rank(self.upcard))
+    }
+}
+
+/// How a flashcard has been answered so far.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CardRecord {
+    pub card: Flashcard,
+    #[serde(rename = "box")]
+    pub box_number: u8,
+    pub seen: u32,
+    pub correct: u32,
+}
+
+/// Leitner-style spaced repetition over the strategy chart, kept between
+/// sessions so cards that keep being missed come up more often.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DrillProgress {
+    pub records: Vec<CardRecord>,
+}
+
+impl DrillProgress {
+    /// Loads the progress at `path`, starting afresh when there is none.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        Ok(read_json(path)?.unwrap_or_default())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        write_json(self, path)
+    }
+
+    pub fn record(&self, card: &Flashcard) -> Option<&CardRecord> {
+        self.records.iter().find(|r| r.card == *card)
+    }
+
+    fn box_number(&self, card: &Flashcard) -> u8 {
+        self.record(card).map_or(NEW_BOX, |r| r.box_number)
+    }
+
+    /// Draws the next card, weighted towards the lower boxes.
+    pub fn next(&self, rng: &mut impl Rng) -> Flashcard {
+        let cards = Flashcard::all();
+        let weights = cards
+            .iter()
+            .map(|card| 4u32.pow((TOP_BOX - self.box_number(card)).into()));
+        let index = WeightedIndex::new(weights).expect("every card has a weight");
+        cards[index.sample(rng)]
+    }
+
+    /// Moves a right answer one box up and a wrong one back to the start.
+    pub fn answer(&mut self, card: Flashcard, correct: bool) {
+        let i = match self.records.iter().position(|r| r.card == card) {
+            Some(i) => i,
+            None => {
+                self.records.push(CardRecord {
+                    card,
+                    box_number: NEW_BOX,
+                    seen: 0,
+                    correct: 0,
+                });
+                self.records.len() - 1
+            }
+        };
+        let record = &mut self.records[i];
+        record.seen += 1;
+        if correct {
+            record.correct += 1;
+            record.box_number = (record.box_number + 1).min(TOP_BOX);
+        } else {
+            record.box_number = 0;
+        }
+    }
+
+    pub fn accuracy(&self) -> Option<f64> {
+        let seen: u32 = self.records.iter().map(|r| r.seen).sum();
+        let correct: u32 = self.records.iter().map(|r| r.correct).sum();
+        (seen > 0).then(|| correct as f64 / seen as f64)
+    }
+
+    /// Cards whose last answer was wrong.
+    pub fn missed(&self) -> impl Iterator<Item = &Flashcard> {
+        self.records
+            .iter()
+            .filter(|r| r.box_number == 0)
+            .map(|r| &r.card)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use rstest::*;
+
+    #[rstest]
+    #[case::hard_five(HandCategory::Hard, 5, &["2h", "3s"], false)]
+    #[case::hard_twenty(HandCategory::Hard, 20, &["10h", "Ks"], false)]
+    #[case::soft_eighteen(HandCategory::Soft, 18, &["Ah", "7s"], true)]
+    #[case::pair_of_aces(HandCategory::Pair, 11, &["Ah", "As"], true)]
+    fn cards_deal_the_hand_they_name(
+        #[case] category: HandCategory,
+        #[case] value: u8,
+        #[case] cards: &[&str],
+        #[case] soft: bool,
+    ) {
+        let card = Flashcard {
+            category,
+            value,
+            upcard: 10,
+        };
+        let hand = card.hand();
+        let codes: Vec<_> = hand.cards().iter().map(Card::code).collect();
+        assert_eq!(codes, cards);
+        assert_eq!(hand.is_soft(), soft);
+        assert_eq!(hand.cards_are_equal(), category == HandCategory::Pair);
+        if category != HandCategory::Pair {
+            assert_eq!(hand.sum_value(), value);
+        }
+    }
+
+    #[rstest]
+    fn missed_cards_come_up_more_often() {
+        let mut progress = DrillProgress::default();
+        let missed = Flashcard {
+            category: HandCategory::Soft,
+            value: 18,
+            upcard: 9,
+        };
+        progress.answer(missed, false);
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let draws = 1000;
+        let hits = (0..draws)
+            .filter(|_| progress.next(&mut rng) == missed)
+            .count();
+        // One card in 340 would come up about 3 times in 1000 draws.
+        assert!(hits > 6, "{}", hits);
+        assert_eq!(progress.missed().collect::<Vec<_>>(), vec![&missed]);
+    }
+
+    #[rstest]
+    fn right_answers_climb_the_boxes() {
+        let mut progress = DrillProgress::default();
+        let card = Flashcard::all()[0];
+        for correct in [true, true, true, false, true] {
+            progress.answer(card, correct);
+        }
+        let record = progress.record(&card).unwrap();
+        assert_eq!((record.box_number, record.seen, record.correct), (1, 5, 4));
+        assert_eq!(progress.accuracy(), Some(0.8));
+    }
+}
@@ -0,0 +1,276 @@
+//! The house edge of a ruleset, from the exact value of every starting deal
+//! rather than a simulation.
+
+use serde::Serialize;
+
+use crate::{
+    action::Action,
+    dealer::{card, Composition, DealerTable, Shoe, ACE, TEN},
+    ev::{expected_values_with, first_actions},
+    hand::Hand,
+    rules::{DoubleOn, TableRules},
+};
+
+/// How much one rule moves the house edge away from the standard rules.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuleCost {
+    pub rule: String,
+    /// Added to the house edge, so a negative cost favours the player.
+    pub cost: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HouseEdge {
+    pub decks: Option<u8>,
+    /// The player's expected loss per unit of initial bet.
+    pub edge: f64,
+    /// The edge of the standard rules with the same shoe.
+    pub standard: f64,
+    /// Every rule that differs from the standard ones, taken alone.
+    pub rules: Vec<RuleCost>,
+    /// Rules that differ but cannot be priced, as split hands are never
+    /// resplit.
+    pub unpriced: Vec<String>,
+}
+
+impl HouseEdge {
+    /// The edge for `rules` and what each of them costs, from a full shoe
+    /// of `decks` decks or an infinite deck.
+    pub fn new(rules: &TableRules, decks: Option<u8>) -> Self {
+        let standard = house_edge(&TableRules::default(), decks);
+        let costs = differences(rules)
+            .into_iter()
+            .map(|(rule, changed)| RuleCost {
+                rule,
+                cost: house_edge(&changed, decks) - standard,
+            })
+            .collect();
+        Self {
+            decks,
+            edge: house_edge(rules, decks),
+            standard,
+            rules: costs,
+            unpriced: unpriced(rules),
+        }
+    }
+
+    /// What the rules cost together beyond the sum of their own costs.
+    pub fn interaction(&self) -> f64 {
+        let sum: f64 = self.rules.iter().map(|r| r.cost).sum();
+        self.edge - self.standard - sum
+    }
+}
+
+/// The player's expected loss per unit of initial bet, playing every
+/// starting hand the best way for its exact cards and upcard.
+pub fn house_edge(rules: &TableRules, decks: Option<u8>) -> f64 {
+    let full = match decks {
+        Some(decks) => Shoe::Finite(Composition::full(decks)),
+        None => Shoe::Infinite,
+    };
+    let mut dealer = DealerTable::new(rules);
+    let mut ev = 0.0;
+    for (upcard, p_upcard) in full.draws() {
+        let after_upcard = full.remove(upcard);
+        for (first, p_first) in after_upcard.draws() {
+            let after_first = after_upcard.remove(first);
+            for (second, p_second) in after_first.draws().filter(|&(i, _)| i >= first) {
+                let order = if first == second { 1.0 } else { 2.0 };
+                let p = order * p_upcard * p_first * p_second;
+                let shoe = after_first.remove(second);
+                ev += p * deal_value(&mut dealer, rules, [first, second], upcard, shoe);
+            }
+        }
+    }
+    -ev
+}
+
+/// The value of a starting hand played the best allowed way. When the
+/// dealer peeks, a dealer natural settles the hand before it is played.
+fn deal_value(
+    dealer: &mut DealerTable,
+    rules: &TableRules,
+    [first, second]: [usize; 2],
+    upcard: usize,
+    shoe: Shoe,
+) -> f64 {
+    let mut hand = Hand::new(1, None);
+    hand.add_card_to_hand(card(first, 'h'));
+    hand.add_card_to_hand(card(second, 's'));
+    let values = expected_values_with(dealer, &hand, &card(upcard, 'd'), &shoe, rules);
+    let actions = if hand.is_natural() {
+        vec![Action::Stand]
+    } else {
+        first_actions(&hand, rules)
+    };
+    let played = values.ranked(&actions)[0].ev;
+    let hole = match upcard {
+        ACE => TEN,
+        TEN => ACE,
+        _ => return played,
+    };
+    if !rules.dealer_peeks {
+        return played;
+    }
+    let natural = shoe
+        .draws()
+        .find(|&(i, _)| i == hole)
+        .map_or(0.0, |(_, p)| p);
+    let push_or_lose = if hand.is_natural() { 0.0 } else { -1.0 };
+    natural * push_or_lose + (1.0 - natural) * played
+}
+
+/// The standard rules with one rule at a time changed to its value in
+/// `rules`, named after what it changes to.
+fn differences(rules: &TableRules) -> Vec<(String, TableRules)> {
+    let standard = TableRules::default();
+    let mut changes = Vec::new();
+    let mut change = |differs: bool, name: String, apply: &dyn Fn(&mut TableRules)| {
+        if differs {
+            let mut changed = standard.clone();
+            apply(&mut changed);
+            changes.push((name, changed));
+        }
+    };
+    change(
+        rules.dealer_hits_soft_17 != standard.dealer_hits_soft_17,
+        if rules.dealer_hits_soft_17 {
+            "dealer hits soft 17".into()
+        } else {
+            "dealer stands on soft 17".into()
+        },
+        &|r| r.dealer_hits_soft_17 = rules.dealer_hits_soft_17,
+    );
+    change(
+        rules.dealer_peeks != standard.dealer_peeks,
+        if rules.dealer_peeks {
+            "dealer peeks".into()
+        } else {
+            "no hole card".into()
+        },
+        &|r| r.dealer_peeks = rules.dealer_peeks,
+    );
+    change(
+        rules.blackjack_payout != standard.blackjack_payout,
+        format!("{} payout", rules.blackjack_payout),
+        &|r| r.blackjack_payout = rules.blackjack_payout,
+    );
+    change(
+        rules.double_on != standard.double_on,
+        match rules.double_on {
+            DoubleOn::Any => "double on any two cards".into(),
+            double_on => format!("double on {}", double_on),
+        },
+        &|r| r.double_on = rules.double_on,
+    );
+    change(
+        rules.double_after_split != standard.double_after_split,
+        if rules.double_after_split {
+            "double after split".into()
+        } else {
+            "no double after split".into()
+        },
+        &|r| r.double_after_split = rules.double_after_split,
+    );
+    change(
+        rules.surrender != standard.surrender,
+        if rules.surrender {
+            "late surrender".into()
+        } else {
+            "no surrender".into()
+        },
+        &|r| r.surrender = rules.surrender,
+    );
+    change(rules.max_hands < 2, "no splitting".into(), &|r| {
+        r.max_hands = rules.max_hands
+    });
+    change(
+        rules.hit_split_aces != standard.hit_split_aces,
+        if rules.hit_split_aces {
+            "hit split aces".into()
+        } else {
+            "split aces get one card".into()
+        },
+        &|r| r.hit_split_aces = rules.hit_split_aces,
+    );
+    changes
+}
+
+/// The rules that differ from the standard ones only in how often hands
+/// are resplit, which the analysis leaves out.
+fn unpriced(rules: &TableRules) -> Vec<String> {
+    let standard = TableRules::default();
+    let mut unpriced = vec![];
+    if rules.max_hands >= 2 && rules.max_hands != standard.max_hands {
+        unpriced.push(format!("split to {} hands", rules.max_hands));
+    }
+    if rules.resplit_aces != standard.resplit_aces {
+        unpriced.push(if rules.resplit_aces {
+            "resplit aces".into()
+        } else {
+            "no resplitting aces".into()
+        });
+    }
+    unpriced
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Payout;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    fn standard_rules_are_close_to_even() {
+        // Shoe games with these rules are usually quoted at 0.3% to 0.5%.
+        let edge = house_edge(&TableRules::default(), None);
+        assert!((0.002..0.006).contains(&edge), "{}", edge);
+    }
+
+    #[rstest]
+    fn six_to_five_costs_about_one_and_a_half_percent() {
+        let rules = TableRules {
+            blackjack_payout: Payout::new(6, 5),
+            ..TableRules::default()
+        };
+        let edge = HouseEdge::new(&rules, None);
+        assert_eq!(edge.rules.len(), 1);
+        assert_eq!(edge.rules[0].rule, "6:5 payout");
+        assert!((0.013..0.0145).contains(&edge.rules[0].cost), "{:?}", edge);
+        assert!(edge.interaction().abs() < 1e-12);
+    }
+
+    #[rstest]
+    #[case::hits_soft_17(TableRules { dealer_hits_soft_17: true, ..TableRules::default() })]
+    #[case::no_surrender(TableRules { surrender: false, ..TableRules::default() })]
+    #[case::no_double_after_split(TableRules { double_after_split: false, ..TableRules::default() })]
+    #[case::double_on_ten_to_eleven(TableRules { double_on: DoubleOn::TenToEleven, ..TableRules::default() })]
+    fn worse_rules_cost_the_player(#[case] rules: TableRules) {
+        let edge = HouseEdge::new(&rules, None);
+        assert!(edge.rules[0].cost > 0.0, "{:?}", edge);
+    }
+
+    #[rstest]
+    fn standard_rules_differ_from_nothing() {
+        assert_eq!(differences(&TableRules::default()), vec![]);
+        assert_eq!(unpriced(&TableRules::default()), Vec::<String>::new());
+    }
+
+    #[rstest]
+    fn resplitting_rules_are_not_priced() {
+        let rules = TableRules {
+            max_hands: 2,
+            resplit_aces: true,
+            ..TableRules::default()
+        };
+        let edge = HouseEdge::new(&rules, None);
+        assert_eq!(edge.rules, vec![]);
+        assert_eq!(edge.unpriced, vec!["split to 2 hands", "resplit aces"]);
+        let no_splitting = TableRules {
+            max_hands: 1,
+            ..TableRules::default()
+        };
+        assert_eq!(differences(&no_splitting)[0].0, "no splitting");
+    }
+}
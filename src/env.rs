@@ -0,0 +1,391 @@
+//! A reinforcement learning environment in the manner of OpenAI Gym. Its
+//! dynamics are the game itself: a [`Game`] runs on its own thread with the
+//! agent in its only seat, and every decision the seat is asked for comes
+//! back from [`Environment::reset`] or [`Environment::step`].
+
+use std::{
+    sync::mpsc::{channel, Receiver, Sender},
+    thread::{self, JoinHandle},
+};
+
+use serde::Serialize;
+
+use crate::{
+    action::Action,
+    command::Command,
+    controller::{BettingView, Controller, TableView},
+    counting::CountingSystem,
+    error::Error,
+    game::{Game, BET},
+    hand::Hand,
+    player::{Player, PlayerKind},
+    silent_printer::SilentPrinter,
+    table::TableConfig,
+};
+
+/// Chips the agent's seat starts with, enough never to go broke.
+const BANKROLL: u32 = u32::MAX / 2;
+
+/// What the agent sees of a hand it has to play.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Observation {
+    pub total: u8,
+    pub soft: bool,
+    /// The dealer's upcard, 11 for an ace.
+    pub dealer_upcard: u8,
+    /// Whether the hand is one of a split pair.
+    pub from_split: bool,
+    pub legal_actions: Vec<Action>,
+    /// The counts as the seat knows them, with the hole card face down. Only
+    /// given with a counting system.
+    pub running_count: Option<i32>,
+    pub true_count: Option<f64>,
+}
+
+/// What an action, or dealing a new round, led to.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Step {
+    /// The next decision, none once the round is over.
+    pub observation: Option<Observation>,
+    /// What the round won or lost in bets, zero until it is over.
+    pub reward: f64,
+    pub done: bool,
+}
+
+/// A blackjack table for an agent to learn at, one round per episode. The
+/// agent bets the same every round and never takes insurance.
+#[derive(Debug)]
+pub struct Environment {
+    table: TableConfig,
+    seed: Option<u64>,
+    counting: Option<CountingSystem>,
+    bet: u32,
+    game: Option<Running>,
+    /// The decision the game is waiting for, if any.
+    pending: Option<Observation>,
+}
+
+/// The channels to a game playing on its own thread.
+#[derive(Debug)]
+struct Running {
+    requests: Sender<Request>,
+    events: Receiver<Event>,
+    /// The thread, until it has been found stopped.
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Running {
+    /// Runs `play` on a thread of its own, handing it the requests to
+    /// serve and where to send what comes of them.
+    fn start(play: impl FnOnce(Receiver<Request>, Sender<Event>) + Send + 'static) -> Self {
+        let (requests, requests_rx) = channel();
+        let (events_tx, events) = channel();
+        Self {
+            requests,
+            events,
+            thread: Some(thread::spawn(move || play(requests_rx, events_tx))),
+        }
+    }
+
+    /// Why the game stopped, once its channels have closed.
+    fn stopped(&mut self) -> Error {
+        let reason = match self.thread.take().map(JoinHandle::join) {
+            Some(Err(panic)) => panic
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| panic.downcast_ref::<&str>().map(|s| s.to_string()))
+                .unwrap_or_else(|| "it panicked".into()),
+            Some(Ok(())) => "it ended".into(),
+            None => "it is no longer running".into(),
+        };
+        Error::GameStopped(reason)
+    }
+}
+
+#[derive(Debug)]
+enum Request {
+    Deal,
+    Act(Action),
+}
+
+#[derive(Debug)]
+enum Event {
+    Decision(Observation),
+    /// The round is over with the seat's net chips.
+    RoundOver(i64),
+}
+
+impl Environment {
+    pub fn new(table: TableConfig) -> Self {
+        Self {
+            table,
+            seed: None,
+            counting: None,
+            bet: BET,
+            game: None,
+            pending: None,
+        }
+    }
+
+    /// Shuffles the shoe reproducibly, so the same actions always lead to
+    /// the same episodes.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Adds the running and true counts to every observation.
+    pub fn with_counting(mut self, counting: CountingSystem) -> Self {
+        self.counting = Some(counting);
+        self
+    }
+
+    /// Bets `bet` every round rather than the table's usual bet.
+    pub fn with_bet(mut self, bet: u32) -> Self {
+        self.bet = bet;
+        self
+    }
+
+    /// The decision the game is waiting for, if any.
+    pub fn observation(&self) -> Option<&Observation> {
+        self.pending.as_ref()
+    }
+
+    /// Deals a new round, standing on every hand of a round left
+    /// unfinished. A round decided by a natural is over straight away.
+    pub fn reset(&mut self) -> Result<Step, Error> {
+        while self.pending.is_some() {
+            self.step(Action::Stand)?;
+        }
+        if self.game.is_none() {
+            self.game = Some(self.spawn());
+        }
+        self.send(Request::Deal)
+    }
+
+    /// Plays `action` on the current hand. Fails when the action is not one
+    /// of the legal ones or no round is in play.
+    pub fn step(&mut self, action: Action) -> Result<Step, Error> {
+        let pending = self.pending.as_ref().ok_or(Error::RoundOver)?;
+        if !pending.legal_actions.contains(&action) {
+            return Err(Error::IllegalAction(action));
+        }
+        self.send(Request::Act(action))
+    }
+
+    /// What the agent bets on every round, within the table limits.
+    pub fn bet(&self) -> u32 {
+        let limits = &self.table.limits;
+        self.bet.clamp(limits.min_bet, limits.max_bet).max(1)
+    }
+
+    /// Hands a request to the game and waits for what comes of it. Fails
+    /// with why the game stopped if it has.
+    fn send(&mut self, request: Request) -> Result<Step, Error> {
+        let game = self.game.as_mut().ok_or(Error::Quit)?;
+        let event = match game.requests.send(request) {
+            Ok(()) => game.events.recv().ok(),
+            Err(_) => None,
+        };
+        let step = match event.ok_or_else(|| game.stopped())? {
+            Event::Decision(observation) => Step {
+                observation: Some(observation),
+                reward: 0.0,
+                done: false,
+            },
+            Event::RoundOver(net) => Step {
+                observation: None,
+                reward: net as f64 / self.bet() as f64,
+                done: true,
+            },
+        };
+        self.pending = step.observation.clone();
+        Ok(step)
+    }
+
+    /// Starts the game thread, which plays a round whenever one is dealt
+    /// and stops once the environment is dropped.
+    fn spawn(&self) -> Running {
+        let table = self.table.clone();
+        let (seed, counting, bet) = (self.seed, self.counting, self.bet());
+        Running::start(move |requests_rx, events_tx| {
+            let agent = AgentController {
+                requests: requests_rx,
+                events: events_tx.clone(),
+                bet,
+                counting,
+            };
+            let player = Player::new("Agent".into(), BANKROLL, PlayerKind::Player)
+                .with_controller(Box::new(agent));
+            let mut game = Game::new(u32::MAX, SilentPrinter)
+                .with_rules(table.rules.clone())
+                .with_shoe(&table.shoe)
+                .with_limits(table.limits.clone())
+                .with_players(vec![player]);
+            if let Some(seed) = seed {
+                game = game.with_seed(seed);
+            }
+            if let Some(counting) = counting {
+                game = game.with_counting(counting);
+            }
+            loop {
+                let before = game.players()[0].pot;
+                if !game.play_round() {
+                    return;
+                }
+                let net = game.players()[0].pot as i64 - before as i64;
+                if events_tx.send(Event::RoundOver(net)).is_err() {
+                    return;
+                }
+            }
+        })
+    }
+}
+
+/// The agent's seat, passing decisions out to the environment.
+#[derive(Debug)]
+struct AgentController {
+    requests: Receiver<Request>,
+    events: Sender<Event>,
+    bet: u32,
+    counting: Option<CountingSystem>,
+}
+
+impl Controller for AgentController {
+    fn choose_command(
+        &mut self,
+        hand: &Hand,
+        table: &TableView,
+        possible_actions: &[Action],
+    ) -> Result<Command, Error> {
+        let true_count = self
+            .counting
+            .zip(table.count)
+            .map(|(counting, count)| counting.true_count(count, table.shoe.total() as usize));
+        let observation = Observation {
+            total: hand.sum_value(),
+            soft: hand.is_soft(),
+            dealer_upcard: table.dealer_upcard.value(0),
+            from_split: hand.is_from_split(),
+            legal_actions: possible_actions.to_vec(),
+            running_count: table.count,
+            true_count,
+        };
+        self.events
+            .send(Event::Decision(observation))
+            .map_err(|_| Error::Quit)?;
+        match self.requests.recv() {
+            Ok(Request::Act(action)) => Ok(Command::Action(action)),
+            Ok(Request::Deal) | Err(_) => Err(Error::Quit),
+        }
+    }
+
+    /// Waits for the round to be dealt.
+    fn bet(&mut self, _table: &BettingView) -> Result<Command, Error> {
+        match self.requests.recv() {
+            Ok(Request::Deal) => Ok(Command::Bet(self.bet)),
+            Ok(Request::Act(_)) | Err(_) => Err(Error::Quit),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    /// Every step of `rounds` rounds played by standing or hitting below 12.
+    fn episodes(env: &mut Environment, rounds: usize) -> Vec<Step> {
+        let mut steps = vec![];
+        for _ in 0..rounds {
+            let mut step = env.reset().unwrap();
+            steps.push(step.clone());
+            while let Some(observation) = step.observation {
+                let action = if observation.total < 12 {
+                    Action::Hit
+                } else {
+                    Action::Stand
+                };
+                step = env.step(action).unwrap();
+                steps.push(step.clone());
+            }
+        }
+        steps
+    }
+
+    #[rstest]
+    fn episodes_end_with_the_reward() {
+        let mut env = Environment::new(TableConfig::default()).with_seed(7);
+        let steps = episodes(&mut env, 200);
+        assert_eq!(steps.iter().filter(|s| s.done).count(), 200);
+        for step in &steps {
+            assert_eq!(step.done, step.observation.is_none());
+            if !step.done {
+                assert_eq!(step.reward, 0.0);
+            }
+        }
+        let rewards: Vec<f64> = steps.iter().filter(|s| s.done).map(|s| s.reward).collect();
+        assert!(rewards.contains(&1.0) && rewards.contains(&-1.0) && rewards.contains(&1.5));
+    }
+
+    #[rstest]
+    fn episodes_are_reproducible() {
+        let first = episodes(
+            &mut Environment::new(TableConfig::default()).with_seed(3),
+            50,
+        );
+        let second = episodes(
+            &mut Environment::new(TableConfig::default()).with_seed(3),
+            50,
+        );
+        assert_eq!(first, second);
+    }
+
+    #[rstest]
+    fn observes_the_counts() {
+        let mut env = Environment::new(TableConfig::default())
+            .with_seed(5)
+            .with_counting(CountingSystem::HiLo);
+        let steps = episodes(&mut env, 20);
+        let observation = steps
+            .iter()
+            .rev()
+            .find_map(|s| s.observation.as_ref())
+            .unwrap();
+        assert!(observation.running_count.is_some() && observation.true_count.is_some());
+        let mut env = Environment::new(TableConfig::default()).with_seed(5);
+        let steps = episodes(&mut env, 20);
+        assert!(steps
+            .iter()
+            .filter_map(|s| s.observation.as_ref())
+            .all(|o| o.running_count.is_none() && o.true_count.is_none()));
+    }
+
+    #[rstest]
+    fn reports_a_game_that_stopped() {
+        let mut env = Environment::new(TableConfig::default());
+        env.game = Some(Running::start(|_, _| panic!("the dealer fainted")));
+        let error = env.reset().unwrap_err();
+        assert!(matches!(&error, Error::GameStopped(reason) if reason == "the dealer fainted"));
+        assert!(matches!(env.reset(), Err(Error::GameStopped(_))));
+    }
+
+    #[rstest]
+    fn rejects_steps_out_of_turn() {
+        let mut env = Environment::new(TableConfig::default()).with_seed(1);
+        assert!(matches!(env.step(Action::Stand), Err(Error::RoundOver)));
+        let mut step = env.reset().unwrap();
+        while step.done {
+            step = env.reset().unwrap();
+        }
+        let observation = step.observation.unwrap();
+        if !observation.legal_actions.contains(&Action::Split) {
+            assert!(matches!(
+                env.step(Action::Split),
+                Err(Error::IllegalAction(Action::Split))
+            ));
+        }
+        assert!(env.reset().is_ok());
+    }
+}
@@ -0,0 +1,117 @@
+use std::{fmt, io, path::PathBuf};
+
+use crate::{action::Action, table::TableConfig};
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidAction(String),
+    IllegalAction(Action),
+    InvalidCard(String),
+    InsufficientFunds {
+        player: String,
+        bet: u32,
+        pot: u32,
+    },
+    MissingDecision,
+    RoundOver,
+    GameStopped(String),
+    UnknownPreset(String),
+    Quit,
+    Io(io::Error),
+    File {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        line: usize,
+        reason: String,
+    },
+    /// A setting that cannot be used, in the file at `path` if it came
+    /// from one.
+    Config {
+        path: Option<PathBuf>,
+        key: String,
+        reason: String,
+    },
+    UnsupportedVersion {
+        path: PathBuf,
+        version: i64,
+        supported: i64,
+    },
+    Database {
+        path: PathBuf,
+        source: rusqlite::Error,
+    },
+    Bot {
+        name: String,
+        reason: String,
+    },
+}
+
+impl Error {
+    /// Whether the error came from bad player input, so the player can
+    /// simply be asked again.
+    pub fn is_recoverable(&self) -> bool {
+        matches!(self, Error::InvalidAction(_) | Error::IllegalAction(_))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidAction(input) => {
+                write!(f, "{:?} is not a valid action or command", input)
+            }
+            Error::IllegalAction(action) => write!(f, "{:?} is not allowed right now", action),
+            Error::InvalidCard(input) => write!(f, "{:?} is not a valid card code", input),
+            Error::InsufficientFunds { player, bet, pot } => write!(
+                f,
+                "{} is betting {} with only {} in the pot",
+                player, bet, pot
+            ),
+            Error::MissingDecision => write!(f, "The hand history has no more recorded decisions"),
+            Error::RoundOver => write!(f, "The round is over; deal a new one first"),
+            Error::GameStopped(reason) => write!(f, "The game stopped unexpectedly: {}", reason),
+            Error::UnknownPreset(name) => write!(
+                f,
+                "{:?} is neither a known preset ({}) nor an existing file",
+                name,
+                TableConfig::PRESETS.join(", ")
+            ),
+            Error::Quit => write!(f, "The session was ended by a player"),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::File { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Parse { path, line, reason } => {
+                write!(f, "{}:{}: {}", path.display(), line, reason)
+            }
+            Error::Config { path, key, reason } => {
+                if let Some(path) = path {
+                    write!(f, "{}: ", path.display())?;
+                }
+                write!(f, "{}: {}", key, reason)
+            }
+            Error::UnsupportedVersion {
+                path,
+                version,
+                supported,
+            } => write!(
+                f,
+                "{}: written in format version {}, but only version {} can be read",
+                path.display(),
+                version,
+                supported
+            ),
+            Error::Database { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Bot { name, reason } => write!(f, "Bot {:?} {}", name, reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
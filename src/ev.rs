@@ -0,0 +1,298 @@
+//! Exact expected values of every play, by recursion over the cards left
+//! in the shoe rather than by simulation.
+//!
+//! Every value is per unit of the original bet and, when the dealer peeks,
+//! conditional on the dealer not having a natural. Two simplifications keep
+//! the recursion tractable, as in most combinatorial analyzers: the dealer's
+//! hole card does not affect the cards the player draws, and a split hand is
+//! played without resplitting, each half drawing from the same shoe.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::{
+    action::Action,
+    card::Card,
+    dealer::{index, DealerTable, Shoe, Total, ACE},
+    hand::Hand,
+    rules::TableRules,
+};
+
+/// The value of one play for the hand being analyzed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ActionValue {
+    pub action: Action,
+    pub ev: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExpectedValues {
+    pub values: Vec<ActionValue>,
+}
+
+impl ExpectedValues {
+    /// The plays that are allowed, most valuable first.
+    pub fn ranked(&self, possible_actions: &[Action]) -> Vec<ActionValue> {
+        let mut ranked: Vec<_> = self
+            .values
+            .iter()
+            .filter(|v| possible_actions.contains(&v.action))
+            .copied()
+            .collect();
+        ranked.sort_by(|a, b| b.ev.total_cmp(&a.ev));
+        ranked
+    }
+}
+
+/// Expected values of standing, hitting and, for a two card hand, doubling,
+/// splitting a pair and surrendering. `shoe` holds the cards the player has
+/// not seen, so the player's cards and the upcard must already be out of it.
+pub fn expected_values(
+    hand: &Hand,
+    dealer_upcard: &Card,
+    shoe: &Shoe,
+    rules: &TableRules,
+) -> ExpectedValues {
+    let mut dealer = DealerTable::new(rules);
+    expected_values_with(&mut dealer, hand, dealer_upcard, shoe, rules)
+}
+
+/// [`expected_values`] reusing the dealer probabilities worked out for
+/// earlier hands, which must have been for the same `rules`.
+pub(crate) fn expected_values_with(
+    dealer: &mut DealerTable,
+    hand: &Hand,
+    dealer_upcard: &Card,
+    shoe: &Shoe,
+    rules: &TableRules,
+) -> ExpectedValues {
+    let mut calculator = Calculator::new(index(dealer_upcard), rules, dealer);
+    let cards: Vec<usize> = hand.cards().iter().map(index).collect();
+    let total = Total::of(cards.iter().copied());
+    let two_cards = cards.len() == 2;
+
+    let stand = if hand.is_natural() {
+        calculator.natural(*shoe)
+    } else {
+        calculator.stand(*shoe, total)
+    };
+    let mut values = vec![
+        ActionValue {
+            action: Action::Hit,
+            ev: calculator.hit(*shoe, total),
+        },
+        ActionValue {
+            action: Action::Stand,
+            ev: stand,
+        },
+    ];
+    if two_cards {
+        values.push(ActionValue {
+            action: Action::DoubleDown,
+            ev: calculator.double(*shoe, total),
+        });
+    }
+    if two_cards && cards[0] == cards[1] {
+        values.push(ActionValue {
+            action: Action::Split,
+            ev: calculator.split(*shoe, cards[0]),
+        });
+    }
+    if two_cards && !hand.is_from_split() {
+        values.push(ActionValue {
+            action: Action::Surrender,
+            ev: -0.5,
+        });
+    }
+    ExpectedValues { values }
+}
+
+/// The plays `rules` allow on a first two-card hand.
+pub(crate) fn first_actions(hand: &Hand, rules: &TableRules) -> Vec<Action> {
+    let mut actions = vec![Action::Hit, Action::Stand];
+    if rules.double_on.allows(hand.sum_value()) {
+        actions.push(Action::DoubleDown);
+    }
+    if hand.cards_are_equal() && rules.max_hands > 1 {
+        actions.push(Action::Split);
+    }
+    if rules.surrender {
+        actions.push(Action::Surrender);
+    }
+    actions
+}
+
+/// Memoized recursion for one upcard and ruleset.
+struct Calculator<'a> {
+    upcard: usize,
+    rules: &'a TableRules,
+    dealer: &'a mut DealerTable,
+    play: HashMap<(Shoe, Total), f64>,
+}
+
+impl<'a> Calculator<'a> {
+    fn new(upcard: usize, rules: &'a TableRules, dealer: &'a mut DealerTable) -> Self {
+        Self {
+            upcard,
+            rules,
+            dealer,
+            play: HashMap::new(),
+        }
+    }
+
+    fn stand(&mut self, shoe: Shoe, total: Total) -> f64 {
+        let value = total.value();
+        if value > 21 {
+            return -1.0;
+        }
+        let outcomes = self.dealer.outcomes_for(self.upcard, shoe);
+        let mut ev = outcomes.bust - outcomes.natural;
+        for (dealer, p) in (17..=21).zip(outcomes.totals) {
+            ev += p * (value.cmp(&dealer) as i8) as f64;
+        }
+        ev
+    }
+
+    /// A natural pays out unless the dealer has one too.
+    fn natural(&mut self, shoe: Shoe) -> f64 {
+        let payout = &self.rules.blackjack_payout;
+        let win = payout.numerator as f64 / payout.denominator as f64;
+        let dealer_natural = self.dealer.outcomes_for(self.upcard, shoe).natural;
+        (1.0 - dealer_natural) * win
+    }
+
+    fn hit(&mut self, shoe: Shoe, total: Total) -> f64 {
+        shoe.draws()
+            .map(|(i, p)| {
+                let next = total.add(i);
+                let ev = if next.value() > 21 {
+                    -1.0
+                } else {
+                    self.play(shoe.remove(i), next)
+                };
+                p * ev
+            })
+            .sum()
+    }
+
+    /// The better of standing and hitting, once doubling is no longer open.
+    fn play(&mut self, shoe: Shoe, total: Total) -> f64 {
+        if let Some(ev) = self.play.get(&(shoe, total)) {
+            return *ev;
+        }
+        let stand = self.stand(shoe, total);
+        let ev = if total.value() == 21 {
+            stand
+        } else {
+            stand.max(self.hit(shoe, total))
+        };
+        self.play.insert((shoe, total), ev);
+        ev
+    }
+
+    fn double(&mut self, shoe: Shoe, total: Total) -> f64 {
+        let ev: f64 = shoe
+            .draws()
+            .map(|(i, p)| p * self.stand(shoe.remove(i), total.add(i)))
+            .sum();
+        2.0 * ev
+    }
+
+    /// Two hands started from the pair card, each drawing its second card.
+    fn split(&mut self, shoe: Shoe, pair: usize) -> f64 {
+        let split_aces = pair == ACE;
+        let ev: f64 = shoe
+            .draws()
+            .map(|(i, p)| {
+                let (shoe, total) = (shoe.remove(i), Total::of([pair, i]));
+                let ev = if split_aces && !self.rules.hit_split_aces {
+                    self.stand(shoe, total)
+                } else {
+                    let mut ev = self.play(shoe, total);
+                    if self.rules.double_after_split && self.rules.double_on.allows(total.value()) {
+                        ev = ev.max(self.double(shoe, total));
+                    }
+                    ev
+                };
+                p * ev
+            })
+            .sum();
+        2.0 * ev
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dealer::Composition;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    fn cards(codes: &[&str]) -> Vec<Card> {
+        codes.iter().map(|code| code.parse().unwrap()).collect()
+    }
+
+    fn values(codes: &[&str], upcard: &str, decks: u8, rules: &TableRules) -> ExpectedValues {
+        let mut hand = Hand::new(1, None);
+        for card in cards(codes) {
+            hand.add_card_to_hand(card);
+        }
+        let upcard: Card = upcard.parse().unwrap();
+        let shoe =
+            Shoe::Finite(Composition::full(decks).without(hand.cards().iter().chain([&upcard])));
+        expected_values(&hand, &upcard, &shoe, rules)
+    }
+
+    fn stand(values: &ExpectedValues) -> f64 {
+        values.ranked(&[Action::Stand])[0].ev
+    }
+
+    #[rstest]
+    #[case::hard_sixteen_vs_ten(&["10h", "6s"], "10d", Action::Surrender)]
+    #[case::hard_eleven_vs_six(&["5h", "6s"], "6d", Action::DoubleDown)]
+    #[case::hard_thirteen_vs_two(&["10h", "3s"], "2d", Action::Stand)]
+    #[case::soft_eighteen_vs_nine(&["Ah", "7s"], "9d", Action::Hit)]
+    #[case::eights_vs_nine(&["8h", "8s"], "9d", Action::Split)]
+    #[case::tens_vs_six(&["10h", "10s"], "6d", Action::Stand)]
+    fn best_play_agrees_with_basic_strategy(
+        #[case] codes: &[&str],
+        #[case] upcard: &str,
+        #[case] expected: Action,
+    ) {
+        let values = values(codes, upcard, 6, &TableRules::default());
+        let all = [
+            Action::Hit,
+            Action::Stand,
+            Action::DoubleDown,
+            Action::Split,
+            Action::Surrender,
+        ];
+        assert_eq!(values.ranked(&all)[0].action, expected);
+    }
+
+    #[rstest]
+    fn standing_on_twenty_vs_six_matches_published_value() {
+        // 20 against a 6 wins about 70% more often than it loses.
+        let stand = stand(&values(&["10h", "Ks"], "6d", 6, &TableRules::default()));
+        assert!((0.69..0.72).contains(&stand), "{}", stand);
+    }
+
+    #[rstest]
+    fn natural_pays_the_table_payout() {
+        let rules = TableRules::default();
+        let natural = stand(&values(&["Ah", "Ks"], "6d", 1, &rules));
+        assert_eq!(natural, 1.5);
+    }
+
+    #[rstest]
+    fn dealer_natural_counts_without_peek() {
+        let peek = TableRules::default();
+        let no_peek = TableRules {
+            dealer_peeks: false,
+            ..TableRules::default()
+        };
+        let twenty = |rules| stand(&values(&["10h", "Ks"], "Ad", 6, rules));
+        assert!(twenty(&no_peek) < twenty(&peek) - 0.2);
+    }
+}
@@ -1,159 +1,946 @@
 use crate::{
+    betting::Betting,
+    card::Card,
+    controller::{BettingView, ReplayController, TableView},
+    counting::CountingSystem,
+    database::Database,
+    dealer::Composition,
     deck::Deck,
-    hand::HandCondition,
+    error::Error,
+    hand::{Hand, HandCondition, HandState},
+    history::{DecisionRecord, HandHistory, PayoutRecord, RoundRecord, SeatRecord},
     player::{Player, PlayerKind},
-    printer::{Generic, Message, Printer},
+    printer::{Generic, Message, Printer, Result as RoundResult},
+    rules::TableRules,
+    save::{SaveFile, SAVE_VERSION},
+    stats::Statistics,
+    table::{Limits, SeatConfig, ShoeConfig, TableConfig},
 };
 
+/// What a seat bets when it does not choose.
+pub const BET: u32 = 20;
+
 #[derive(Debug)]
 pub struct Game<T: Printer> {
-    number_of_rounds: u16,
-    played_rounds: u16,
+    number_of_rounds: u32,
+    played_rounds: u32,
     players: Vec<Player>,
     dealer: Player,
     deck: Deck,
     printer: T,
+    history: Option<HandHistory>,
+    database: Option<Database>,
+    rules: TableRules,
+    penetration: f64,
+    limits: Limits,
+    counting: Option<CountingSystem>,
+    train: bool,
+    stats: Statistics,
+    /// What stopped the session early, other than a seat quitting.
+    failure: Option<Error>,
+    /// Whether a seat quit during the round being played.
+    quitting: bool,
 }
 
 impl<T: Printer> Game<T> {
-    pub fn new(_number_of_rounds: u16, printer: T) -> Self {
+    pub fn new(number_of_rounds: u32, printer: T) -> Self {
         Self {
             printer,
-            number_of_rounds: 1,
+            number_of_rounds,
             played_rounds: 0,
             players: vec![],
             deck: Deck::new(),
             dealer: Player::new("Dealer".into(), 0, PlayerKind::Dealer),
+            history: None,
+            database: None,
+            rules: TableRules::default(),
+            penetration: ShoeConfig::default().penetration,
+            limits: Limits::default(),
+            counting: None,
+            train: false,
+            stats: Statistics::default(),
+            failure: None,
+            quitting: false,
         }
     }
 
-    pub fn start(&mut self, players_count: u8) {
-        while self.played_rounds < self.number_of_rounds {
-            self.setup_round(players_count);
-            self.run_round().unwrap_or_else(|e| {
-                panic!("Failed to run round {}: {}", self.number_of_rounds + 1, e)
-            });
-            self.played_rounds += 1;
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        let decks = self.deck.decks();
+        self.deck = Deck::with_seed(seed);
+        self.deck.set_decks(decks);
+        self
+    }
+
+    pub fn with_shoe(mut self, shoe: &ShoeConfig) -> Self {
+        self.deck.set_decks(shoe.decks);
+        self.penetration = shoe.penetration;
+        self
+    }
+
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn with_rules(mut self, rules: TableRules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    pub fn with_players(mut self, players: Vec<Player>) -> Self {
+        self.players = players;
+        self
+    }
+
+    pub fn with_history(mut self, history: HandHistory) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    pub fn with_database(mut self, database: Database) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Lets seats ask for the running count, as trainer mode does.
+    pub fn with_counting(mut self, counting: CountingSystem) -> Self {
+        self.counting = Some(counting);
+        self
+    }
+
+    /// Checks every decision against basic strategy and reports mistakes.
+    pub fn with_training(mut self) -> Self {
+        self.train = true;
+        self
+    }
+
+    /// Picks a saved session back up for `number_of_rounds` more rounds.
+    /// Fails when a seat's chart file can no longer be read.
+    pub fn from_save(save: SaveFile, number_of_rounds: u32, printer: T) -> Result<Self, Error> {
+        let players = save
+            .table
+            .seats
+            .iter()
+            .enumerate()
+            .map(|(i, seat)| {
+                let mut player = Player::new(seat.name.clone(), seat.bankroll, PlayerKind::Player)
+                    .with_controller(seat.controller()?);
+                let saved = save.betting.get(i).cloned().flatten();
+                if let Some(betting) = saved.or_else(|| seat.betting.clone().map(Betting::new)) {
+                    player = player.with_betting(betting);
+                }
+                Ok(player)
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Self {
+            number_of_rounds: save.played_rounds.saturating_add(number_of_rounds),
+            played_rounds: save.played_rounds,
+            deck: Deck::from_state(save.table.shoe.decks, save.shoe),
+            penetration: save.table.shoe.penetration,
+            rules: save.table.rules,
+            limits: save.table.limits,
+            counting: save.counting,
+            train: save.train,
+            ..Self::new(number_of_rounds, printer).with_players(players)
+        })
+    }
+
+    /// Everything needed to continue this session later. Seats whose
+    /// controller cannot be saved come back as human seats.
+    pub fn save(&self) -> SaveFile {
+        let seats = self
+            .players
+            .iter()
+            .map(|p| {
+                let mut seat = SeatConfig {
+                    name: p.name.clone(),
+                    bankroll: p.pot,
+                    controller: p.controller_kind().unwrap_or_default(),
+                    chart: None,
+                    counter: None,
+                    q_table: None,
+                    bot: None,
+                    betting: p.betting().map(|b| b.config().clone()),
+                };
+                p.configure_seat(&mut seat);
+                seat
+            })
+            .collect();
+        SaveFile {
+            version: SAVE_VERSION,
+            played_rounds: self.played_rounds,
+            table: TableConfig {
+                rules: self.rules.clone(),
+                shoe: ShoeConfig {
+                    decks: self.deck.decks(),
+                    penetration: self.penetration,
+                },
+                limits: self.limits.clone(),
+                seats,
+                ..TableConfig::default()
+            },
+            counting: self.counting,
+            train: self.train,
+            shoe: self.deck.state(),
+            betting: self.players.iter().map(|p| p.betting().cloned()).collect(),
         }
     }
 
-    fn setup_round(&mut self, players_count: u8) {
-        self.printer.set_round_settings(self.played_rounds + 1);
-        self.players = vec![];
-        for i in 0..players_count {
-            let mut player = Player::new(format!("Player {}", i + 1), 100, PlayerKind::Player);
-            let mut create_result = player.new_hand(20, None);
-            while create_result.is_err() {
-                create_result = player.new_hand(10, None)
+    pub fn players(&self) -> &[Player] {
+        &self.players
+    }
+
+    pub fn stats(&self) -> &Statistics {
+        &self.stats
+    }
+
+    pub fn played_rounds(&self) -> u32 {
+        self.played_rounds
+    }
+
+    /// What stopped the session early, such as a round that could not be
+    /// recorded, if anything other than a seat quitting did.
+    pub fn failure(&self) -> Option<&Error> {
+        self.failure.as_ref()
+    }
+
+    pub fn start(&mut self) {
+        while self.played_rounds < self.number_of_rounds && self.play_round() {}
+        self.printer
+            .print_message(Message::Summary(self.stats.clone()), None);
+        for p in self.players.iter().filter(|p| p.training().decisions > 0) {
+            self.printer
+                .print_message(Message::Training(p.training().clone()), Some(p));
+        }
+        self.printer
+            .print_message(Message::Generic(Generic::Exiting), None);
+    }
+
+    /// Plays one more round, whatever the number of rounds the game was
+    /// set up for. Returns false when nobody could bet or a seat quit.
+    pub fn play_round(&mut self) -> bool {
+        self.start_round();
+        if self.deck.needs_shuffle(self.penetration) {
+            self.shuffle(None);
+        }
+        // A seat whose input has run out has quit as surely as one that said
+        // so. No card is out yet, so every bet placed so far goes back.
+        match self.place_bets() {
+            Ok(true) => (),
+            Ok(false) => return false,
+            Err(Error::Quit | Error::Io(_)) => {
+                for player in self.players.iter_mut() {
+                    player.return_bets();
+                }
+                return false;
+            }
+            Err(e) => panic!(
+                "Failed to take bets for round {}: {}",
+                self.played_rounds + 1,
+                e
+            ),
+        }
+        let round = match self.run_round() {
+            Ok(round) => round,
+            Err(e) => panic!("Failed to run round {}: {}", self.played_rounds + 1, e),
+        };
+        self.stats.record(&round);
+        let playing = self.players.iter_mut().filter(|p| !p.is_sitting_out());
+        for (seat, p) in round.seats.iter().zip(playing) {
+            p.record_round(p.pot as i64 - seat.bankroll as i64);
+        }
+        self.played_rounds += 1;
+        // The round is played either way, but a session that can no longer
+        // be recorded stops here rather than go on unrecorded.
+        if let Err(e) = self.record(&round) {
+            self.failure = Some(e);
+            return false;
+        }
+        !std::mem::take(&mut self.quitting)
+    }
+
+    fn record(&mut self, round: &RoundRecord) -> Result<(), Error> {
+        if let Some(history) = self.history.as_mut() {
+            history.record(round)?;
+        }
+        if let Some(database) = self.database.as_mut() {
+            database.record(round)?;
+        }
+        Ok(())
+    }
+
+    /// Re-runs recorded rounds with the recorded shoes and decisions,
+    /// returning every divergence from the recorded outcomes.
+    pub fn replay(&mut self, rounds: &[RoundRecord]) -> Vec<String> {
+        let mut differences = vec![];
+        for round in rounds {
+            match self.replay_round(round) {
+                Ok(replayed) => differences.extend(round.differences(&replayed)),
+                Err(e) => differences.push(format!("Round {}: {}", round.round, e)),
             }
+        }
+        differences
+    }
+
+    fn replay_round(&mut self, round: &RoundRecord) -> Result<RoundRecord, Error> {
+        self.played_rounds = round.round.saturating_sub(1);
+        self.rules = round.rules.clone();
+        self.deck.set_decks(round.decks);
+        self.start_round();
+        self.players = vec![];
+        for seat in &round.seats {
+            let decisions = round
+                .decisions
+                .iter()
+                .filter(|d| d.player == seat.name)
+                .map(|d| d.action);
+            let controller = ReplayController::new(decisions).with_insurance(seat.insurance > 0);
+            let mut player = Player::new(seat.name.clone(), seat.bankroll, PlayerKind::Player)
+                .with_controller(Box::new(controller));
+            player.new_hand(seat.bet, None)?;
             self.players.push(player);
         }
+
+        self.shuffle(Some(round.shoe_seed));
+        for _ in 0..round.shoe_position {
+            self.deck.deal_card();
+        }
+        self.run_round()
+    }
+
+    fn start_round(&mut self) {
+        self.printer.set_round_settings(self.played_rounds + 1);
+        self.printer
+            .print_message(Message::Generic(Generic::Starting), None);
+
+        self.dealer = Player::new("Dealer".into(), 0, PlayerKind::Dealer);
         match self.dealer.new_hand(0, None) {
             Ok(_) => (),
             Err(_) => unreachable!(),
         };
     }
 
-    fn run_round(&mut self) -> Result<(), String> {
-        self.printer
-            .print_message(Message::Generic(Generic::Starting), None);
+    /// Every seat bets what it can of the bet it chooses, within the table
+    /// limits. Seats that cannot cover the minimum sit out. Returns whether
+    /// anyone is still able to play.
+    fn place_bets(&mut self) -> Result<bool, Error> {
+        let shoe = Composition::full(self.deck.decks()).without(self.deck.dealt_since(0));
+        for player in self.players.iter_mut() {
+            player.reset();
+            if player.pot < self.limits.min_bet {
+                player.sit_out();
+                continue;
+            }
+            let table = BettingView {
+                rules: &self.rules,
+                limits: &self.limits,
+                shoe,
+                decks: self.deck.decks(),
+                bankroll: player.pot,
+                bet: BET,
+                counting: self.counting,
+                stats: &self.stats,
+            };
+            let bet = player
+                .choose_bet(&table, &self.printer)?
+                .clamp(self.limits.min_bet, self.limits.max_bet)
+                .min(player.pot);
+            match player.new_hand(bet, None) {
+                Ok(_) => (),
+                Err(_) => unreachable!(),
+            };
+        }
+        Ok(self.players.iter().any(|p| !p.is_sitting_out()))
+    }
 
+    fn shuffle(&mut self, shoe_seed: Option<u64>) {
         self.printer
             .print_message(Message::Generic(Generic::Shuffling), None);
 
-        self.deck.shuffle();
+        match shoe_seed {
+            Some(shoe_seed) => self.deck.shuffle_with_seed(shoe_seed),
+            None => self.deck.shuffle(),
+        }
+    }
+
+    fn run_round(&mut self) -> Result<RoundRecord, Error> {
+        let shoe_position = self.deck.position();
+        let mut round = RoundRecord {
+            round: self.played_rounds + 1,
+            shoe_seed: self.deck.shoe_seed(),
+            shoe_position,
+            decks: self.deck.decks(),
+            rules: self.rules.clone(),
+            seats: self
+                .players
+                .iter()
+                .filter(|p| !p.is_sitting_out())
+                .map(|p| {
+                    let bet = p.hands[0].current_bet();
+                    SeatRecord {
+                        name: p.name.clone(),
+                        bankroll: p.pot + bet,
+                        bet,
+                        insurance: 0,
+                    }
+                })
+                .collect(),
+            cards: vec![],
+            dealer_cards: vec![],
+            decisions: vec![],
+            payouts: vec![],
+        };
 
-        for _ in 0..2 {
-            self.dealer.add_card_to_hand(self.deck.deal_card(), 0);
-            for p in self.players.iter_mut() {
-                p.add_card_to_hand(self.deck.deal_card(), 0);
+        self.deal_initial_cards();
+        let dealer_upcard = self.dealer.hands[0].cards()[0].clone();
+        self.printer
+            .print_message(Message::DealerUpcard(dealer_upcard.clone()), None);
+        if dealer_upcard.is_ace() {
+            self.offer_insurance(&dealer_upcard, &round.decisions);
+            for (seat, p) in round.seats.iter_mut().zip(self.playing()) {
+                seat.insurance = p.insurance();
             }
         }
 
-        let mut out = 0;
-        loop {
-            if out == 12 {
-                break;
-            };
-            out += 1;
-
+        if self.rules.dealer_peeks && self.dealer.hands[0].is_natural() {
             self.printer
-                .print_message(Message::PlayerStatus, Some(&self.dealer));
+                .print_message(Message::Generic(Generic::DealerBlackjack), None);
+        } else {
+            self.play_seats(&dealer_upcard, &mut round.decisions)?;
+            self.play_dealer(&dealer_upcard, &mut round.decisions)?;
+        }
 
-            // PLAYERS ACTIONS
-            for p in self.players.iter_mut().filter(|p| p.still_playing()) {
-                for current_hand in 0..p.hands.len() {
-                    p.play(current_hand, &mut self.deck, &self.printer)?;
-                }
+        round.payouts = self.verify_results();
+        round.dealer_cards = self.dealer.hands[0].cards().to_vec();
+        round.cards = self.deck.dealt_since(shoe_position).to_vec();
+        Ok(round)
+    }
+
+    /// Two cards to every seat and the dealer, in table order. Without a
+    /// hole card the dealer only gets the upcard for now.
+    fn deal_initial_cards(&mut self) {
+        for i in 0..2 {
+            for p in self.players.iter_mut().filter(|p| !p.is_sitting_out()) {
+                p.add_card_to_hand(self.deck.deal_card(), 0);
             }
+            if i == 0 || self.rules.dealer_peeks {
+                self.dealer.add_card_to_hand(self.deck.deal_card(), 0);
+            }
+        }
+    }
+
+    fn playing(&self) -> impl Iterator<Item = &Player> {
+        self.players.iter().filter(|p| !p.is_sitting_out())
+    }
 
-            // DEALERS ACTIONS
-            if self.dealer.still_playing() {
-                println!("The Dealer will play now");
-                self.dealer.play(0, &mut self.deck, &self.printer)?;
+    /// Every seat is offered insurance against the dealer's ace before
+    /// anyone looks at the hole card.
+    fn offer_insurance(&mut self, dealer_upcard: &Card, decisions: &[DecisionRecord]) {
+        let hole_card = self.dealer.hands[0].cards().get(1);
+        let (count, shoe) = seen_by_seats(&self.deck, self.counting, hole_card);
+        let table = TableView {
+            rules: &self.rules,
+            dealer_upcard,
+            decisions,
+            count,
+            shoe,
+            decks: self.deck.decks(),
+            stats: &self.stats,
+            train: false,
+        };
+        for p in self.players.iter_mut().filter(|p| !p.is_sitting_out()) {
+            let stake = p.offer_insurance(&table);
+            if stake > 0 {
+                self.printer
+                    .print_message(Message::Insurance(stake), Some(p));
             }
+        }
+    }
 
-            if self.should_break_loop() {
-                break;
+    /// Every seat plays its hands in turn, including the ones it splits
+    /// into along the way. A seat that quits, or runs out of input, stands
+    /// on whatever it has left to play so the round can be settled.
+    fn play_seats(
+        &mut self,
+        dealer_upcard: &Card,
+        decisions: &mut Vec<DecisionRecord>,
+    ) -> Result<(), Error> {
+        let hole_card = self.dealer.hands[0].cards().get(1).cloned();
+        for p in self.players.iter_mut().filter(|p| !p.is_sitting_out()) {
+            self.printer.print_message(Message::PlayerStatus, Some(p));
+            let mut current_hand = 0;
+            while current_hand < p.hands.len() {
+                while p.hands[current_hand].is_active() {
+                    let (count, shoe) =
+                        seen_by_seats(&self.deck, self.counting, hole_card.as_ref());
+                    let table = TableView {
+                        rules: &self.rules,
+                        dealer_upcard,
+                        decisions,
+                        count,
+                        shoe,
+                        decks: self.deck.decks(),
+                        stats: &self.stats,
+                        train: self.train,
+                    };
+                    let hand = &p.hands[current_hand];
+                    let (total, soft) = (hand.sum_value(), hand.is_soft());
+                    let action = match p.play(current_hand, &mut self.deck, &table, &self.printer) {
+                        Ok(action) => action,
+                        Err(Error::Quit | Error::Io(_)) => {
+                            self.quitting = true;
+                            p.hands
+                                .iter_mut()
+                                .filter(|h| h.is_active())
+                                .for_each(Hand::stand);
+                            break;
+                        }
+                        Err(e) => return Err(e),
+                    };
+                    decisions.push(DecisionRecord {
+                        player: p.name.clone(),
+                        hand: current_hand,
+                        action,
+                        total,
+                        soft,
+                    });
+                }
+                current_hand += 1;
             }
         }
-        self.verify_results();
         Ok(())
     }
 
-    fn should_break_loop(&self) -> bool {
-        let players = self.players.iter().all(|p| !p.still_playing());
-        let dealer = !self.dealer.still_playing();
-        players && dealer
+    /// Reveals or draws the second dealer card, then draws to the dealer's
+    /// rules while any seat still has a hand in play.
+    fn play_dealer(
+        &mut self,
+        dealer_upcard: &Card,
+        decisions: &mut Vec<DecisionRecord>,
+    ) -> Result<(), Error> {
+        self.printer
+            .print_message(Message::Generic(Generic::DealerTurn), None);
+        if !self.rules.dealer_peeks {
+            self.dealer.deal_card(&mut self.deck, 0, &self.printer);
+        }
+        self.printer
+            .print_message(Message::PlayerStatus, Some(&self.dealer));
+
+        let hands_in_play = self
+            .players
+            .iter()
+            .flat_map(|p| &p.hands)
+            .any(|h| h.is_live());
+        if !hands_in_play || self.dealer.hands[0].is_natural() {
+            return Ok(());
+        }
+        while self.dealer.hands[0].is_active() {
+            let table = TableView {
+                rules: &self.rules,
+                dealer_upcard,
+                decisions,
+                count: None,
+                shoe: Composition::full(self.deck.decks()).without(self.deck.dealt_since(0)),
+                decks: self.deck.decks(),
+                stats: &self.stats,
+                train: false,
+            };
+            let hand = &self.dealer.hands[0];
+            let (total, soft) = (hand.sum_value(), hand.is_soft());
+            let action = self.dealer.play(0, &mut self.deck, &table, &self.printer)?;
+            decisions.push(DecisionRecord {
+                player: self.dealer.name.clone(),
+                hand: 0,
+                action,
+                total,
+                soft,
+            });
+        }
+        Ok(())
     }
 
-    fn verify_results(&mut self) {
+    fn verify_results(&mut self) -> Vec<PayoutRecord> {
         self.printer
             .print_message(Message::Generic(Generic::VerifyResults), None);
 
-        let dealer_hand = self.dealer.check_hand_condition(0);
+        let mut payouts = vec![];
+        let dealer_hand = &self.dealer.hands[0];
         for p in self.players.iter_mut() {
-            let single_hand = p.hands.len() == 1;
-            for (i, h) in p.hands.iter().enumerate() {
-                let player_hand = h.check_hand();
-                if single_hand {
-                    println!("Comparing {}'s hand with the Dealers'", p.name);
+            if p.insurance() > 0 {
+                let paid = if dealer_hand.is_natural() {
+                    3 * p.insurance()
                 } else {
-                    println!("Comparing {}'s {} hand with the Dealers'", p.name, i + 1);
-                }
-                match (&dealer_hand, &player_hand) {
-                    (HandCondition::Under, HandCondition::Under) => {
-                        let dealer_hand = &self.dealer.hands[0];
-                        if dealer_hand > h {
-                            println!("Dealer won");
-                        } else if dealer_hand == h {
-                            println!("Tie!");
-                            p.pot += h.current_bet();
-                        } else {
-                            println!("Player won");
-                            p.pot += 2 * h.current_bet();
-                        }
-                    }
-                    (HandCondition::Blackjack, HandCondition::Blackjack) => {
-                        println!("Tie!");
-                        p.pot += h.current_bet();
-                    }
-                    (HandCondition::Busted, _) => {
-                        println!("Player won");
-                        p.pot += 2 * h.current_bet();
-                    }
-                    (HandCondition::Blackjack, _) => {
-                        println!("Dealer won");
-                    }
-                    (_, HandCondition::Blackjack) => {
-                        println!("Player won");
-                        p.pot += (2.5 * h.current_bet() as f32).ceil() as u32;
-                    }
-                    (_, HandCondition::Busted) => {
-                        println!("Dealer won");
-                    }
+                    0
                 };
+                p.pot += paid;
+                self.printer
+                    .print_message(Message::InsurancePaid(paid), Some(p));
+            }
+            for (i, h) in p.hands.iter().enumerate() {
+                let (result, payout) = settle(h, dealer_hand, &self.rules);
+                p.pot += payout;
+                self.printer
+                    .print_message(Message::Result(result, i), Some(p));
+                payouts.push(PayoutRecord {
+                    player: p.name.clone(),
+                    hand: i,
+                    bet: h.current_bet(),
+                    result,
+                    payout,
+                    cards: h.cards().to_vec(),
+                    total: h.sum_value(),
+                    natural: h.is_natural(),
+                });
+            }
+        }
+        payouts
+    }
+}
+
+/// The running count and the unseen cards as the seats know them, with the
+/// dealer's hole card still face down.
+fn seen_by_seats(
+    deck: &Deck,
+    counting: Option<CountingSystem>,
+    hole_card: Option<&Card>,
+) -> (Option<i32>, Composition) {
+    let count = counting.map(|counting| {
+        let hidden = hole_card.map_or(0, |card| counting.tag(card));
+        counting.running_count(deck.dealt_since(0)) - hidden
+    });
+    let seen = Composition::full(deck.decks()).without(deck.dealt_since(0));
+    (count, hole_card.map_or(seen, |card| seen.with(card)))
+}
+
+/// The result of `hand` against the dealer's final hand and what it pays
+/// back, stake included.
+fn settle(hand: &Hand, dealer_hand: &Hand, rules: &TableRules) -> (RoundResult, u32) {
+    let bet = hand.current_bet();
+    if hand.state() == HandState::Surrendered {
+        return (RoundResult::Surrendered, bet / 2);
+    }
+    match (dealer_hand.check_hand(), hand.check_hand()) {
+        (_, HandCondition::Busted) => (RoundResult::DealerWon, 0),
+        (HandCondition::Blackjack, HandCondition::Blackjack) => (RoundResult::Tie, bet),
+        (HandCondition::Blackjack, _) => (RoundResult::DealerWon, 0),
+        (_, HandCondition::Blackjack) => (
+            RoundResult::Blackjack,
+            bet + rules.blackjack_payout.winnings(bet),
+        ),
+        (HandCondition::Busted, _) => (RoundResult::PlayerWon, 2 * bet),
+        (HandCondition::Under, HandCondition::Under) => {
+            if dealer_hand > hand {
+                (RoundResult::DealerWon, 0)
+            } else if dealer_hand == hand {
+                (RoundResult::Tie, bet)
+            } else {
+                (RoundResult::PlayerWon, 2 * bet)
             }
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        action::Action,
+        betting::{BettingConfig, BettingSystem},
+        command::Command,
+        controller::{BasicStrategyController, Controller},
+        counter::{CounterConfig, CountingController},
+        rules::Payout,
+        silent_printer::SilentPrinter,
+    };
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    fn hand(codes: &[&str]) -> Hand {
+        let mut hand = Hand::new(20, None);
+        for code in codes {
+            hand.add_card_to_hand(code.parse().unwrap());
+        }
+        hand
+    }
+
+    #[rstest]
+    #[case::higher_total(&["10h", "9s"], &["10d", "8c"], RoundResult::PlayerWon, 40)]
+    #[case::lower_total(&["10h", "7s"], &["10d", "8c"], RoundResult::DealerWon, 0)]
+    #[case::push(&["10h", "8s"], &["10d", "8c"], RoundResult::Tie, 20)]
+    #[case::both_bust(&["10h", "6s", "9d"], &["10d", "6c", "Kc"], RoundResult::DealerWon, 0)]
+    #[case::dealer_busts(&["10h", "2s"], &["10d", "6c", "Kc"], RoundResult::PlayerWon, 40)]
+    #[case::natural(&["Ah", "Ks"], &["10d", "Qc"], RoundResult::Blackjack, 50)]
+    #[case::natural_beats_three_card_21(&["Ah", "Ks"], &["7d", "7c", "7s"], RoundResult::Blackjack, 50)]
+    #[case::natural_against_natural(&["Ah", "Ks"], &["Ad", "Qc"], RoundResult::Tie, 20)]
+    #[case::dealer_natural_beats_21(&["7d", "7c", "7s"], &["Ad", "Qc"], RoundResult::DealerWon, 0)]
+    fn settles_hands(
+        #[case] player: &[&str],
+        #[case] dealer: &[&str],
+        #[case] result: RoundResult,
+        #[case] payout: u32,
+    ) {
+        let settled = settle(&hand(player), &hand(dealer), &TableRules::default());
+        assert_eq!(settled, (result, payout));
+    }
+
+    #[rstest]
+    fn surrender_returns_half_the_bet() {
+        let mut surrendered = hand(&["10h", "6s"]);
+        surrendered.surrender();
+        assert_eq!(
+            settle(&surrendered, &hand(&["10d", "Kc"]), &TableRules::default()),
+            (RoundResult::Surrendered, 10)
+        );
+    }
+
+    #[rstest]
+    fn natural_pays_the_table_payout() {
+        let rules = TableRules {
+            blackjack_payout: Payout::new(6, 5),
+            ..TableRules::default()
+        };
+        assert_eq!(
+            settle(&hand(&["Ah", "Ks"]), &hand(&["10d", "9c"]), &rules),
+            (RoundResult::Blackjack, 44)
+        );
+    }
+
+    /// Two basic strategy seats, the second betting by the Labouchère line.
+    fn bot_game(rounds: u32) -> Game<SilentPrinter> {
+        let ana = Player::new("Ana".into(), 1000, PlayerKind::Player)
+            .with_controller(Box::new(BasicStrategyController));
+        let bo = Player::new("Bo".into(), 1000, PlayerKind::Player)
+            .with_controller(Box::new(BasicStrategyController))
+            .with_betting(Betting::new(BettingConfig {
+                system: BettingSystem::Labouchere,
+                unit: Some(5),
+            }));
+        let players = vec![ana, bo];
+        Game::new(rounds, SilentPrinter)
+            .with_shoe(&ShoeConfig {
+                decks: 6,
+                penetration: 0.75,
+            })
+            .with_players(players)
+            .with_seed(11)
+            .with_counting(CountingSystem::HiLo)
+    }
+
+    #[rstest]
+    fn resumed_game_continues_exactly() {
+        let mut uninterrupted = bot_game(20);
+        uninterrupted.start();
+
+        let mut first_half = bot_game(10);
+        first_half.start();
+        let save = first_half.save();
+        assert!(
+            matches!(&save.betting[..], [None, Some(b)] if *b != Betting::new(b.config().clone()))
+        );
+        let save = serde_json::to_string(&save).unwrap();
+        let mut resumed =
+            Game::from_save(serde_json::from_str(&save).unwrap(), 10, SilentPrinter).unwrap();
+        assert_eq!(resumed.played_rounds(), 10);
+        assert_eq!(
+            resumed.deck.dealt_since(0).len(),
+            first_half.deck.dealt_since(0).len()
+        );
+        resumed.start();
+
+        let bankrolls = |game: &Game<SilentPrinter>| -> Vec<u32> {
+            game.players().iter().map(|p| p.pot).collect()
+        };
+        assert_eq!(resumed.played_rounds(), 20);
+        assert_eq!(bankrolls(&resumed), bankrolls(&uninterrupted));
+        assert_eq!(resumed.deck.position(), uninterrupted.deck.position());
+    }
+
+    /// Stands on everything, whatever the chart says.
+    #[derive(Debug)]
+    struct AlwaysStand;
+
+    impl Controller for AlwaysStand {
+        fn choose_command(
+            &mut self,
+            _hand: &Hand,
+            _table: &TableView,
+            _possible_actions: &[Action],
+        ) -> Result<Command, Error> {
+            Ok(Command::Action(Action::Stand))
+        }
+    }
+
+    /// Has no more input to decide with, like a console at end of file,
+    /// from the betting prompt on or only once the cards are dealt.
+    #[derive(Debug)]
+    struct EndOfInput {
+        at_the_bet: bool,
+    }
+
+    impl Controller for EndOfInput {
+        fn choose_command(
+            &mut self,
+            _hand: &Hand,
+            _table: &TableView,
+            _possible_actions: &[Action],
+        ) -> Result<Command, Error> {
+            Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into())
+        }
+
+        fn bet(&mut self, table: &BettingView) -> Result<Command, Error> {
+            if self.at_the_bet {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+            Ok(Command::Bet(table.bet))
+        }
+    }
+
+    #[rstest]
+    #[case::at_the_bet(true)]
+    #[case::mid_round(false)]
+    fn running_out_of_input_ends_the_session(#[case] at_the_bet: bool) {
+        let mut game = bot_game(20);
+        game.players.push(
+            Player::new("Cy".into(), 1000, PlayerKind::Player)
+                .with_controller(Box::new(EndOfInput { at_the_bet })),
+        );
+        game.start();
+        assert!(game.played_rounds() < 20);
+        if at_the_bet {
+            assert_eq!(game.played_rounds(), 0);
+            assert!(game.players().iter().all(|p| p.pot == 1000));
+        }
+    }
+
+    /// Doubles whenever it can, hits otherwise, or quits.
+    #[derive(Debug)]
+    enum Reckless {
+        Doubles,
+        Hits,
+        Quits,
+    }
+
+    impl Controller for Reckless {
+        fn choose_command(
+            &mut self,
+            _hand: &Hand,
+            _table: &TableView,
+            possible_actions: &[Action],
+        ) -> Result<Command, Error> {
+            match self {
+                Reckless::Doubles if possible_actions.contains(&Action::DoubleDown) => {
+                    Ok(Command::Action(Action::DoubleDown))
+                }
+                Reckless::Doubles | Reckless::Hits => Ok(Command::Action(Action::Hit)),
+                Reckless::Quits => Err(Error::Quit),
+            }
+        }
+    }
+
+    #[rstest]
+    fn quitting_mid_round_settles_every_hand() {
+        let game = (0..100).find_map(|seed| {
+            let players = [Reckless::Doubles, Reckless::Hits, Reckless::Quits]
+                .into_iter()
+                .enumerate()
+                .map(|(i, controller)| {
+                    Player::new(format!("P{}", i), 1000, PlayerKind::Player)
+                        .with_controller(Box::new(controller))
+                })
+                .collect();
+            let mut game = Game::new(5, SilentPrinter)
+                .with_players(players)
+                .with_seed(seed);
+            let quit = !game.play_round();
+            (quit && game.players[0].hands[0].current_bet() == 2 * BET).then_some(game)
+        });
+        let game = game.expect("a round where the first seat doubles and the last quits");
+        assert_eq!(game.played_rounds(), 1);
+        let dealer = &game.dealer.hands[0];
+        for p in game.players() {
+            let staked: u32 = p.hands.iter().map(Hand::current_bet).sum();
+            let paid: u32 = p
+                .hands
+                .iter()
+                .map(|h| settle(h, dealer, &game.rules).1)
+                .sum();
+            assert_eq!(p.pot, 1000 - staked + paid, "{}", p.name);
+        }
+        assert_eq!(game.players[1].hands[0].check_hand(), HandCondition::Busted);
+        assert_eq!(game.players[2].hands[0].state(), HandState::Standing);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[rstest]
+    fn a_history_that_cannot_be_written_stops_the_session() {
+        let history = HandHistory::append(std::path::Path::new("/dev/full")).unwrap();
+        let mut game = bot_game(20).with_history(history);
+        game.start();
+        assert_eq!(game.played_rounds(), 1);
+        assert!(matches!(game.failure(), Some(Error::Io(_))));
+    }
+
+    #[rstest]
+    fn counts_rounds_past_a_u16() {
+        let history =
+            std::env::temp_dir().join(format!("blackjack-rounds-{}.jsonl", std::process::id()));
+        let mut game = bot_game(u32::MAX).with_history(HandHistory::create(&history).unwrap());
+        game.played_rounds = u16::MAX.into();
+        assert!(game.play_round());
+        assert_eq!(game.played_rounds(), 65_536);
+        assert_eq!(HandHistory::load(&history).unwrap()[0].round, 65_536);
+        std::fs::remove_file(&history).unwrap();
+    }
+
+    #[rstest]
+    fn training_checks_every_decision() {
+        let mut game = bot_game(20).with_training();
+        game.players.push(
+            Player::new("Cy".into(), 1000, PlayerKind::Player)
+                .with_controller(Box::new(AlwaysStand)),
+        );
+        game.start();
+
+        let [ana, _, cy] = game.players() else {
+            panic!("expected three seats")
+        };
+        assert!(ana.training().decisions > 0);
+        assert_eq!(ana.training().mistakes(), 0);
+        assert!(cy.training().mistakes() > 0);
+        assert!(cy.training().hard_mistakes > 0);
+    }
+
+    #[rstest]
+    fn counting_seat_replays_its_bets_and_insurance() {
+        let mut game = bot_game(0);
+        game.players[1] = Player::new("Bo".into(), 10_000, PlayerKind::Player)
+            .with_controller(Box::new(CountingController::new(CounterConfig::default())));
+        let mut rounds = vec![];
+        for _ in 0..400 {
+            game.start_round();
+            if game.deck.needs_shuffle(game.penetration) {
+                game.shuffle(None);
+            }
+            game.place_bets().unwrap();
+            rounds.push(game.run_round().unwrap());
+            game.played_rounds += 1;
+        }
+        let bets = |name: &str| -> Vec<u32> {
+            rounds
+                .iter()
+                .flat_map(|r| &r.seats)
+                .filter(|s| s.name == name)
+                .map(|s| s.bet)
+                .collect()
+        };
+        assert!(bets("Ana").iter().all(|&bet| bet == BET));
+        assert!(bets("Bo").contains(&10) && bets("Bo").contains(&120));
+        assert!(rounds
+            .iter()
+            .flat_map(|r| &r.seats)
+            .any(|s| s.insurance > 0));
+
+        let mut replay = Game::new(0, SilentPrinter);
+        assert_eq!(replay.replay(&rounds), Vec::<String>::new());
+    }
+}
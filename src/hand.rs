@@ -6,8 +6,18 @@ use crate::card::Card;
 pub struct Hand {
     cards: Vec<Card>,
     bet: u32,
+    state: HandState,
+    from_split: bool,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandState {
+    Playing,
+    Standing,
+    Surrendered,
+}
+
+#[derive(Debug, PartialEq, Eq)]
 pub enum HandCondition {
     Under,
     Blackjack,
@@ -15,12 +25,19 @@ pub enum HandCondition {
 }
 
 impl Hand {
+    /// A hand started from a card is the second half of a split.
     pub fn new(bet: u32, first_card: Option<Card>) -> Self {
         let mut cards = vec![];
+        let from_split = first_card.is_some();
         if let Some(card) = first_card {
             cards.push(card);
         }
-        Self { bet, cards }
+        Self {
+            bet,
+            cards,
+            state: HandState::Playing,
+            from_split,
+        }
     }
 
     pub fn add_card_to_hand(&mut self, card: Card) {
@@ -32,10 +49,25 @@ impl Hand {
     }
 
     pub fn sum_value(&self) -> u8 {
-        self.cards.iter().fold(0, |mut acc, item| {
-            acc += item.value(acc);
-            acc
-        })
+        self.total().0
+    }
+
+    /// Whether an Ace is currently being counted as 11.
+    pub fn is_soft(&self) -> bool {
+        self.total().1
+    }
+
+    /// Aces count as 1 until the end, then a single one is promoted to 11 if
+    /// that does not bust the hand.
+    fn total(&self) -> (u8, bool) {
+        let hard = self.cards.iter().fold(0, |acc, card| {
+            acc + if card.is_ace() { 1 } else { card.value(0) }
+        });
+        if self.cards.iter().any(Card::is_ace) && hard <= 11 {
+            (hard + 10, true)
+        } else {
+            (hard, false)
+        }
     }
 
     pub fn size(&self) -> usize {
@@ -43,14 +75,49 @@ impl Hand {
     }
 
     pub fn check_hand(&self) -> HandCondition {
-        match self.sum_value() {
-            0..=20 => HandCondition::Under,
-            21 => HandCondition::Blackjack,
-            _ => HandCondition::Busted,
+        if self.is_natural() {
+            HandCondition::Blackjack
+        } else if self.sum_value() > 21 {
+            HandCondition::Busted
+        } else {
+            HandCondition::Under
         }
     }
 
+    /// 21 on the first two cards. A split hand reaching 21 is not a natural.
+    pub fn is_natural(&self) -> bool {
+        !self.from_split && self.size() == 2 && self.sum_value() == 21
+    }
+
+    pub fn is_from_split(&self) -> bool {
+        self.from_split
+    }
+
+    /// Whether the hand still takes decisions: it has not stood, doubled or
+    /// surrendered and is below 21.
+    pub fn is_active(&self) -> bool {
+        self.state == HandState::Playing && self.sum_value() < 21
+    }
+
+    /// Whether the hand is still in play against the dealer's total.
+    pub fn is_live(&self) -> bool {
+        self.state != HandState::Surrendered && self.check_hand() == HandCondition::Under
+    }
+
+    pub fn state(&self) -> HandState {
+        self.state
+    }
+
+    pub fn stand(&mut self) {
+        self.state = HandState::Standing;
+    }
+
+    pub fn surrender(&mut self) {
+        self.state = HandState::Surrendered;
+    }
+
     pub fn split(&mut self) -> (Option<Card>, u32) {
+        self.from_split = true;
         (self.cards.pop(), self.bet)
     }
 
@@ -58,6 +125,10 @@ impl Hand {
         self.bet
     }
 
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
     pub fn cards_are_equal(&self) -> bool {
         self.cards.iter().all(|c| *c == self.cards[0])
     }
@@ -89,3 +160,53 @@ impl PartialOrd for Hand {
         self_value.partial_cmp(&other_value)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    fn hand(codes: &[&str]) -> Hand {
+        let mut hand = Hand::new(10, None);
+        for code in codes {
+            hand.add_card_to_hand(code.parse().unwrap());
+        }
+        hand
+    }
+
+    #[rstest]
+    #[case::hard(&["10h", "7s"], 17, false)]
+    #[case::soft(&["Ah", "6s"], 17, true)]
+    #[case::ace_first_then_stiff(&["Ah", "5s", "9d"], 15, false)]
+    #[case::two_aces(&["Ah", "As"], 12, true)]
+    #[case::two_aces_and_nine(&["Ah", "As", "9c"], 21, true)]
+    #[case::natural(&["Ah", "Ks"], 21, true)]
+    #[case::busted(&["10h", "6s", "Kd"], 26, false)]
+    fn sum_value_and_softness(#[case] codes: &[&str], #[case] total: u8, #[case] soft: bool) {
+        let hand = hand(codes);
+        assert_eq!(hand.sum_value(), total);
+        assert_eq!(hand.is_soft(), soft);
+    }
+
+    #[rstest]
+    #[case::natural(&["Ah", "Ks"], HandCondition::Blackjack)]
+    #[case::three_card_21(&["7h", "7s", "7d"], HandCondition::Under)]
+    #[case::busted(&["10h", "6s", "Kd"], HandCondition::Busted)]
+    fn check_hand(#[case] codes: &[&str], #[case] condition: HandCondition) {
+        assert_eq!(hand(codes).check_hand(), condition);
+    }
+
+    #[rstest]
+    fn split_21_is_not_a_natural() {
+        let mut first = hand(&["Ah", "As"]);
+        let (card, bet) = first.split();
+        let mut second = Hand::new(bet, card);
+        first.add_card_to_hand("Kd".parse().unwrap());
+        second.add_card_to_hand("Qc".parse().unwrap());
+
+        assert_eq!(first.check_hand(), HandCondition::Under);
+        assert_eq!(second.check_hand(), HandCondition::Under);
+        assert!(!first.is_active());
+    }
+}
@@ -0,0 +1,271 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    action::Action, card::Card, error::Error, printer::Result as RoundResult, rules::TableRules,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeatRecord {
+    pub name: String,
+    pub bankroll: u32,
+    pub bet: u32,
+    /// The stake insured against a dealer natural, if any.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub insurance: u32,
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecisionRecord {
+    pub player: String,
+    pub hand: usize,
+    pub action: Action,
+    /// Total of the hand when the decision was taken.
+    #[serde(default)]
+    pub total: u8,
+    #[serde(default)]
+    pub soft: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PayoutRecord {
+    pub player: String,
+    pub hand: usize,
+    pub bet: u32,
+    pub result: RoundResult,
+    pub payout: u32,
+    #[serde(default)]
+    pub cards: Vec<Card>,
+    /// Final total of the hand.
+    #[serde(default)]
+    pub total: u8,
+    #[serde(default)]
+    pub natural: bool,
+}
+
+/// Everything needed to replay a round: the shoe it was dealt from, who sat
+/// down and what they bet, and what happened afterwards.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundRecord {
+    pub round: u32,
+    pub shoe_seed: u64,
+    pub shoe_position: usize,
+    #[serde(default = "RoundRecord::default_decks")]
+    pub decks: u8,
+    #[serde(default)]
+    pub rules: TableRules,
+    pub seats: Vec<SeatRecord>,
+    pub cards: Vec<Card>,
+    /// The dealer's final hand, upcard first.
+    #[serde(default)]
+    pub dealer_cards: Vec<Card>,
+    pub decisions: Vec<DecisionRecord>,
+    pub payouts: Vec<PayoutRecord>,
+}
+
+impl RoundRecord {
+    fn default_decks() -> u8 {
+        1
+    }
+
+    /// Describes every way `replayed` diverges from this record.
+    pub fn differences(&self, replayed: &RoundRecord) -> Vec<String> {
+        let mut differences = vec![];
+
+        let codes = |cards: &[Card]| cards.iter().map(Card::code).collect::<Vec<String>>();
+        let (recorded_cards, replayed_cards) = (codes(&self.cards), codes(&replayed.cards));
+        if recorded_cards != replayed_cards {
+            differences.push(format!(
+                "Round {}: dealt [{}] but the history has [{}]",
+                self.round,
+                replayed_cards.join(" "),
+                recorded_cards.join(" ")
+            ));
+        }
+        if self.decisions != replayed.decisions {
+            differences.push(format!(
+                "Round {}: replayed {} decisions but the history has {}",
+                self.round,
+                replayed.decisions.len(),
+                self.decisions.len()
+            ));
+        }
+        for (recorded, replayed) in self.payouts.iter().zip(&replayed.payouts) {
+            if recorded != replayed {
+                differences.push(format!(
+                    "Round {}: {}'s hand {} settled as {:?} paying {} but the history has {:?} paying {}",
+                    self.round,
+                    recorded.player,
+                    recorded.hand + 1,
+                    replayed.result,
+                    replayed.payout,
+                    recorded.result,
+                    recorded.payout
+                ));
+            }
+        }
+        if self.payouts.len() != replayed.payouts.len() {
+            differences.push(format!(
+                "Round {}: settled {} hands but the history has {}",
+                self.round,
+                replayed.payouts.len(),
+                self.payouts.len()
+            ));
+        }
+        differences
+    }
+}
+
+/// Appends one JSON line per played round to a hand-history file.
+#[derive(Debug)]
+pub struct HandHistory {
+    writer: BufWriter<File>,
+}
+
+impl HandHistory {
+    /// Starts a new history at `path`, replacing any file there.
+    pub fn create(path: &Path) -> Result<Self, Error> {
+        Self::open(
+            path,
+            OpenOptions::new().write(true).create(true).truncate(true),
+        )
+    }
+
+    /// Continues the history at `path` after the rounds already in it, as a
+    /// resumed session does.
+    pub fn append(path: &Path) -> Result<Self, Error> {
+        Self::open(path, OpenOptions::new().append(true).create(true))
+    }
+
+    fn open(path: &Path, options: &OpenOptions) -> Result<Self, Error> {
+        let file = options.open(path).map_err(|source| Error::File {
+            path: path.into(),
+            source,
+        })?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+        })
+    }
+
+    pub fn record(&mut self, round: &RoundRecord) -> Result<(), Error> {
+        serde_json::to_writer(&mut self.writer, round).map_err(io::Error::from)?;
+        writeln!(self.writer)?;
+        Ok(self.writer.flush()?)
+    }
+
+    pub fn load(path: &Path) -> Result<Vec<RoundRecord>, Error> {
+        let file = File::open(path).map_err(|source| Error::File {
+            path: path.into(),
+            source,
+        })?;
+        let mut rounds = vec![];
+        for (i, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let round = serde_json::from_str(&line).map_err(|e| Error::Parse {
+                path: path.into(),
+                line: i + 1,
+                reason: e.to_string(),
+            })?;
+            rounds.push(round);
+        }
+        Ok(rounds)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    fn round() -> RoundRecord {
+        RoundRecord {
+            round: 1,
+            shoe_seed: 42,
+            shoe_position: 0,
+            decks: 1,
+            rules: TableRules::default(),
+            seats: vec![SeatRecord {
+                name: "Player 1".into(),
+                bankroll: 100,
+                bet: 20,
+                insurance: 0,
+            }],
+            cards: ["Ah", "10d", "9s", "Kc"]
+                .iter()
+                .map(|c| c.parse().unwrap())
+                .collect(),
+            dealer_cards: vec![],
+            decisions: vec![DecisionRecord {
+                player: "Player 1".into(),
+                hand: 0,
+                action: Action::Stand,
+                total: 19,
+                soft: false,
+            }],
+            payouts: vec![PayoutRecord {
+                player: "Player 1".into(),
+                hand: 0,
+                bet: 20,
+                result: RoundResult::PlayerWon,
+                payout: 40,
+                cards: vec![],
+                total: 19,
+                natural: false,
+            }],
+        }
+    }
+
+    #[rstest]
+    fn json_line_round_trips() {
+        let line = serde_json::to_string(&round()).unwrap();
+        let parsed: RoundRecord = serde_json::from_str(&line).unwrap();
+        assert!(line.contains(r#""cards":["Ah","10d","9s","Kc"]"#));
+        assert_eq!(round().differences(&parsed), Vec::<String>::new());
+    }
+
+    #[rstest]
+    fn appending_keeps_the_rounds_already_written() {
+        let path =
+            std::env::temp_dir().join(format!("blackjack-history-{}.jsonl", std::process::id()));
+        HandHistory::create(&path)
+            .unwrap()
+            .record(&round())
+            .unwrap();
+        HandHistory::append(&path)
+            .unwrap()
+            .record(&round())
+            .unwrap();
+        assert_eq!(HandHistory::load(&path).unwrap().len(), 2);
+        HandHistory::create(&path)
+            .unwrap()
+            .record(&round())
+            .unwrap();
+        assert_eq!(HandHistory::load(&path).unwrap().len(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[rstest]
+    fn differences_reports_payout_changes() {
+        let mut replayed = round();
+        replayed.payouts[0].result = RoundResult::Tie;
+        replayed.payouts[0].payout = 20;
+
+        assert_eq!(
+            round().differences(&replayed),
+            vec!["Round 1: Player 1's hand 1 settled as Tie paying 20 but the history has PlayerWon paying 40"]
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use serde_json::json;
+
+use crate::{
+    player::Player,
+    printer::{Generic, Info, Message, Printer},
+};
+
+/// Prints every message as a JSON object on its own line, for scripts.
+pub struct JsonPrinter {
+    round: u32,
+}
+
+impl JsonPrinter {
+    pub fn new(round: u32) -> Self {
+        Self { round }
+    }
+}
+
+impl Printer for JsonPrinter {
+    fn set_round_settings(&mut self, round: u32) {
+        self.round = round;
+    }
+
+    fn print_message(&self, message: Message, player: Option<&Player>) {
+        let name = player.map(|p| p.name.as_str());
+        let event = match message {
+            Message::PlayerStatus => {
+                let player = player.expect("Did not pass a valid player ref to Printer");
+                let hands: Vec<_> = player.hands.iter().map(|h| h.cards()).collect();
+                json!({ "event": "status", "player": name, "pot": player.pot, "hands": hands })
+            }
+            Message::DealerUpcard(card) => json!({ "event": "dealer_upcard", "card": card }),
+            Message::HandStatus(current_hand) => {
+                let player = player.expect("Did not pass a valid player ref to Printer");
+                let hand = &player.hands[current_hand];
+                json!({
+                    "event": "turn",
+                    "player": name,
+                    "hand": current_hand,
+                    "cards": hand.cards(),
+                    "total": hand.sum_value(),
+                    "soft": hand.is_soft(),
+                })
+            }
+            Message::BetPrompt(bet) => {
+                json!({ "event": "bet_prompt", "player": name, "bet": bet })
+            }
+            Message::Prompt(actions) => {
+                json!({ "event": "prompt", "player": name, "actions": actions })
+            }
+            Message::CardDealt(card, current_hand) => {
+                json!({ "event": "card", "player": name, "hand": current_hand, "card": card })
+            }
+            Message::Action(action) => {
+                json!({ "event": "action", "player": name, "action": action })
+            }
+            Message::Result(result, current_hand) => {
+                let player = player.expect("Did not pass a valid player ref to Printer");
+                json!({
+                    "event": "result",
+                    "player": name,
+                    "hand": current_hand,
+                    "result": result,
+                    "pot": player.pot,
+                })
+            }
+            Message::Insurance(stake) => {
+                json!({ "event": "insurance", "player": name, "stake": stake })
+            }
+            Message::InsurancePaid(payout) => {
+                json!({ "event": "insurance_paid", "player": name, "payout": payout })
+            }
+            Message::InvalidInput(e) => {
+                json!({ "event": "invalid_input", "player": name, "message": e.to_string() })
+            }
+            Message::Info(info) => match info {
+                Info::Help => json!({ "event": "help" }),
+                Info::Rules(rules) => json!({ "event": "rules", "rules": rules }),
+                Info::Bankroll => {
+                    let player = player.expect("Did not pass a valid player ref to Printer");
+                    json!({ "event": "bankroll", "player": name, "pot": player.pot })
+                }
+                Info::Count(count) => json!({ "event": "count", "count": count }),
+                Info::Hint(values) => json!({
+                    "event": "hint",
+                    "action": values.first().map(|v| v.action),
+                    "values": values,
+                }),
+                Info::Kelly(advice) => json!({ "event": "kelly", "advice": advice }),
+                Info::History(decisions) => json!({ "event": "history", "decisions": decisions }),
+                Info::Stats(stats) => json!({ "event": "stats", "player": name, "stats": stats }),
+            },
+            Message::Summary(stats) => {
+                let players: Vec<_> = stats
+                    .players()
+                    .iter()
+                    .map(|(name, stats)| json!({ "player": name, "stats": stats }))
+                    .collect();
+                json!({ "event": "summary", "players": players })
+            }
+            Message::Feedback(chosen, advice) => json!({
+                "event": "feedback",
+                "player": name,
+                "chosen": chosen,
+                "correct": chosen == advice.action,
+                "advice": advice,
+            }),
+            Message::Training(training) => {
+                json!({ "event": "training", "player": name, "training": training })
+            }
+            Message::Generic(m) => {
+                let event = match m {
+                    Generic::VerifyResults => "verify_results",
+                    Generic::Shuffling => "shuffling",
+                    Generic::Starting => "starting",
+                    Generic::Exiting => "exiting",
+                    Generic::DealerTurn => "dealer_turn",
+                    Generic::DealerBlackjack => "dealer_blackjack",
+                };
+                json!({ "event": event, "round": self.round })
+            }
+        };
+        println!("{}", event);
+    }
+}
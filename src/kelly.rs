@@ -0,0 +1,87 @@
+//! Bet sizing by the Kelly criterion, from the edge the true count gives
+//! over the table's rules.
+
+use serde::Serialize;
+
+use crate::{edge::house_edge, rules::TableRules};
+
+/// What each point of true count adds to the player's edge.
+pub const EDGE_PER_TRUE_COUNT: f64 = 0.005;
+
+/// Variance of a hand's result per chip bet, doubles and splits included.
+pub const VARIANCE: f64 = 1.33;
+
+/// The bets the Kelly criterion suggests for one round.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KellyAdvice {
+    pub true_count: f64,
+    /// The player's expected win per chip bet.
+    pub edge: f64,
+    /// The bet that grows the bankroll fastest, nothing without an edge.
+    pub optimal: u32,
+    /// The part of the optimal bet actually advised.
+    pub fraction: f64,
+    pub fractional: u32,
+}
+
+/// Suggests bets for a table's rules.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KellyAdvisor {
+    /// The player's edge off the top of the shoe.
+    base_edge: f64,
+    fraction: f64,
+}
+
+impl KellyAdvisor {
+    /// An advisor for `rules` betting `fraction` of the optimal bet, such as
+    /// 0.5 for half Kelly.
+    pub fn new(rules: &TableRules, fraction: f64) -> Self {
+        Self {
+            base_edge: -house_edge(rules, None),
+            fraction,
+        }
+    }
+
+    /// The player's edge at a true count.
+    pub fn edge(&self, true_count: f64) -> f64 {
+        self.base_edge + EDGE_PER_TRUE_COUNT * true_count
+    }
+
+    pub fn advise(&self, bankroll: u32, true_count: f64) -> KellyAdvice {
+        let edge = self.edge(true_count);
+        let optimal = (bankroll as f64 * edge.max(0.0) / VARIANCE).floor();
+        KellyAdvice {
+            true_count,
+            edge,
+            optimal: optimal as u32,
+            fraction: self.fraction,
+            fractional: (optimal * self.fraction).floor() as u32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    fn bets_nothing_without_an_edge() {
+        let advice = KellyAdvisor::new(&TableRules::default(), 0.5).advise(10_000, 0.0);
+        assert!(advice.edge < 0.0);
+        assert_eq!((advice.optimal, advice.fractional), (0, 0));
+    }
+
+    #[rstest]
+    #[case::plus_two(2.0)]
+    #[case::plus_five(5.0)]
+    fn bets_the_edge_over_the_variance(#[case] true_count: f64) {
+        let advisor = KellyAdvisor::new(&TableRules::default(), 0.5);
+        let advice = advisor.advise(10_000, true_count);
+        let expected = 10_000.0 * advisor.edge(true_count) / VARIANCE;
+        assert_eq!(advice.optimal, expected.floor() as u32);
+        assert_eq!(advice.fractional, (expected.floor() / 2.0).floor() as u32);
+        assert!(advice.optimal > 0);
+    }
+}
@@ -0,0 +1,36 @@
+pub mod action;
+pub mod betting;
+pub mod bot;
+pub mod card;
+pub mod chart;
+pub mod command;
+pub mod console_printer;
+pub mod controller;
+pub mod count_drill;
+pub mod counter;
+pub mod counting;
+pub mod database;
+pub mod dealer;
+pub mod deck;
+pub mod drill;
+pub mod edge;
+pub mod env;
+pub mod error;
+pub mod ev;
+pub mod game;
+pub mod hand;
+pub mod history;
+pub mod json_printer;
+pub mod kelly;
+pub mod optimizer;
+pub mod player;
+pub mod printer;
+pub mod profile;
+pub mod q_table;
+pub mod ruin;
+pub mod rules;
+pub mod save;
+pub mod silent_printer;
+pub mod stats;
+pub mod strategy;
+pub mod table;
@@ -1,16 +1,920 @@
-mod action;
-mod card;
-mod console_printer;
-mod deck;
-mod game;
-mod hand;
-mod player;
-mod printer;
-
-use console_printer::ConsolePrinter;
-use game::Game;
+mod cli;
+
+use std::{
+    fmt::Display,
+    io::{self, Write},
+    path::Path,
+    process, thread,
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde_json::json;
+use strum::IntoEnumIterator;
+
+use blackjack::{
+    action::Action,
+    betting::{Betting, BettingConfig},
+    bot::{BotConfig, BotController},
+    card::Card,
+    chart::StrategyChart,
+    command::Command,
+    console_printer::{percent, print_stats, ConsolePrinter, Theme},
+    controller::{BasicStrategyController, ChartController, ControllerKind},
+    count_drill::{is_correct, CountingProgress},
+    counter::{CounterConfig, CountingController},
+    counting::CountingSystem,
+    database::Database,
+    dealer::{Composition, DealerTable, Shoe},
+    deck::Deck,
+    drill::DrillProgress,
+    edge::HouseEdge,
+    ev::expected_values,
+    game::Game,
+    hand::Hand,
+    history::HandHistory,
+    json_printer::JsonPrinter,
+    optimizer::Optimizer,
+    player::{Player, PlayerKind},
+    printer::Printer,
+    profile::{home_dir, Profile, ProfileStore},
+    q_table::{QTable, QTableController},
+    ruin::{Sessions, PERCENTILES},
+    save::SaveFile,
+    silent_printer::SilentPrinter,
+    stats::PlayerStats,
+    strategy::{advise, basic_strategy},
+    table::{SeatConfig, TableConfig},
+};
+use cli::{
+    AnalyzeArgs, ChartArgs, Cli, CliCommand, CountArgs, DealerArgs, DrillArgs, EdgeArgs,
+    EvolveArgs, ExportFormat, LearnArgs, OutputFormat, ProfileArgs, ReplayArgs, ResumeArgs,
+    RuinArgs, StrategyArgs, TableArgs, DEFAULT_BANKROLL,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Play,
+    Simulate,
+    Train,
+}
 
 fn main() {
-    let mut game = Game::new(1, ConsolePrinter::new(1));
-    game.start(1);
+    let cli = Cli::parse();
+    match cli
+        .command
+        .unwrap_or_else(|| CliCommand::Play(TableArgs::default()))
+    {
+        CliCommand::Play(args) => table(&args, Mode::Play),
+        CliCommand::Simulate(args) => table(&args, Mode::Simulate),
+        CliCommand::Train(args) => table(&args, Mode::Train),
+        CliCommand::Resume(args) => resume(&args),
+        CliCommand::Profile(args) => profile(&args),
+        CliCommand::Replay(args) => match args.format {
+            OutputFormat::Text => replay(&args, ConsolePrinter::new(1)),
+            OutputFormat::Json => replay(&args, JsonPrinter::new(1)),
+        },
+        CliCommand::Analyze(args) => analyze(&args),
+        CliCommand::Drill(args) => drill(&args),
+        CliCommand::Count(args) => count(&args),
+        CliCommand::Dealer(args) => dealer(&args),
+        CliCommand::Chart(args) => chart(&args),
+        CliCommand::Edge(args) => edge(&args),
+        CliCommand::Ruin(args) => ruin(&args),
+        CliCommand::Learn(args) => learn(&args),
+        CliCommand::Evolve(args) => evolve(&args),
+    }
+}
+
+fn table(args: &TableArgs, mode: Mode) {
+    let store = ProfileStore::new(ProfileStore::default_dir());
+    let bankroll = args.bankroll.unwrap_or(DEFAULT_BANKROLL);
+    // A simulation plays the profiles' seats by a strategy rather than by
+    // hand, so it is not the player's own record and leaves them as they are.
+    let simulated = matches!(mode, Mode::Simulate);
+    let mut profiles: Vec<Profile> = args
+        .profiles
+        .iter()
+        .map(|name| {
+            if !simulated {
+                return store.load_or_create(name, bankroll);
+            }
+            let profile = store.load(name)?;
+            Ok(profile.unwrap_or_else(|| Profile::new(name.clone(), bankroll)))
+        })
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|e| exit(e));
+
+    let table = args
+        .table
+        .clone()
+        .or_else(|| profiles.iter().find_map(|p| p.table.clone()))
+        .unwrap_or_else(|| "standard".into());
+    let mut config = TableConfig::load(&table).unwrap_or_else(|e| exit(e));
+    if let Some(theme) = profiles.iter().find_map(|p| p.settings.theme) {
+        config.theme = theme;
+    }
+    // Profiles always sit in the first seats, each a human seat of its own.
+    let mut seats = args.seats(&config).unwrap_or_else(|e| exit(e));
+    for (seat, profile) in seats.iter_mut().zip(&profiles) {
+        seat.name = profile.name.clone();
+        seat.bankroll = profile.bankroll;
+    }
+
+    let save = args.save.as_deref();
+    let (rounds, bankrolls) = match (mode, args.format) {
+        (Mode::Simulate, format) => {
+            let game = setup(args, &config, &seats, mode, SilentPrinter);
+            let game = session(game, save, config.theme);
+            print_summary(&game, &seats, format);
+            standings(&game)
+        }
+        (_, OutputFormat::Text) => {
+            let printer = ConsolePrinter::new(1).with_theme(config.theme);
+            let game = setup(args, &config, &seats, mode, printer);
+            standings(&session(game, save, config.theme))
+        }
+        (_, OutputFormat::Json) => {
+            let game = setup(args, &config, &seats, mode, JsonPrinter::new(1));
+            standings(&session(game, save, config.theme))
+        }
+    };
+
+    if simulated {
+        return;
+    }
+    for (profile, (bankroll, stats)) in profiles.iter_mut().zip(bankrolls) {
+        profile.record_session(&table, rounds, bankroll, &stats);
+        store.save(profile).unwrap_or_else(|e| exit(e));
+    }
+}
+
+/// Rounds played and every seat's final bankroll and statistics, in seat
+/// order.
+fn standings<T: Printer>(game: &Game<T>) -> (u32, Vec<(u32, PlayerStats)>) {
+    let bankrolls = game
+        .players()
+        .iter()
+        .map(|p| {
+            (
+                p.pot,
+                game.stats().get(&p.name).cloned().unwrap_or_default(),
+            )
+        })
+        .collect();
+    (game.played_rounds(), bankrolls)
+}
+
+fn profile(args: &ProfileArgs) {
+    let store = ProfileStore::new(ProfileStore::default_dir());
+    let profiles = match &args.name {
+        Some(name) => match store.load(name).unwrap_or_else(|e| exit(e)) {
+            Some(profile) => vec![profile],
+            None => exit(format!("There is no profile called {:?}", name)),
+        },
+        None => store.list().unwrap_or_else(|e| exit(e)),
+    };
+    match args.format {
+        OutputFormat::Text => {
+            if profiles.is_empty() {
+                println!("No profiles yet, create one with --profile <NAME>.");
+            }
+            for profile in &profiles {
+                println!(
+                    "{}: {} chips ({:+} over {} sessions)",
+                    profile.name,
+                    profile.bankroll,
+                    profile.net(),
+                    profile.sessions.len()
+                );
+                if args.name.is_some() {
+                    print_stats("Lifetime", &profile.stats);
+                    for session in &profile.sessions {
+                        println!(
+                            "  {} rounds at {}: {} -> {}",
+                            session.rounds,
+                            session.table,
+                            session.bankroll_before,
+                            session.bankroll_after
+                        );
+                    }
+                }
+            }
+        }
+        OutputFormat::Json => println!("{}", json!(profiles)),
+    }
+}
+
+fn resume(args: &ResumeArgs) {
+    let save = SaveFile::load(&args.file).unwrap_or_else(|e| exit(e));
+    match args.format {
+        OutputFormat::Text => {
+            let printer = ConsolePrinter::new(1).with_theme(save.table.theme);
+            resume_with(args, save, printer);
+        }
+        OutputFormat::Json => resume_with(args, save, JsonPrinter::new(1)),
+    }
+}
+
+fn resume_with<T: Printer>(args: &ResumeArgs, save: SaveFile, printer: T) {
+    let theme = save.table.theme;
+    let mut game = Game::from_save(save, args.rounds, printer).unwrap_or_else(|e| exit(e));
+    if let Some(path) = &args.history {
+        let history = HandHistory::append(path).unwrap_or_else(|e| exit(e));
+        game = game.with_history(history);
+    }
+    if let Some(path) = &args.database {
+        let database = Database::open(path).unwrap_or_else(|e| exit(e));
+        game = game.with_database(database);
+    }
+    session(game, Some(&args.file), theme);
+}
+
+/// Plays the session and, when asked to, saves it for later. Seats whose
+/// controller gave up along the way are reported.
+fn session<T: Printer>(mut game: Game<T>, save: Option<&Path>, theme: Theme) -> Game<T> {
+    game.start();
+    if let Some(failure) = game.failure() {
+        eprintln!(
+            "The session stopped after round {}: {}",
+            game.played_rounds(),
+            failure
+        );
+    }
+    for player in game.players() {
+        if let Some(failure) = player.controller_failure() {
+            eprintln!(
+                "{}; {} played basic strategy from then on",
+                failure, player.name
+            );
+        }
+    }
+    if let Some(path) = save {
+        let mut state = game.save();
+        state.table.theme = theme;
+        state.write(path).unwrap_or_else(|e| exit(e));
+    }
+    game
+}
+
+/// The player for a seat. Simulated seats play by the chart, count cards or
+/// ask a bot program when asked to. Otherwise human seats play basic
+/// strategy and the others their own controller.
+fn seat_player(seat: &SeatConfig, strategy: &StrategyArgs, mode: Mode) -> Player {
+    let player = Player::new(seat.name.clone(), seat.bankroll, PlayerKind::Player);
+    let player = match mode {
+        Mode::Simulate => match (&strategy.chart, &strategy.q_table) {
+            (Some(path), _) => {
+                let chart = ChartController::load(path).unwrap_or_else(|e| exit(e));
+                player.with_controller(Box::new(chart))
+            }
+            (None, Some(path)) => {
+                let q_table = QTableController::load(path).unwrap_or_else(|e| exit(e));
+                player.with_controller(Box::new(q_table))
+            }
+            _ if strategy.bot.is_some() => {
+                let command = strategy.bot.iter().flat_map(|c| c.split_whitespace());
+                let config = BotConfig {
+                    command: command.map(String::from).collect(),
+                    timeout_ms: strategy.bot_timeout,
+                };
+                let bot = BotController::spawn(config).unwrap_or_else(|e| exit(e));
+                player.with_controller(Box::new(bot))
+            }
+            _ if strategy.counter => {
+                player.with_controller(Box::new(CountingController::new(CounterConfig {
+                    kelly: strategy.kelly,
+                    ..CounterConfig::default()
+                })))
+            }
+            _ if seat.controller == ControllerKind::Human => {
+                player.with_controller(Box::new(BasicStrategyController))
+            }
+            _ => player.with_controller(seat.controller().unwrap_or_else(|e| exit(e))),
+        },
+        Mode::Play | Mode::Train => {
+            player.with_controller(seat.controller().unwrap_or_else(|e| exit(e)))
+        }
+    };
+    let betting = match strategy.betting {
+        Some(system) => Some(BettingConfig {
+            system,
+            unit: strategy.unit,
+        }),
+        None => seat.betting.clone(),
+    };
+    match betting {
+        Some(betting) => player.with_betting(Betting::new(betting)),
+        None => player,
+    }
+}
+
+fn setup<T: Printer>(
+    args: &TableArgs,
+    config: &TableConfig,
+    seats: &[SeatConfig],
+    mode: Mode,
+    printer: T,
+) -> Game<T> {
+    let players = seats
+        .iter()
+        .map(|seat| seat_player(seat, &args.strategy, mode))
+        .collect();
+
+    let mut game = Game::new(args.rounds, printer)
+        .with_rules(config.rules.clone())
+        .with_shoe(&config.shoe)
+        .with_limits(config.limits.clone())
+        .with_players(players);
+    if let Some(seed) = args.seed {
+        game = game.with_seed(seed);
+    }
+    if let Some(path) = &args.history {
+        let history = HandHistory::create(path).unwrap_or_else(|e| exit(e));
+        game = game.with_history(history);
+    }
+    if let Some(path) = &args.database {
+        let database = Database::open(path).unwrap_or_else(|e| exit(e));
+        game = game.with_database(database);
+    }
+    if mode == Mode::Train {
+        game = game.with_counting(CountingSystem::HiLo).with_training();
+    }
+    game
+}
+
+fn print_summary<T: Printer>(game: &Game<T>, seats: &[SeatConfig], format: OutputFormat) {
+    let results = game.players().iter().zip(seats);
+    let net = |player: &Player, seat: &SeatConfig| player.pot as i64 - seat.bankroll as i64;
+    let stats = |player: &Player| game.stats().get(&player.name).cloned().unwrap_or_default();
+    match format {
+        OutputFormat::Text => {
+            println!("Simulated {} rounds", game.played_rounds());
+            for (player, seat) in results {
+                println!(
+                    "{}: {} -> {} chips ({:+})",
+                    player.name,
+                    seat.bankroll,
+                    player.pot,
+                    net(player, seat)
+                );
+                print_stats(&player.name, &stats(player));
+            }
+        }
+        OutputFormat::Json => {
+            let players: Vec<_> = results
+                .map(|(p, seat)| {
+                    json!({
+                        "name": p.name,
+                        "bankroll": p.pot,
+                        "net": net(p, seat),
+                        "stats": stats(p),
+                    })
+                })
+                .collect();
+            println!(
+                "{}",
+                json!({ "rounds": game.played_rounds(), "players": players })
+            );
+        }
+    }
+}
+
+fn replay<T: Printer>(args: &ReplayArgs, printer: T) {
+    let rounds = HandHistory::load(&args.file).unwrap_or_else(|e| exit(e));
+    let mut game = Game::new(1, printer);
+    let differences = game.replay(&rounds);
+    match args.format {
+        OutputFormat::Text if differences.is_empty() => {
+            println!("Replayed {} rounds, all outcomes match.", rounds.len());
+        }
+        OutputFormat::Text => {
+            for difference in &differences {
+                println!("{}", difference);
+            }
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            json!({
+                "rounds": rounds.len(),
+                "differences": differences,
+            })
+        ),
+    }
+    if !differences.is_empty() {
+        exit(format!(
+            "Replay diverged from the history in {} places",
+            differences.len()
+        ));
+    }
+}
+
+fn analyze(args: &AnalyzeArgs) {
+    let config = TableConfig::load(&args.table).unwrap_or_else(|e| exit(e));
+    let rules = config.rules;
+    let parse = |code: &String| -> Card { code.parse().unwrap_or_else(|e| exit(e)) };
+    let upcard = parse(&args.upcard);
+    let mut hand = Hand::new(1, None);
+    for code in &args.hand {
+        hand.add_card_to_hand(parse(code));
+    }
+    let seen: Vec<Card> = args.seen.iter().map(parse).collect();
+    let shoe = Shoe::Finite(
+        Composition::full(args.decks.unwrap_or(config.shoe.decks))
+            .without(hand.cards().iter().chain([&upcard]).chain(&seen)),
+    );
+
+    let possible_actions: Vec<Action> = Action::iter()
+        .filter(|action| action.can_execute(&hand, 1, 1, &rules))
+        .collect();
+    let action = basic_strategy(&hand, &upcard, &possible_actions, &rules);
+    let values = expected_values(&hand, &upcard, &shoe, &rules).ranked(&possible_actions);
+    match args.format {
+        OutputFormat::Text => {
+            println!(
+                "{} ({}{}) against {}: {}",
+                hand,
+                if hand.is_soft() { "soft " } else { "" },
+                hand.sum_value(),
+                upcard,
+                action
+            );
+            for value in &values {
+                println!("  {:<18} {:+.4}", value.action.to_string(), value.ev);
+            }
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            json!({
+                "hand": hand.cards(),
+                "total": hand.sum_value(),
+                "soft": hand.is_soft(),
+                "upcard": upcard,
+                "action": action,
+                "best": values.first().map(|v| v.action),
+                "values": values,
+            })
+        ),
+    }
+}
+
+fn dealer(args: &DealerArgs) {
+    let config = TableConfig::load(&args.table).unwrap_or_else(|e| exit(e));
+    let decks = (!args.infinite).then(|| args.decks.unwrap_or(config.shoe.decks));
+    let table = DealerTable::new(&config.rules).table(decks);
+    let upcard = |value: u8| match value {
+        11 => "A".to_string(),
+        value => value.to_string(),
+    };
+    let columns = ["17", "18", "19", "20", "21", "natural", "bust"];
+    match args.format {
+        ExportFormat::Text => {
+            let shoe = decks.map_or("an infinite deck".into(), |d| format!("{} decks", d));
+            println!("Dealer final totals from {}", shoe);
+            println!(
+                "{:>7}{}",
+                "upcard",
+                columns.map(|c| format!("{:>9}", c)).concat()
+            );
+            for (value, outcomes) in &table {
+                let cells = outcomes.columns().map(|p| format!("{:>8.2}%", p * 100.0));
+                println!("{:>7}{}", upcard(*value), cells.concat());
+            }
+        }
+        ExportFormat::Csv => {
+            println!("upcard,{}", columns.join(","));
+            for (value, outcomes) in &table {
+                let cells = outcomes.columns().map(|p| format!("{:.6}", p));
+                println!("{},{}", upcard(*value), cells.join(","));
+            }
+        }
+        ExportFormat::Json => {
+            let rows: Vec<_> = table
+                .iter()
+                .map(|(value, outcomes)| json!({ "upcard": upcard(*value), "outcomes": outcomes }))
+                .collect();
+            println!(
+                "{}",
+                json!({ "decks": decks, "rules": config.rules, "rows": rows })
+            );
+        }
+    }
+}
+
+fn chart(args: &ChartArgs) {
+    let (chart, title) = match &args.load {
+        Some(path) => {
+            let chart = StrategyChart::load(path).unwrap_or_else(|e| exit(e));
+            (chart, format!("Strategy from {}", path.display()))
+        }
+        None => {
+            let config = TableConfig::load(&args.table).unwrap_or_else(|e| exit(e));
+            let decks = (!args.infinite).then(|| args.decks.unwrap_or(config.shoe.decks));
+            let shoe = match decks {
+                Some(1) => "a single deck".into(),
+                Some(decks) => format!("{} decks", decks),
+                None => "an infinite deck".into(),
+            };
+            let chart = StrategyChart::generate(&config.rules, decks);
+            (chart, format!("Strategy for {}", shoe))
+        }
+    };
+    match args.format {
+        ExportFormat::Text => {
+            println!("{}\n", title);
+            println!("{}", chart);
+        }
+        ExportFormat::Csv => print!("{}", chart.to_csv()),
+        ExportFormat::Json => println!("{}", json!(chart)),
+    }
+}
+
+fn edge(args: &EdgeArgs) {
+    let tables = if args.presets {
+        TableConfig::PRESETS
+            .iter()
+            .map(|name| (name.to_string(), TableConfig::preset(name).unwrap()))
+            .collect()
+    } else {
+        let config = TableConfig::load(&args.table).unwrap_or_else(|e| exit(e));
+        vec![(args.table.clone(), config)]
+    };
+    for (i, (name, config)) in tables.iter().enumerate() {
+        let decks = (!args.infinite).then(|| args.decks.unwrap_or(config.shoe.decks));
+        let edge = HouseEdge::new(&config.rules, decks);
+        match args.format {
+            OutputFormat::Text => {
+                let shoe = match decks {
+                    Some(1) => "a single deck".into(),
+                    Some(decks) => format!("{} decks", decks),
+                    None => "an infinite deck".into(),
+                };
+                let cost = |edge: f64| format!("{:+.2}%", edge * 100.0);
+                if i > 0 {
+                    println!();
+                }
+                println!(
+                    "House edge at {} from {}: {:.2}%",
+                    name,
+                    shoe,
+                    edge.edge * 100.0
+                );
+                println!("  {:<28}{:>8}", "standard rules", cost(edge.standard));
+                for rule in &edge.rules {
+                    println!("  {:<28}{:>8}", rule.rule, cost(rule.cost));
+                }
+                // Rules change each other's worth: surrendering before a
+                // dealer without a hole card checks is early surrender.
+                if edge.interaction().abs() >= 0.00005 {
+                    println!("  {:<28}{:>8}", "rules combined", cost(edge.interaction()));
+                }
+                if !edge.unpriced.is_empty() {
+                    println!(
+                        "  Not priced, as split hands are never resplit: {}",
+                        edge.unpriced.join(", ")
+                    );
+                }
+            }
+            OutputFormat::Json => {
+                let mut value = json!(edge);
+                value["table"] = json!(name);
+                println!("{}", value);
+            }
+        }
+    }
+}
+
+fn ruin(args: &RuinArgs) {
+    let config = TableConfig::load(&args.table).unwrap_or_else(|e| exit(e));
+    let mut seat = config.seats.first().cloned().unwrap_or(SeatConfig {
+        name: "Player 1".into(),
+        bankroll: args.bankroll,
+        controller: ControllerKind::Human,
+        chart: None,
+        counter: None,
+        q_table: None,
+        bot: None,
+        betting: None,
+    });
+    seat.bankroll = args.bankroll;
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let sessions = Sessions::simulate(&config, args.sessions, args.hands, seed, || {
+        seat_player(&seat, &args.strategy, Mode::Simulate)
+    });
+    if let Some(path) = &args.trajectories {
+        sessions.write_csv(path).unwrap_or_else(|e| exit(e));
+    }
+    let histogram = sessions.histogram(args.bins as usize);
+    match args.format {
+        OutputFormat::Text => {
+            println!(
+                "Risk of ruin at {} from {} chips over {} hands: {} ({} of {} sessions, seed {})",
+                args.table,
+                args.bankroll,
+                args.hands,
+                percent(Some(sessions.risk_of_ruin())),
+                sessions.ruined(),
+                args.sessions,
+                seed
+            );
+            let bankroll = |p| sessions.percentile(p).map_or("-".into(), |b| b.to_string());
+            let percentiles: Vec<String> = PERCENTILES
+                .iter()
+                .map(|&p| format!("{}th {}", p, bankroll(p)))
+                .collect();
+            println!(
+                "Final bankroll: median {}, percentiles {}",
+                bankroll(50),
+                percentiles.join(", ")
+            );
+            let most = histogram.iter().map(|bin| bin.sessions).max().unwrap_or(0);
+            let label = histogram.last().map_or(1, |bin| bin.to.to_string().len());
+            for bin in &histogram {
+                let bar = (bin.sessions * 50).div_ceil(most.max(1));
+                println!(
+                    "{:>w$} - {:>w$} | {} {}",
+                    bin.from,
+                    bin.to,
+                    "#".repeat(bar),
+                    bin.sessions,
+                    w = label
+                );
+            }
+        }
+        OutputFormat::Json => {
+            let percentiles: serde_json::Map<String, serde_json::Value> = PERCENTILES
+                .iter()
+                .map(|&p| (p.to_string(), json!(sessions.percentile(p))))
+                .collect();
+            println!(
+                "{}",
+                json!({
+                    "table": args.table,
+                    "seed": seed,
+                    "sessions": args.sessions,
+                    "hands": args.hands,
+                    "bankroll": args.bankroll,
+                    "ruined": sessions.ruined(),
+                    "risk_of_ruin": sessions.risk_of_ruin(),
+                    "median": sessions.percentile(50),
+                    "percentiles": percentiles,
+                    "histogram": histogram,
+                })
+            );
+        }
+    }
+}
+
+fn drill(args: &DrillArgs) {
+    let rules = TableConfig::load(&args.table)
+        .unwrap_or_else(|e| exit(e))
+        .rules;
+    let path = args
+        .progress
+        .clone()
+        .unwrap_or_else(|| home_dir().join("drill.json"));
+    let mut progress = DrillProgress::load(&path).unwrap_or_else(|e| exit(e));
+    let mut rng = match args.seed {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+        None => ChaCha8Rng::from_entropy(),
+    };
+
+    let mut answered = 0;
+    let mut correct = 0;
+    let mut lines = io::stdin().lines();
+    'cards: for i in 0..args.cards {
+        let card = progress.next(&mut rng);
+        let (hand, upcard) = (card.hand(), card.upcard());
+        let possible_actions: Vec<Action> = Action::iter()
+            .filter(|action| action.can_execute(&hand, 1, 1, &rules))
+            .collect();
+        println!("\n{}/{}: {}", i + 1, args.cards, card);
+        println!("Your {} against the dealer's {}", hand, upcard);
+
+        let action = loop {
+            let actions: Vec<_> = possible_actions.iter().map(|a| a.to_string()).collect();
+            print!("{}? ", actions.join(", "));
+            let _ = io::stdout().flush();
+            let Some(Ok(input)) = lines.next() else {
+                break 'cards;
+            };
+            match input.trim().parse() {
+                Ok(Command::Action(action)) if possible_actions.contains(&action) => break action,
+                Ok(Command::Quit) => break 'cards,
+                Ok(_) => println!("Answer with one of the actions."),
+                Err(e) => println!("{}", e),
+            }
+        };
+        let advice = advise(&hand, &upcard, &possible_actions, &rules);
+        answered += 1;
+        if action == advice.action {
+            correct += 1;
+            println!("Correct. {}.", advice.reason);
+        } else {
+            println!("Wrong, the play is {}. {}.", advice.action, advice.reason);
+        }
+        progress.answer(card, action == advice.action);
+    }
+
+    progress.save(&path).unwrap_or_else(|e| exit(e));
+    println!("\n{} of {} correct this session", correct, answered);
+    if let Some(accuracy) = progress.accuracy() {
+        println!("{:.1}% correct over every session", accuracy * 100.0);
+    }
+    let missed: Vec<_> = progress.missed().map(|card| card.to_string()).collect();
+    if !missed.is_empty() {
+        println!("Still to learn: {}", missed.join("; "));
+    }
+}
+
+fn count(args: &CountArgs) {
+    let path = args
+        .progress
+        .clone()
+        .unwrap_or_else(|| home_dir().join("counting.json"));
+    let mut progress = CountingProgress::load(&path).unwrap_or_else(|e| exit(e));
+    let mut deck = match args.seed {
+        Some(seed) => Deck::with_seed(seed),
+        None => Deck::new(),
+    };
+    let system = args.system;
+    let mut lines = io::stdin().lines();
+    let ask = |lines: &mut io::Lines<io::StdinLock>, question: &str| -> Option<(i32, f64)> {
+        let start = Instant::now();
+        loop {
+            print!("{} ", question);
+            let _ = io::stdout().flush();
+            let input = lines.next()?.ok()?;
+            match input.trim().parse() {
+                Ok(answer) => return Some((answer, start.elapsed().as_secs_f64())),
+                Err(_) if input.trim().eq_ignore_ascii_case("quit") => return None,
+                Err(_) => println!("Answer with a whole number, or quit."),
+            }
+        }
+    };
+
+    if args.deck {
+        deck.shuffle();
+        let held_back = 1;
+        println!(
+            "Count down the deck with {}, pressing Enter for each card.",
+            system
+        );
+        print!("Enter to start ");
+        let _ = io::stdout().flush();
+        let _ = lines.next();
+        let start = Instant::now();
+        for i in 0..52 - held_back {
+            print!("{}/{}: {} ", i + 1, 52 - held_back, deck.deal_card());
+            let _ = io::stdout().flush();
+            let _ = lines.next();
+        }
+        let seconds = start.elapsed().as_secs_f64();
+        let running = system.running_count(deck.dealt_since(0));
+        if let Some((answer, _)) = ask(&mut lines, "Running count?") {
+            let correct = answer == running;
+            progress.record_deck_run(system, correct, seconds);
+            println!(
+                "{}: the count is {}, done in {:.1}s. The card held back was {}.",
+                if correct { "Correct" } else { "Wrong" },
+                running,
+                seconds,
+                deck.deal_card()
+            );
+        }
+    } else {
+        deck.set_decks(args.decks);
+        deck.shuffle();
+        let pause = Duration::from_secs_f64(args.speed.max(0.0));
+        for _ in 0..args.questions {
+            if deck.needs_shuffle(0.75) {
+                deck.shuffle();
+                println!("Shuffling, the count starts again from 0.");
+            }
+            for _ in 0..args.cards {
+                print!("\r{:<6}", deck.deal_card().to_string());
+                let _ = io::stdout().flush();
+                thread::sleep(pause);
+            }
+            print!("\r{:<6}\r", "");
+            let running = system.running_count(deck.dealt_since(0));
+            let true_count = system.true_count(running, deck.remaining());
+            let answer = if args.true_count {
+                let question = format!(
+                    "True count, with {:.1} decks left?",
+                    deck.remaining() as f64 / 52.0
+                );
+                ask(&mut lines, &question)
+            } else {
+                ask(&mut lines, "Running count?")
+            };
+            let Some((answer, seconds)) = answer else {
+                break;
+            };
+            let correct = if args.true_count {
+                is_correct(answer, true_count)
+            } else {
+                answer == running
+            };
+            progress.record_answer(system, correct, seconds);
+            println!(
+                "{}: running count {}, true count {:.1}",
+                if correct { "Correct" } else { "Wrong" },
+                running,
+                true_count
+            );
+        }
+    }
+
+    progress.save(&path).unwrap_or_else(|e| exit(e));
+    if let Some(record) = progress.get(system) {
+        println!(
+            "{}: {} of {} counts right ({}), {:.1}s per answer",
+            system,
+            record.correct,
+            record.answers,
+            percent(record.accuracy()),
+            record.seconds_per_answer().unwrap_or_default()
+        );
+        println!(
+            "{}: {} of {} decks counted down right, best {}",
+            system,
+            record.deck_runs_correct,
+            record.deck_runs,
+            record
+                .best_deck_seconds
+                .map_or("-".into(), |s| format!("{:.1}s", s))
+        );
+    }
+}
+
+fn learn(args: &LearnArgs) {
+    let q_table = match &args.load {
+        Some(path) => QTable::load(path).unwrap_or_else(|e| exit(e)),
+        None => {
+            let config = TableConfig::load(&args.table).unwrap_or_else(|e| exit(e));
+            let seed = args.seed.unwrap_or_else(rand::random);
+            let q_table = QTable::train(&config, args.episodes, seed).unwrap_or_else(|e| exit(e));
+            if args.format == OutputFormat::Text {
+                println!(
+                    "Trained for {} episodes with seed {}\n",
+                    args.episodes, seed
+                );
+            }
+            if let Some(path) = &args.output {
+                q_table.save(path).unwrap_or_else(|e| exit(e));
+            }
+            q_table
+        }
+    };
+    let chart = StrategyChart::generate(&q_table.rules, Some(q_table.decks));
+    let comparison = q_table.compare(&chart);
+    match args.format {
+        OutputFormat::Text => print!("{}", comparison),
+        OutputFormat::Json => println!("{}", json!(comparison)),
+    }
+}
+
+fn evolve(args: &EvolveArgs) {
+    let config = TableConfig::load(&args.table).unwrap_or_else(|e| exit(e));
+    let basic = StrategyChart::generate(&config.rules, Some(config.shoe.decks));
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let mut optimizer = Optimizer::new(config.clone(), seed)
+        .with_population(args.population as usize)
+        .with_rounds(args.rounds)
+        .with_mutation(args.mutation);
+    if args.format == OutputFormat::Text {
+        println!("Evolving with seed {}\n", seed);
+    }
+    let best = optimizer.run(args.generations, |generation| {
+        let agreement = generation.chart.agreement(&basic, &config.rules);
+        match args.format {
+            OutputFormat::Text => println!(
+                "Generation {}: best {:+.4} bets a round, mean {:+.4}, plays like basic strategy in {} of {} cells",
+                generation.number,
+                generation.best,
+                generation.mean,
+                agreement,
+                basic.cells()
+            ),
+            OutputFormat::Json => println!(
+                "{}",
+                json!({ "generation": generation, "basic_strategy_cells": agreement })
+            ),
+        }
+    });
+    if let Some(path) = &args.output {
+        best.chart.save(path).unwrap_or_else(|e| exit(e));
+    }
+    if args.format == OutputFormat::Text {
+        println!("\nBest chart of generation {}:\n", best.number);
+        println!("{}", best.chart);
+    }
+}
+
+fn exit(message: impl Display) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
 }
@@ -0,0 +1,304 @@
+//! A genetic search for strategy charts: a population of charts, each scored
+//! by the chips it wins over simulated rounds, bred by crossover and
+//! mutation into the next generation. Started from random charts, it shows
+//! whether play alone finds its way to basic strategy under any rules.
+
+use std::collections::BTreeMap;
+
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::Serialize;
+
+use crate::{
+    chart::{StrategyChart, UPCARDS},
+    controller::ChartController,
+    game::{Game, BET},
+    player::{Player, PlayerKind},
+    silent_printer::SilentPrinter,
+    strategy::Play,
+    table::TableConfig,
+};
+
+/// Charts carried over unchanged into the next generation.
+const ELITES: usize = 2;
+
+/// Charts drawn to pick each parent, the fittest of them winning.
+const TOURNAMENT: usize = 3;
+
+/// Chips each simulated seat starts with, enough never to go broke.
+const BANKROLL: u32 = u32::MAX / 2;
+
+/// The best chart of a generation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Generation {
+    pub number: u32,
+    /// The best chart's average win per round, in bets.
+    pub best: f64,
+    /// The population's average fitness.
+    pub mean: f64,
+    pub chart: StrategyChart,
+}
+
+/// Evolves strategy charts for one table, reproducibly from a seed.
+#[derive(Debug)]
+pub struct Optimizer {
+    config: TableConfig,
+    rng: ChaCha8Rng,
+    population: usize,
+    rounds: u32,
+    mutation: f64,
+}
+
+impl Optimizer {
+    pub fn new(config: TableConfig, seed: u64) -> Self {
+        Self {
+            config,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            population: 30,
+            rounds: 10_000,
+            mutation: 0.02,
+        }
+    }
+
+    /// Charts in every generation, at least three.
+    pub fn with_population(mut self, population: usize) -> Self {
+        self.population = population.max(ELITES + 1);
+        self
+    }
+
+    /// Rounds each chart is played for its fitness.
+    pub fn with_rounds(mut self, rounds: u32) -> Self {
+        self.rounds = rounds;
+        self
+    }
+
+    /// The chance of each cell of a new chart changing to a random play,
+    /// kept between 0 and 1. Not a number is no mutation at all.
+    pub fn with_mutation(mut self, mutation: f64) -> Self {
+        self.mutation = if mutation.is_nan() {
+            0.0
+        } else {
+            mutation.clamp(0.0, 1.0)
+        };
+        self
+    }
+
+    /// Runs `generations` generations from random charts, handing each one's
+    /// best chart to `report` as it is found.
+    pub fn run(&mut self, generations: u32, mut report: impl FnMut(&Generation)) -> Generation {
+        let mut population: Vec<StrategyChart> =
+            (0..self.population).map(|_| self.random_chart()).collect();
+        let mut best = None;
+        for number in 1..=generations {
+            // Every chart of a generation plays the same shoes, so they are
+            // told apart by their plays rather than their luck.
+            let shoe_seed = self.rng.gen();
+            let mut scored: Vec<(f64, StrategyChart)> = population
+                .into_iter()
+                .map(|chart| (self.fitness(&chart, shoe_seed), chart))
+                .collect();
+            scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+            let generation = Generation {
+                number,
+                best: scored[0].0,
+                mean: scored.iter().map(|(f, _)| f).sum::<f64>() / scored.len() as f64,
+                chart: scored[0].1.clone(),
+            };
+            report(&generation);
+            best = Some(generation);
+            population = self.breed(&scored);
+        }
+        best.expect("at least one generation")
+    }
+
+    /// The chart's average win per round, in bets.
+    pub fn fitness(&self, chart: &StrategyChart, shoe_seed: u64) -> f64 {
+        let player = Player::new("Chart".into(), BANKROLL, PlayerKind::Player)
+            .with_controller(Box::new(ChartController::new(chart.clone())));
+        let mut game = Game::new(0, SilentPrinter)
+            .with_rules(self.config.rules.clone())
+            .with_shoe(&self.config.shoe)
+            .with_limits(self.config.limits.clone())
+            .with_players(vec![player])
+            .with_seed(shoe_seed);
+        for _ in 0..self.rounds {
+            game.play_round();
+        }
+        let net = game.players()[0].pot as f64 - BANKROLL as f64;
+        let limits = &self.config.limits;
+        let bet = BET.clamp(limits.min_bet, limits.max_bet);
+        net / (self.rounds.max(1) as f64 * bet as f64)
+    }
+
+    /// The next generation from one scored fittest first: the elites as they
+    /// are, then children of parents won in tournaments.
+    fn breed(&mut self, scored: &[(f64, StrategyChart)]) -> Vec<StrategyChart> {
+        let mut next: Vec<StrategyChart> =
+            scored.iter().take(ELITES).map(|(_, c)| c.clone()).collect();
+        while next.len() < self.population {
+            let first = self.tournament(scored);
+            let second = self.tournament(scored);
+            let mut child = self.crossover(first, second);
+            self.mutate(&mut child);
+            next.push(child);
+        }
+        next
+    }
+
+    fn tournament<'a>(&mut self, scored: &'a [(f64, StrategyChart)]) -> &'a StrategyChart {
+        let (_, chart) = (0..TOURNAMENT)
+            .map(|_| &scored[self.rng.gen_range(0..scored.len())])
+            .max_by(|(a, _), (b, _)| a.total_cmp(b))
+            .unwrap();
+        chart
+    }
+
+    /// Every row taken whole from one parent or the other.
+    fn crossover(&mut self, first: &StrategyChart, second: &StrategyChart) -> StrategyChart {
+        let mut pick = |a: &BTreeMap<u8, [Play; 10]>, b: &BTreeMap<u8, [Play; 10]>| {
+            a.iter()
+                .map(|(value, row)| {
+                    let row = if self.rng.gen_bool(0.5) {
+                        *row
+                    } else {
+                        b[value]
+                    };
+                    (*value, row)
+                })
+                .collect()
+        };
+        StrategyChart {
+            hard: pick(&first.hard, &second.hard),
+            soft: pick(&first.soft, &second.soft),
+            pairs: pick(&first.pairs, &second.pairs),
+        }
+    }
+
+    fn mutate(&mut self, chart: &mut StrategyChart) {
+        let (totals, pairs) = self.plays();
+        let sections = [
+            (&mut chart.hard, &totals),
+            (&mut chart.soft, &totals),
+            (&mut chart.pairs, &pairs),
+        ];
+        for (rows, plays) in sections {
+            for cell in rows.values_mut().flatten() {
+                if self.rng.gen_bool(self.mutation) {
+                    *cell = *plays.choose(&mut self.rng).unwrap();
+                }
+            }
+        }
+    }
+
+    fn random_chart(&mut self) -> StrategyChart {
+        let (totals, pairs) = self.plays();
+        let mut row = |plays: &[Play]| UPCARDS.map(|_| *plays.choose(&mut self.rng).unwrap());
+        StrategyChart {
+            hard: (5..=20).map(|total| (total, row(&totals))).collect(),
+            soft: (13..=20).map(|total| (total, row(&totals))).collect(),
+            pairs: (2..=11).map(|rank| (rank, row(&pairs))).collect(),
+        }
+    }
+
+    /// The plays a cell can hold for totals and for pairs, surrenders only
+    /// where the rules allow them.
+    fn plays(&self) -> (Vec<Play>, Vec<Play>) {
+        let mut totals = vec![
+            Play::Hit,
+            Play::Stand,
+            Play::DoubleOrHit,
+            Play::DoubleOrStand,
+        ];
+        if self.config.rules.surrender {
+            totals.extend([Play::SurrenderOrHit, Play::SurrenderOrStand]);
+        }
+        let mut pairs = totals.clone();
+        pairs.push(Play::Split);
+        (totals, pairs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::TableRules;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    fn optimizer(seed: u64) -> Optimizer {
+        Optimizer::new(TableConfig::default(), seed)
+            .with_population(4)
+            .with_rounds(200)
+    }
+
+    fn cells(chart: &StrategyChart) -> Vec<Play> {
+        [&chart.hard, &chart.soft, &chart.pairs]
+            .iter()
+            .flat_map(|rows| rows.values().flatten().copied())
+            .collect()
+    }
+
+    #[rstest]
+    fn searches_reproducibly() {
+        let mut reported = vec![];
+        let best = optimizer(5).run(3, |generation| reported.push(generation.clone()));
+        assert_eq!(reported.len(), 3);
+        assert_eq!(reported.last(), Some(&best));
+        assert!(reported.iter().all(|g| g.best >= g.mean));
+        assert_eq!(optimizer(5).run(3, |_| ()), best);
+    }
+
+    #[rstest]
+    fn basic_strategy_is_fitter_than_always_hitting() {
+        let optimizer = optimizer(1).with_rounds(2_000);
+        let basic = StrategyChart::generate(&TableRules::default(), None);
+        let mut hitting = basic.clone();
+        for row in [&mut hitting.hard, &mut hitting.soft, &mut hitting.pairs] {
+            row.values_mut().for_each(|plays| *plays = [Play::Hit; 10]);
+        }
+        assert!(optimizer.fitness(&basic, 3) > optimizer.fitness(&hitting, 3) + 0.2);
+    }
+
+    #[rstest]
+    fn children_take_their_cells_from_the_parents() {
+        let mut optimizer = optimizer(2).with_mutation(0.0);
+        let (first, second) = (optimizer.random_chart(), optimizer.random_chart());
+        let child = optimizer.crossover(&first, &second);
+        let from_parents = cells(&child)
+            .iter()
+            .zip(cells(&first).iter().zip(cells(&second)))
+            .all(|(c, (a, b))| c == a || *c == b);
+        assert!(from_parents);
+        let mut mutated = child.clone();
+        optimizer.mutate(&mut mutated);
+        assert_eq!(mutated, child);
+    }
+
+    #[rstest]
+    #[case::negative(-0.5, 0.0)]
+    #[case::above_one(3.0, 1.0)]
+    #[case::not_a_number(f64::NAN, 0.0)]
+    fn keeps_the_mutation_a_chance(#[case] mutation: f64, #[case] kept: f64) {
+        let mut optimizer = optimizer(6).with_mutation(mutation);
+        assert_eq!(optimizer.mutation, kept);
+        let mut chart = optimizer.random_chart();
+        optimizer.mutate(&mut chart);
+    }
+
+    #[rstest]
+    fn surrenders_only_where_allowed() {
+        let config = TableConfig {
+            rules: TableRules {
+                surrender: false,
+                ..TableRules::default()
+            },
+            ..TableConfig::default()
+        };
+        let mut optimizer = Optimizer::new(config, 4).with_mutation(1.0);
+        let mut chart = optimizer.random_chart();
+        optimizer.mutate(&mut chart);
+        assert!(cells(&chart)
+            .iter()
+            .all(|p| !matches!(p, Play::SurrenderOrHit | Play::SurrenderOrStand)));
+    }
+}
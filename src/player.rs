@@ -1,15 +1,27 @@
-use std::{io, str::FromStr};
-
 use strum::IntoEnumIterator;
 
 use crate::{
     action::Action,
+    betting::Betting,
     card::Card,
+    command::Command,
+    controller::{BettingView, ConsoleController, Controller, ControllerKind, TableView},
+    dealer::Shoe,
     deck::Deck,
-    hand::{Hand, HandCondition},
-    printer::Printer,
+    error::Error,
+    ev::expected_values,
+    hand::Hand,
+    kelly::KellyAdvisor,
+    printer::{Info, Message, Printer},
+    rules::TableRules,
+    stats::TrainingStats,
+    strategy::advise,
+    table::SeatConfig,
 };
 
+/// The part of the optimal Kelly bet suggested at the betting prompt.
+const KELLY_FRACTION: f64 = 0.5;
+
 #[derive(Debug)]
 pub enum PlayerKind {
     Dealer,
@@ -19,9 +31,7 @@ pub enum PlayerKind {
 #[derive(Debug, PartialEq, Eq)]
 pub enum PlayerStatus {
     Playing,
-    Won,
-    Standing,
-    Lost,
+    SittingOut,
 }
 
 #[derive(Debug)]
@@ -31,6 +41,10 @@ pub struct Player {
     pub pot: u32,
     status: PlayerStatus,
     pub hands: Vec<Hand>,
+    controller: Box<dyn Controller>,
+    training: TrainingStats,
+    insurance: u32,
+    betting: Option<Betting>,
 }
 
 impl Player {
@@ -41,12 +55,145 @@ impl Player {
             pot,
             status: PlayerStatus::Playing,
             hands: vec![],
+            controller: Box::new(ConsoleController),
+            training: TrainingStats::default(),
+            insurance: 0,
+            betting: None,
         }
     }
 
-    pub fn new_hand(&mut self, bet: u32, card: Option<Card>) -> Result<(), String> {
+    pub fn with_controller(mut self, controller: Box<dyn Controller>) -> Self {
+        self.controller = controller;
+        self
+    }
+
+    /// Bets by a betting system instead of what the controller chooses.
+    pub fn with_betting(mut self, betting: Betting) -> Self {
+        self.betting = Some(betting);
+        self
+    }
+
+    /// Clears the previous round so the seat can bet again.
+    pub fn reset(&mut self) {
+        self.hands = vec![];
+        self.status = PlayerStatus::Playing;
+        self.insurance = 0;
+    }
+
+    /// Hands back every bet still on the table, for a round that was
+    /// abandoned before it was settled.
+    pub fn return_bets(&mut self) {
+        self.pot += self.hands.iter().map(|h| h.current_bet()).sum::<u32>() + self.insurance;
+        self.hands = vec![];
+        self.insurance = 0;
+    }
+
+    pub fn controller_kind(&self) -> Option<ControllerKind> {
+        self.controller.kind()
+    }
+
+    /// Why the seat's controller gave up, if it did.
+    pub fn controller_failure(&self) -> Option<String> {
+        self.controller.failure()
+    }
+
+    pub fn configure_seat(&self, seat: &mut SeatConfig) {
+        self.controller.configure(seat);
+    }
+
+    pub fn betting(&self) -> Option<&Betting> {
+        self.betting.as_ref()
+    }
+
+    /// The bet on the next round, from the betting system if the seat has
+    /// one and from the controller otherwise.
+    pub fn choose_bet(
+        &mut self,
+        table: &BettingView,
+        printer: &impl Printer,
+    ) -> Result<u32, Error> {
+        if let Some(betting) = self.betting.as_mut() {
+            return Ok(betting.bet(table));
+        }
+        loop {
+            printer.print_message(Message::BetPrompt(table.bet), Some(self));
+            let info = match self.controller.bet(table) {
+                Ok(Command::Bet(bet)) => return Ok(bet),
+                Ok(Command::Action(action)) => {
+                    let error = Error::IllegalAction(action);
+                    printer.print_message(Message::InvalidInput(error), Some(self));
+                    continue;
+                }
+                Ok(Command::Help) => Info::Help,
+                Ok(Command::Rules) => Info::Rules(table.rules.clone()),
+                Ok(Command::Bankroll) => Info::Bankroll,
+                Ok(Command::Count) => {
+                    let count = table
+                        .counting
+                        .map(|counting| counting.running_count_of(&table.shoe, table.decks));
+                    Info::Count(count)
+                }
+                Ok(Command::Hint) => Info::Kelly(table.true_count().map(|true_count| {
+                    KellyAdvisor::new(table.rules, KELLY_FRACTION).advise(self.pot, true_count)
+                })),
+                Ok(Command::History) => Info::History(vec![]),
+                Ok(Command::Stats) => {
+                    Info::Stats(table.stats.get(&self.name).cloned().unwrap_or_default())
+                }
+                Ok(Command::Quit) => return Err(Error::Quit),
+                Err(e) if e.is_recoverable() => {
+                    printer.print_message(Message::InvalidInput(e), Some(self));
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            printer.print_message(Message::Info(info), Some(self));
+        }
+    }
+
+    /// Tells the betting system what the last round won or lost.
+    pub fn record_round(&mut self, net: i64) {
+        if let Some(betting) = self.betting.as_mut() {
+            betting.record(net);
+        }
+    }
+
+    /// Offers insurance on the first hand, taking half its bet from the pot
+    /// when the controller accepts and the pot covers it.
+    pub fn offer_insurance(&mut self, table: &TableView) -> u32 {
+        let stake = self.hands[0].current_bet() / 2;
+        if stake == 0 || stake > self.pot || !self.controller.insure(&self.hands[0], table) {
+            return 0;
+        }
+        self.pot -= stake;
+        self.insurance = stake;
+        stake
+    }
+
+    /// The insurance taken this round.
+    pub fn insurance(&self) -> u32 {
+        self.insurance
+    }
+
+    pub fn training(&self) -> &TrainingStats {
+        &self.training
+    }
+
+    pub fn sit_out(&mut self) {
+        self.status = PlayerStatus::SittingOut;
+    }
+
+    pub fn is_sitting_out(&self) -> bool {
+        self.status == PlayerStatus::SittingOut
+    }
+
+    pub fn new_hand(&mut self, bet: u32, card: Option<Card>) -> Result<(), Error> {
         if bet > self.pot {
-            return Err(format!("{} is betting more than owned pot!", self.name));
+            return Err(Error::InsufficientFunds {
+                player: self.name.clone(),
+                bet,
+                pot: self.pot,
+            });
         }
         self.pot -= bet;
         self.hands.push(Hand::new(bet, card));
@@ -61,42 +208,13 @@ impl Player {
         &mut self,
         current_hand: usize,
         deck: &mut Deck,
+        table: &TableView,
         printer: &impl Printer,
-    ) -> Result<&PlayerStatus, String> {
+    ) -> Result<Action, Error> {
         match self.kind {
-            PlayerKind::Dealer => self.dealer_play(deck, printer)?,
-            PlayerKind::Player => self.player_play(current_hand, deck, printer)?,
-        };
-        Ok(self.check_condition(current_hand))
-    }
-
-    pub fn still_playing(&self) -> bool {
-        matches!(self.status, PlayerStatus::Playing)
-    }
-
-    pub fn check_hand_condition(&self, current_hand: usize) -> HandCondition {
-        self.hands[current_hand].check_hand()
-    }
-
-    fn check_condition(&mut self, current_hand: usize) -> &PlayerStatus {
-        if self.status == PlayerStatus::Playing {
-            match self.check_hand(current_hand) {
-                HandCondition::Under => {
-                    self.status = PlayerStatus::Playing;
-                }
-                HandCondition::Blackjack => {
-                    self.status = PlayerStatus::Won;
-                }
-                HandCondition::Busted => {
-                    self.status = PlayerStatus::Lost;
-                }
-            }
-        };
-        &self.status
-    }
-
-    fn check_hand(&self, current_hand: usize) -> HandCondition {
-        self.hands[current_hand].check_hand()
+            PlayerKind::Dealer => self.dealer_play(deck, table, printer),
+            PlayerKind::Player => self.player_play(current_hand, deck, table, printer),
+        }
     }
 
     fn execute_action(
@@ -104,111 +222,133 @@ impl Player {
         current_hand: usize,
         action: Action,
         deck: &mut Deck,
+        rules: &TableRules,
         printer: &impl Printer,
-    ) -> Result<(), String> {
+    ) -> Result<(), Error> {
+        printer.print_message(Message::Action(action), Some(self));
         match action {
             Action::Hit => {
-                println!("{}: Hit!", self.name);
-                let card = deck.deal_card();
-                println!("{} got the card {}", self.name, card);
-                self.add_card_to_hand(card, current_hand);
+                self.deal_card(deck, current_hand, printer);
             }
             Action::Stand => {
-                println!("{}: Stand.", self.name);
-                self.status = PlayerStatus::Standing;
+                self.hands[current_hand].stand();
             }
             Action::DoubleDown => {
-                println!("{}: DOUBLE DOWN!", self.name);
-                let card = deck.deal_card();
-                println!("{} got the card {}", self.name, card);
-                self.add_card_to_hand(card, current_hand);
+                self.deal_card(deck, current_hand, printer);
                 self.pot -= self.hands[current_hand].current_bet();
                 self.hands[current_hand].double_bet();
-                self.status = PlayerStatus::Standing;
+                self.hands[current_hand].stand();
             }
             Action::Split => {
-                println!("{}: Split!", self.name);
                 let hand = &mut self.hands[current_hand];
+                let split_aces = hand.cards()[0].is_ace();
                 let (card, bet) = hand.split();
                 self.new_hand(bet, card)?;
-                let card = deck.deal_card();
-                println!(
-                    "{} got the card {} for their {} hand",
-                    self.name,
-                    card,
-                    current_hand + 1
-                );
-                self.add_card_to_hand(card, current_hand);
-                let card = deck.deal_card();
-                println!(
-                    "{} got the card {} for their {} hand",
-                    self.name,
-                    card,
-                    self.hands.len()
-                );
-                self.add_card_to_hand(card, self.hands.len() - 1);
+                let new_hand = self.hands.len() - 1;
+                self.deal_card(deck, current_hand, printer);
+                self.deal_card(deck, new_hand, printer);
+                if split_aces && !rules.hit_split_aces {
+                    self.hands[current_hand].stand();
+                    self.hands[new_hand].stand();
+                }
             }
             Action::Surrender => {
-                println!("{}: I surrender!", self.name);
-                self.status = PlayerStatus::Lost;
+                self.hands[current_hand].surrender();
             }
         };
         Ok(())
     }
 
-    fn dealer_play(&mut self, deck: &mut Deck, printer: &impl Printer) -> Result<(), String> {
-        match self.hands[0].sum_value() {
-            0..=16 => self.execute_action(0, Action::Hit, deck, printer)?,
-            _ => self.execute_action(0, Action::Stand, deck, printer)?,
+    pub fn deal_card(&mut self, deck: &mut Deck, current_hand: usize, printer: &impl Printer) {
+        let card = deck.deal_card();
+        printer.print_message(Message::CardDealt(card.clone(), current_hand), Some(self));
+        self.add_card_to_hand(card, current_hand);
+    }
+
+    fn dealer_play(
+        &mut self,
+        deck: &mut Deck,
+        table: &TableView,
+        printer: &impl Printer,
+    ) -> Result<Action, Error> {
+        let hand = &self.hands[0];
+        let action = match hand.sum_value() {
+            0..=16 => Action::Hit,
+            17 if hand.is_soft() && table.rules.dealer_hits_soft_17 => Action::Hit,
+            _ => Action::Stand,
         };
-        Ok(())
+        self.execute_action(0, action, deck, table.rules, printer)?;
+        Ok(action)
     }
 
     fn player_play(
         &mut self,
         current_hand: usize,
         deck: &mut Deck,
+        table: &TableView,
         printer: &impl Printer,
-    ) -> Result<(), String> {
+    ) -> Result<Action, Error> {
         let hand = &self.hands[current_hand];
-        let hand_size = hand.size();
-        let hands_count = self.hands.len();
-        let bet = hand.current_bet();
-        let cards_are_equal = hand.cards_are_equal();
-
         let possible_actions: Vec<Action> = Action::iter()
-            .filter_map(|action| {
-                if action.can_execute(hand_size, hands_count, self.pot, bet, cards_are_equal) {
-                    return Some(action);
-                }
-                None
-            })
+            .filter(|action| action.can_execute(hand, self.hands.len(), self.pot, table.rules))
             .collect();
 
-        println!("{}", "*".repeat(90));
-
-        println!(
-            "{}, this is your status for your {} hand:\n{}",
-            self.name,
-            current_hand + 1,
-            self.hands[current_hand]
-        );
-        println!();
-
-        println!("What will you do?");
-        Action::print_actions_list(&possible_actions);
+        printer.print_message(Message::HandStatus(current_hand), Some(self));
 
-        let mut input = String::new();
-        io::stdin()
-            .read_line(&mut input)
-            .expect("Failed to read from stdin");
-        let input = input.trim();
+        let action = loop {
+            printer.print_message(Message::Prompt(possible_actions.clone()), Some(self));
 
-        let action = Action::from_str(input)?;
-        self.execute_action(current_hand, action, deck, printer)?;
-
-        println!("{}", "*".repeat(90));
+            let hand = &self.hands[current_hand];
+            let info = match self
+                .controller
+                .choose_command(hand, table, &possible_actions)
+            {
+                Ok(Command::Action(action)) if possible_actions.contains(&action) => break action,
+                Ok(Command::Action(action)) => {
+                    let error = Error::IllegalAction(action);
+                    printer.print_message(Message::InvalidInput(error), Some(self));
+                    continue;
+                }
+                Ok(Command::Bet(bet)) => {
+                    let error = Error::InvalidAction(bet.to_string());
+                    printer.print_message(Message::InvalidInput(error), Some(self));
+                    continue;
+                }
+                Ok(Command::Help) => Info::Help,
+                Ok(Command::Rules) => Info::Rules(table.rules.clone()),
+                Ok(Command::Bankroll) => Info::Bankroll,
+                Ok(Command::Count) => Info::Count(table.count),
+                Ok(Command::Hint) => {
+                    let values = expected_values(
+                        hand,
+                        table.dealer_upcard,
+                        &Shoe::Finite(table.shoe),
+                        table.rules,
+                    );
+                    Info::Hint(values.ranked(&possible_actions))
+                }
+                Ok(Command::History) => Info::History(table.decisions.to_vec()),
+                Ok(Command::Stats) => {
+                    Info::Stats(table.stats.get(&self.name).cloned().unwrap_or_default())
+                }
+                Ok(Command::Quit) => return Err(Error::Quit),
+                Err(e) if e.is_recoverable() => {
+                    printer.print_message(Message::InvalidInput(e), Some(self));
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            printer.print_message(Message::Info(info), Some(self));
+        };
+        if table.train {
+            let hand = &self.hands[current_hand];
+            let advice = advise(hand, table.dealer_upcard, &possible_actions, table.rules);
+            self.training
+                .record(advice.category, advice.action == action);
+            printer.print_message(Message::Feedback(action, advice), Some(self));
+        }
+        self.execute_action(current_hand, action, deck, table.rules, printer)?;
 
-        Ok(())
+        Ok(action)
     }
 }
@@ -1,10 +1,25 @@
-use crate::{action::Action, player::Player};
+use serde::{Deserialize, Serialize};
 
+use crate::{
+    action::Action,
+    card::Card,
+    error::Error,
+    ev::ActionValue,
+    history::DecisionRecord,
+    kelly::KellyAdvice,
+    player::Player,
+    rules::TableRules,
+    stats::{PlayerStats, Statistics, TrainingStats},
+    strategy::Advice,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Result {
     Tie,
     DealerWon,
     PlayerWon,
     Blackjack,
+    Surrendered,
 }
 
 pub enum Generic {
@@ -12,17 +27,53 @@ pub enum Generic {
     Shuffling,
     Starting,
     Exiting,
+    DealerTurn,
+    DealerBlackjack,
+}
+
+/// Answers to the meta-commands typed at the decision prompt.
+pub enum Info {
+    Help,
+    Rules(TableRules),
+    Bankroll,
+    Count(Option<i32>),
+    /// The allowed plays with their expected values, best first.
+    Hint(Vec<ActionValue>),
+    /// The Kelly bets for the count, at the betting prompt. Only given in
+    /// trainer mode, where the count is shown.
+    Kelly(Option<KellyAdvice>),
+    History(Vec<DecisionRecord>),
+    Stats(PlayerStats),
 }
 
 pub enum Message {
     PlayerStatus,
+    DealerUpcard(Card),
+    HandStatus(usize),
+    /// Asks for a bet, offering the table's usual one.
+    BetPrompt(u32),
+    Prompt(Vec<Action>),
+    CardDealt(Card, usize),
     Generic(Generic),
     Action(Action),
-    Result(Result),
+    Result(Result, usize),
+    InvalidInput(Error),
+    Info(Info),
+    /// Every player's statistics when the session ends.
+    Summary(Statistics),
+    /// The chosen action checked against basic strategy, in train mode.
+    Feedback(Action, Advice),
+    /// A seat's basic strategy record when a train session ends.
+    Training(TrainingStats),
+    /// A seat insured its hand for this stake against a dealer ace.
+    Insurance(u32),
+    /// What a seat's insurance paid back once the hole card was known,
+    /// nothing when the dealer had no natural.
+    InsurancePaid(u32),
 }
 
 pub trait Printer {
     fn print_message(&self, message: Message, player: Option<&Player>);
 
-    fn set_round_settings(&mut self, round: u16);
+    fn set_round_settings(&mut self, round: u32);
 }
@@ -0,0 +1,244 @@
+use std::{
+    env, fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{console_printer::Theme, error::Error, stats::PlayerStats};
+
+/// Per-player preferences applied when their profile takes a seat.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub theme: Option<Theme>,
+}
+
+/// One session played under a profile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    /// Seconds since the Unix epoch at the end of the session.
+    pub ended_at: u64,
+    pub table: String,
+    pub rounds: u32,
+    pub bankroll_before: u32,
+    pub bankroll_after: u32,
+}
+
+/// A named player kept between sessions, with the bankroll they carry from
+/// one session to the next.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub bankroll: u32,
+    /// Table preset or file used when none is given on the command line.
+    #[serde(default)]
+    pub table: Option<String>,
+    #[serde(default)]
+    pub settings: Settings,
+    #[serde(default)]
+    pub sessions: Vec<SessionRecord>,
+    /// Statistics over every session.
+    #[serde(default)]
+    pub stats: PlayerStats,
+}
+
+impl Profile {
+    pub fn new(name: String, bankroll: u32) -> Self {
+        Self {
+            name,
+            bankroll,
+            table: None,
+            settings: Settings::default(),
+            sessions: vec![],
+            stats: PlayerStats::default(),
+        }
+    }
+
+    /// Moves the profile to the bankroll it ended a session with and adds
+    /// the session's statistics to the lifetime ones.
+    pub fn record_session(&mut self, table: &str, rounds: u32, bankroll: u32, stats: &PlayerStats) {
+        let ended_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        self.sessions.push(SessionRecord {
+            ended_at,
+            table: table.into(),
+            rounds,
+            bankroll_before: self.bankroll,
+            bankroll_after: bankroll,
+        });
+        self.bankroll = bankroll;
+        self.stats.merge(stats);
+    }
+
+    pub fn net(&self) -> i64 {
+        self.sessions
+            .iter()
+            .map(|s| s.bankroll_after as i64 - s.bankroll_before as i64)
+            .sum()
+    }
+}
+
+/// `$BLACKJACK_HOME`, or `~/.blackjack` by default, where everything kept
+/// between sessions is stored.
+pub fn home_dir() -> PathBuf {
+    env::var_os("BLACKJACK_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".blackjack")))
+        .unwrap_or_else(|| PathBuf::from(".blackjack"))
+}
+
+/// Reads the JSON file at `path`, none if there is no such file.
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, Error> {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|e| Error::Parse {
+                path: path.into(),
+                line: e.line(),
+                reason: e.to_string(),
+            }),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(source) => Err(Error::File {
+            path: path.into(),
+            source,
+        }),
+    }
+}
+
+/// Writes `value` as pretty JSON to `path`, creating its directory.
+pub fn write_json<T: Serialize>(value: &T, path: &Path) -> Result<(), Error> {
+    let file_error = |source| Error::File {
+        path: path.into(),
+        source,
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(file_error)?;
+    }
+    let contents = serde_json::to_string_pretty(value).map_err(io::Error::from)?;
+    fs::write(path, contents + "\n").map_err(file_error)
+}
+
+/// Profiles stored as one JSON file each in a local directory.
+#[derive(Debug)]
+pub struct ProfileStore {
+    dir: PathBuf,
+}
+
+impl ProfileStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// `profiles` in the blackjack home directory.
+    pub fn default_dir() -> PathBuf {
+        home_dir().join("profiles")
+    }
+
+    pub fn load(&self, name: &str) -> Result<Option<Profile>, Error> {
+        read_json(&self.path(name))
+    }
+
+    /// Loads `name`, creating it with `bankroll` on first use.
+    pub fn load_or_create(&self, name: &str, bankroll: u32) -> Result<Profile, Error> {
+        match self.load(name)? {
+            Some(profile) => Ok(profile),
+            None => {
+                let profile = Profile::new(name.into(), bankroll);
+                self.save(&profile)?;
+                Ok(profile)
+            }
+        }
+    }
+
+    pub fn save(&self, profile: &Profile) -> Result<(), Error> {
+        write_json(profile, &self.path(&profile.name))
+    }
+
+    pub fn list(&self) -> Result<Vec<Profile>, Error> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(source) => {
+                return Err(Error::File {
+                    path: self.dir.clone(),
+                    source,
+                })
+            }
+        };
+        let mut profiles = vec![];
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                profiles.extend(self.load(&name)?);
+            }
+        }
+        profiles.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(profiles)
+    }
+
+    /// Profile names are free text, file names keep only the safe part.
+    fn path(&self, name: &str) -> PathBuf {
+        let file_name: String = name
+            .to_lowercase()
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(file_name + ".json")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    fn store(test: &str) -> ProfileStore {
+        let dir = env::temp_dir().join(format!("blackjack-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        ProfileStore::new(dir)
+    }
+
+    #[rstest]
+    fn profiles_are_created_once_and_written_back() {
+        let store = store("profiles-written-back");
+        let mut profile = store.load_or_create("Ana Lee", 500).unwrap();
+        let stats = PlayerStats {
+            hands: 12,
+            ..PlayerStats::default()
+        };
+        profile.record_session("standard", 10, 560, &stats);
+        store.save(&profile).unwrap();
+
+        let loaded = store.load_or_create("Ana Lee", 100).unwrap();
+        assert_eq!(loaded.bankroll, 560);
+        assert_eq!(loaded.net(), 60);
+        assert_eq!(loaded.sessions[0].bankroll_before, 500);
+        assert_eq!(loaded.stats.hands, 12);
+        assert_eq!(store.list().unwrap(), vec![loaded]);
+    }
+
+    #[rstest]
+    fn missing_profile_is_none() {
+        assert_eq!(store("missing-profile").load("Bo").unwrap(), None);
+    }
+
+    #[rstest]
+    #[case::spaces("Ana Lee", "ana_lee.json")]
+    #[case::path_separators("../etc/passwd", "___etc_passwd.json")]
+    fn file_names_are_sanitized(#[case] name: &str, #[case] file_name: &str) {
+        let store = ProfileStore::new(PathBuf::from("profiles"));
+        assert_eq!(store.path(name), Path::new("profiles").join(file_name));
+    }
+}
@@ -0,0 +1,572 @@
+//! A policy learned by playing rather than derived: Monte Carlo control with
+//! exploring starts, trained against the [`Environment`], so the values it
+//! settles on come from the engine's own payouts. Comparing the result with
+//! basic strategy checks both.
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+use crate::{
+    action::Action,
+    chart::{StrategyChart, UPCARDS},
+    command::Command,
+    controller::{Controller, ControllerKind, TableView},
+    env::{Environment, Observation},
+    error::Error,
+    hand::Hand,
+    rules::TableRules,
+    strategy::{basic_strategy, Play},
+    table::{SeatConfig, TableConfig},
+};
+
+/// What a hand is learned by: its total, whether it is soft or a pair that
+/// can still be split, and the dealer's upcard, 11 for an ace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct State {
+    pub total: u8,
+    pub soft: bool,
+    pub pair: bool,
+    pub upcard: u8,
+}
+
+impl State {
+    pub fn of(observation: &Observation) -> Self {
+        Self {
+            total: observation.total,
+            soft: observation.soft,
+            pair: observation.legal_actions.contains(&Action::Split),
+            upcard: observation.dealer_upcard,
+        }
+    }
+
+    fn of_hand(hand: &Hand, upcard: u8, possible_actions: &[Action]) -> Self {
+        Self {
+            total: hand.sum_value(),
+            soft: hand.is_soft(),
+            pair: possible_actions.contains(&Action::Split),
+            upcard,
+        }
+    }
+}
+
+/// The average reward, in bets, of taking an action in a state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Estimate {
+    pub value: f64,
+    pub visits: u64,
+}
+
+/// Learned action values for every state met in training.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "QTableFile", into = "QTableFile")]
+pub struct QTable {
+    /// The rules and shoe trained at, which the policy is compared for.
+    pub rules: TableRules,
+    pub decks: u8,
+    pub episodes: u64,
+    values: BTreeMap<State, BTreeMap<Action, Estimate>>,
+}
+
+/// A Q-table as written to file, with a list of states rather than a map
+/// JSON cannot key by.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct QTableFile {
+    rules: TableRules,
+    decks: u8,
+    episodes: u64,
+    states: Vec<StateValues>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StateValues {
+    #[serde(flatten)]
+    state: State,
+    actions: BTreeMap<Action, Estimate>,
+}
+
+impl From<QTableFile> for QTable {
+    fn from(file: QTableFile) -> Self {
+        Self {
+            rules: file.rules,
+            decks: file.decks,
+            episodes: file.episodes,
+            values: file
+                .states
+                .into_iter()
+                .map(|s| (s.state, s.actions))
+                .collect(),
+        }
+    }
+}
+
+impl From<QTable> for QTableFile {
+    fn from(table: QTable) -> Self {
+        Self {
+            rules: table.rules,
+            decks: table.decks,
+            episodes: table.episodes,
+            states: table
+                .values
+                .into_iter()
+                .map(|(state, actions)| StateValues { state, actions })
+                .collect(),
+        }
+    }
+}
+
+impl QTable {
+    /// An empty table for `config`'s rules and shoe.
+    pub fn new(config: &TableConfig) -> Self {
+        Self {
+            rules: config.rules.clone(),
+            decks: config.shoe.decks,
+            episodes: 0,
+            values: BTreeMap::new(),
+        }
+    }
+
+    /// Plays `episodes` rounds at `config`'s table, reproducibly from
+    /// `seed`. The first decision of each round is picked at random and the
+    /// rest by the values so far, every one of them then moved towards the
+    /// round's reward. Fails with [`Error::GameStopped`] if the game under
+    /// the environment dies.
+    pub fn train(config: &TableConfig, episodes: u64, seed: u64) -> Result<Self, Error> {
+        let mut table = Self::new(config);
+        let mut env = Environment::new(config.clone()).with_seed(seed);
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut visited = vec![];
+        for _ in 0..episodes {
+            visited.clear();
+            let mut step = env.reset()?;
+            while let Some(observation) = &step.observation {
+                let state = State::of(observation);
+                let action = if visited.is_empty() {
+                    *observation.legal_actions.choose(&mut rng).unwrap()
+                } else {
+                    table.greedy(state, &observation.legal_actions)
+                };
+                visited.push((state, action));
+                step = env.step(action)?;
+            }
+            for &(state, action) in &visited {
+                let estimate = table
+                    .values
+                    .entry(state)
+                    .or_default()
+                    .entry(action)
+                    .or_default();
+                estimate.visits += 1;
+                estimate.value += (step.reward - estimate.value) / estimate.visits as f64;
+            }
+            table.episodes += 1;
+        }
+        Ok(table)
+    }
+
+    /// The legal action to learn from next: any not tried yet in `state`,
+    /// else the best.
+    fn greedy(&self, state: State, legal_actions: &[Action]) -> Action {
+        let tried = self.values.get(&state);
+        legal_actions
+            .iter()
+            .find(|a| tried.is_none_or(|tried| !tried.contains_key(a)))
+            .copied()
+            .or_else(|| self.best(state, legal_actions))
+            .unwrap_or(Action::Stand)
+    }
+
+    /// The legal action with the best value in `state`, if any was tried.
+    pub fn best(&self, state: State, legal_actions: &[Action]) -> Option<Action> {
+        let estimates = self.values.get(&state)?;
+        legal_actions
+            .iter()
+            .filter_map(|a| estimates.get(a).map(|e| (*a, e.value)))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(action, _)| action)
+    }
+
+    /// The chart play learned for `state`: the best action tried, with the
+    /// better of hitting and standing to fall back on from a double or a
+    /// surrender. None if the state was never played.
+    pub fn play(&self, state: State) -> Option<Play> {
+        let estimates = self.values.get(&state)?;
+        let value = |action| estimates.get(&action).map(|e| e.value);
+        let (&best, _) = estimates
+            .iter()
+            .max_by(|(_, a), (_, b)| a.value.total_cmp(&b.value))?;
+        let hit = value(Action::Hit) > value(Action::Stand);
+        Some(match (best, hit) {
+            (Action::Hit, _) => Play::Hit,
+            (Action::Stand, _) => Play::Stand,
+            (Action::Split, _) => Play::Split,
+            (Action::DoubleDown, true) => Play::DoubleOrHit,
+            (Action::DoubleDown, false) => Play::DoubleOrStand,
+            (Action::Surrender, true) => Play::SurrenderOrHit,
+            (Action::Surrender, false) => Play::SurrenderOrStand,
+        })
+    }
+
+    /// Reads a table saved by [`Self::save`].
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path).map_err(|source| Error::File {
+            path: path.into(),
+            source,
+        })?;
+        serde_json::from_str(&contents).map_err(|e| Error::Parse {
+            path: path.into(),
+            line: e.line(),
+            reason: e.to_string(),
+        })
+    }
+
+    /// Writes the table as JSON.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let json = serde_json::to_string_pretty(self).expect("a Q-table serializes");
+        fs::write(path, json + "\n").map_err(|source| Error::File {
+            path: path.into(),
+            source,
+        })
+    }
+
+    /// Every cell of a basic strategy chart, normally the one for the
+    /// table's rules and shoe, next to what was learned for it.
+    pub fn compare(&self, chart: &StrategyChart) -> Comparison {
+        let sections = [
+            (Section::Hard, &chart.hard),
+            (Section::Soft, &chart.soft),
+            (Section::Pair, &chart.pairs),
+        ];
+        let every_action: Vec<Action> = Action::iter().collect();
+        let mut cells = vec![];
+        for (section, rows) in sections {
+            for (&value, plays) in rows {
+                for (&upcard, &basic) in UPCARDS.iter().zip(plays) {
+                    let state = section.state(value, upcard);
+                    let learned = self.play(state);
+                    let estimates = self.values.get(&state);
+                    let basic_value = estimates
+                        .and_then(|e| e.get(&basic.resolve(&every_action)))
+                        .map(|e| e.value);
+                    let learned_value =
+                        estimates.and_then(|e| e.values().map(|e| e.value).max_by(f64::total_cmp));
+                    cells.push(Cell {
+                        section,
+                        value,
+                        upcard,
+                        basic,
+                        learned,
+                        gap: learned_value.zip(basic_value).map(|(l, b)| l - b),
+                        visits: estimates.map_or(0, |e| e.values().map(|e| e.visits).sum()),
+                    });
+                }
+            }
+        }
+        Comparison {
+            episodes: self.episodes,
+            cells,
+        }
+    }
+}
+
+/// A part of the strategy chart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Section {
+    Hard,
+    Soft,
+    Pair,
+}
+
+impl Section {
+    /// The state a chart row stands for, pairs by their rank.
+    fn state(&self, value: u8, upcard: u8) -> State {
+        let (total, soft, pair) = match self {
+            Section::Hard => (value, false, false),
+            Section::Soft => (value, true, false),
+            Section::Pair if value == 11 => (12, true, true),
+            Section::Pair => (2 * value, false, true),
+        };
+        State {
+            total,
+            soft,
+            pair,
+            upcard,
+        }
+    }
+}
+
+/// One chart cell, as basic strategy and the learned policy play it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Cell {
+    pub section: Section,
+    /// The hard or soft total, or a pair's rank with aces as 11.
+    pub value: u8,
+    pub upcard: u8,
+    pub basic: Play,
+    pub learned: Option<Play>,
+    /// How much better the learned play did than the basic one, in bets.
+    pub gap: Option<f64>,
+    pub visits: u64,
+}
+
+impl Cell {
+    pub fn agrees(&self) -> bool {
+        self.learned == Some(self.basic)
+    }
+}
+
+/// The learned policy against basic strategy, cell by cell.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Comparison {
+    pub episodes: u64,
+    pub cells: Vec<Cell>,
+}
+
+impl Comparison {
+    pub fn agreeing(&self) -> usize {
+        self.cells.iter().filter(|c| c.agrees()).count()
+    }
+}
+
+/// The learned chart with disagreements marked, then every disagreement
+/// with the values behind it.
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let header: String = UPCARDS.map(|u| format!("{:>5}", rank(u))).concat();
+        for section in [Section::Hard, Section::Soft, Section::Pair] {
+            let title = match section {
+                Section::Hard => "Hard",
+                Section::Soft => "Soft",
+                Section::Pair => "Pairs",
+            };
+            writeln!(f, "{:<6}{}", title, header)?;
+            let rows = self.cells.iter().filter(|c| c.section == section);
+            for row in rows.collect::<Vec<_>>().chunks(UPCARDS.len()) {
+                let label = match section {
+                    Section::Hard => row[0].value.to_string(),
+                    Section::Soft => format!("A,{}", row[0].value - 11),
+                    Section::Pair => format!("{0},{0}", rank(row[0].value)),
+                };
+                let cells: String = row
+                    .iter()
+                    .map(|c| {
+                        let play = c.learned.map_or("?".into(), |p| p.to_string());
+                        let mark = if c.agrees() { " " } else { "*" };
+                        format!("{:>4}{}", play, mark)
+                    })
+                    .collect();
+                writeln!(f, "{:>5} {}", label, cells)?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(
+            f,
+            "Agrees with basic strategy on {} of {} cells after {} episodes, * marks the others:",
+            self.agreeing(),
+            self.cells.len(),
+            self.episodes
+        )?;
+        for c in self.cells.iter().filter(|c| !c.agrees()) {
+            let hand = match c.section {
+                Section::Hard => format!("hard {}", c.value),
+                Section::Soft => format!("soft {}", c.value),
+                Section::Pair => format!("pair of {}", rank(c.value)),
+            };
+            let learned = c.learned.map_or("nothing".into(), |p| p.to_string());
+            write!(
+                f,
+                "  {} against {}: learned {}, basic {}",
+                hand,
+                rank(c.upcard),
+                learned,
+                c.basic
+            )?;
+            if let Some(gap) = c.gap {
+                write!(f, ", {:+.3} bets", gap)?;
+            }
+            writeln!(f, " over {} visits", c.visits)?;
+        }
+        Ok(())
+    }
+}
+
+fn rank(value: u8) -> String {
+    match value {
+        11 => "A".to_string(),
+        value => value.to_string(),
+    }
+}
+
+/// Plays every hand by a learned Q-table, falling back on basic strategy in
+/// states it never met.
+#[derive(Debug)]
+pub struct QTableController {
+    table: QTable,
+    path: PathBuf,
+}
+
+impl QTableController {
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        Ok(Self {
+            table: QTable::load(path)?,
+            path: path.into(),
+        })
+    }
+}
+
+impl Controller for QTableController {
+    fn choose_command(
+        &mut self,
+        hand: &Hand,
+        table: &TableView,
+        possible_actions: &[Action],
+    ) -> Result<Command, Error> {
+        let state = State::of_hand(hand, table.dealer_upcard.value(0), possible_actions);
+        let action = self.table.best(state, possible_actions).unwrap_or_else(|| {
+            basic_strategy(hand, table.dealer_upcard, possible_actions, table.rules)
+        });
+        Ok(Command::Action(action))
+    }
+
+    fn kind(&self) -> Option<ControllerKind> {
+        Some(ControllerKind::QTable)
+    }
+
+    fn configure(&self, seat: &mut SeatConfig) {
+        seat.q_table = Some(self.path.clone());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{card::Card, dealer::Composition, stats::Statistics};
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+    use std::env;
+
+    fn hand(codes: &[&str]) -> Hand {
+        let mut hand = Hand::new(1, None);
+        for code in codes {
+            hand.add_card_to_hand(code.parse().unwrap());
+        }
+        hand
+    }
+
+    fn state(total: u8, soft: bool, pair: bool, upcard: u8) -> State {
+        State {
+            total,
+            soft,
+            pair,
+            upcard,
+        }
+    }
+
+    /// A table that knows hard 12 against a 4 only.
+    fn twelve_against_four() -> QTable {
+        let mut table = QTable::new(&TableConfig::default());
+        let estimate = |value| Estimate { value, visits: 10 };
+        table.values.insert(
+            state(12, false, false, 4),
+            BTreeMap::from([
+                (Action::Hit, estimate(-0.2)),
+                (Action::Stand, estimate(-0.3)),
+                (Action::DoubleDown, estimate(-0.1)),
+            ]),
+        );
+        table
+    }
+
+    #[rstest]
+    fn learns_to_stand_on_nineteen_and_twenty() {
+        let table = QTable::train(&TableConfig::default(), 20_000, 1).unwrap();
+        assert_eq!(table.episodes, 20_000);
+        let comparison = table.compare(&StrategyChart::generate(&table.rules, None));
+        assert_eq!(comparison.cells.len(), (16 + 8 + 10) * 10);
+        assert!(comparison
+            .cells
+            .iter()
+            .filter(|c| c.section == Section::Hard && c.value >= 19)
+            .all(Cell::agrees));
+    }
+
+    #[rstest]
+    fn training_is_reproducible() {
+        let first = QTable::train(&TableConfig::default(), 2_000, 9).unwrap();
+        assert_eq!(
+            first,
+            QTable::train(&TableConfig::default(), 2_000, 9).unwrap()
+        );
+        assert_ne!(
+            first,
+            QTable::train(&TableConfig::default(), 2_000, 10).unwrap()
+        );
+    }
+
+    #[rstest]
+    fn saved_tables_load_back() {
+        let table = QTable::train(&TableConfig::default(), 500, 3).unwrap();
+        let path = env::temp_dir().join(format!("blackjack-q-table-{}.json", std::process::id()));
+        table.save(&path).unwrap();
+        assert_eq!(QTable::load(&path).unwrap(), table);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[rstest]
+    fn reads_plays_with_their_fallback() {
+        let table = twelve_against_four();
+        assert_eq!(
+            table.play(state(12, false, false, 4)),
+            Some(Play::DoubleOrHit)
+        );
+        assert_eq!(table.play(state(12, false, false, 5)), None);
+        let cell = table
+            .compare(&StrategyChart::generate(&table.rules, None))
+            .cells
+            .into_iter()
+            .find(|c| c.section == Section::Hard && c.value == 12 && c.upcard == 4)
+            .unwrap();
+        assert_eq!((cell.learned, cell.visits), (Some(Play::DoubleOrHit), 30));
+        assert!(!cell.agrees());
+    }
+
+    #[rstest]
+    #[case::learned(&["10h", "2s"], "4d", &[Action::Hit, Action::Stand], Action::Hit)]
+    #[case::learned_double(&["10h", "2s"], "4d", &[Action::Hit, Action::Stand, Action::DoubleDown], Action::DoubleDown)]
+    #[case::basic_strategy(&["10h", "6s"], "10d", &[Action::Hit, Action::Stand], Action::Hit)]
+    fn plays_the_learned_values_or_basic_strategy(
+        #[case] codes: &[&str],
+        #[case] upcard: &str,
+        #[case] possible_actions: &[Action],
+        #[case] expected: Action,
+    ) {
+        let mut controller = QTableController {
+            table: twelve_against_four(),
+            path: "q.json".into(),
+        };
+        let upcard: Card = upcard.parse().unwrap();
+        let table = TableView {
+            rules: &TableRules::default(),
+            dealer_upcard: &upcard,
+            decisions: &[],
+            count: None,
+            shoe: Composition::full(1),
+            decks: 1,
+            stats: &Statistics::default(),
+            train: false,
+        };
+        let command = controller
+            .choose_command(&hand(codes), &table, possible_actions)
+            .unwrap();
+        assert_eq!(command, Command::Action(expected));
+    }
+}
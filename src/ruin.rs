@@ -0,0 +1,197 @@
+//! Risk of ruin: many independent sessions from the same bankroll, and where
+//! the bankroll ends up across them.
+
+use std::{fs, path::Path};
+
+use serde::Serialize;
+
+use crate::{
+    error::Error, game::Game, player::Player, silent_printer::SilentPrinter, table::TableConfig,
+};
+
+/// The percentiles reported alongside the median.
+pub const PERCENTILES: [u8; 4] = [5, 25, 75, 95];
+
+/// Every session's bankroll after each round.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sessions {
+    /// The smallest bet, below which a bankroll is ruined.
+    pub min_bet: u32,
+    /// The starting bankroll then one entry per round. A session that went
+    /// broke stays at what it had left.
+    pub trajectories: Vec<Vec<u32>>,
+}
+
+/// A range of final bankrolls and how many sessions ended in it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Bin {
+    pub from: u32,
+    pub to: u32,
+    pub sessions: usize,
+}
+
+impl Sessions {
+    /// Plays `sessions` sessions of `hands` rounds at `config`'s table, each
+    /// with a new seat from `seat` and its own shoe seed counted up from
+    /// `seed`.
+    pub fn simulate(
+        config: &TableConfig,
+        sessions: u32,
+        hands: u32,
+        seed: u64,
+        seat: impl Fn() -> Player,
+    ) -> Self {
+        let trajectories = (0..sessions)
+            .map(|i| {
+                let player = seat();
+                let bankroll = player.pot;
+                let mut game = Game::new(hands, SilentPrinter)
+                    .with_rules(config.rules.clone())
+                    .with_shoe(&config.shoe)
+                    .with_limits(config.limits.clone())
+                    .with_players(vec![player])
+                    .with_seed(seed.wrapping_add(i as u64));
+                let mut trajectory = Vec::with_capacity(hands as usize + 1);
+                trajectory.push(bankroll);
+                for _ in 0..hands {
+                    game.play_round();
+                    trajectory.push(game.players()[0].pot);
+                }
+                trajectory
+            })
+            .collect();
+        Self {
+            min_bet: config.limits.min_bet,
+            trajectories,
+        }
+    }
+
+    /// Final bankrolls, smallest first.
+    pub fn finals(&self) -> Vec<u32> {
+        let mut finals: Vec<u32> = self
+            .trajectories
+            .iter()
+            .map(|t| *t.last().unwrap())
+            .collect();
+        finals.sort_unstable();
+        finals
+    }
+
+    /// Sessions left unable to cover the table minimum.
+    pub fn ruined(&self) -> usize {
+        self.finals().iter().filter(|&&f| f < self.min_bet).count()
+    }
+
+    pub fn risk_of_ruin(&self) -> f64 {
+        self.ruined() as f64 / self.trajectories.len().max(1) as f64
+    }
+
+    /// The final bankroll that `percent` percent of sessions ended at or
+    /// below, by nearest rank. None without any sessions.
+    pub fn percentile(&self, percent: u8) -> Option<u32> {
+        let finals = self.finals();
+        let rank = (percent as f64 / 100.0 * finals.len() as f64).ceil() as usize;
+        let last = finals.len().checked_sub(1)?;
+        Some(finals[rank.saturating_sub(1).min(last)])
+    }
+
+    /// Final bankrolls in `bins` ranges of equal width, from nothing to the
+    /// best session.
+    pub fn histogram(&self, bins: usize) -> Vec<Bin> {
+        let finals = self.finals();
+        let top = finals.last().copied().unwrap_or(0) + 1;
+        let width = top.div_ceil(bins as u32).max(1);
+        (0..bins as u32)
+            .map(|i| {
+                let (from, to) = (i * width, (i + 1) * width);
+                Bin {
+                    from,
+                    to,
+                    sessions: finals.iter().filter(|&&f| (from..to).contains(&f)).count(),
+                }
+            })
+            .collect()
+    }
+
+    /// One `session,round,bankroll` line per session and round, round 0
+    /// being the start.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("session,round,bankroll\n");
+        for (session, trajectory) in self.trajectories.iter().enumerate() {
+            for (round, bankroll) in trajectory.iter().enumerate() {
+                csv += &format!("{},{},{}\n", session + 1, round, bankroll);
+            }
+        }
+        csv
+    }
+
+    pub fn write_csv(&self, path: &Path) -> Result<(), Error> {
+        fs::write(path, self.to_csv()).map_err(|source| Error::File {
+            path: path.into(),
+            source,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{controller::BasicStrategyController, player::PlayerKind};
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    fn sessions(finals: &[u32]) -> Sessions {
+        Sessions {
+            min_bet: 5,
+            trajectories: finals.iter().map(|&f| vec![100, f]).collect(),
+        }
+    }
+
+    #[rstest]
+    fn reports_ruin_and_percentiles() {
+        let sessions = sessions(&[0, 180, 3, 120, 90, 60, 150, 100, 110, 70]);
+        assert_eq!(sessions.ruined(), 2);
+        assert_eq!(sessions.risk_of_ruin(), 0.2);
+        assert_eq!(sessions.percentile(50), Some(90));
+        assert_eq!(sessions.percentile(5), Some(0));
+        assert_eq!(sessions.percentile(95), Some(180));
+    }
+
+    #[rstest]
+    fn no_sessions_have_no_percentiles() {
+        let sessions = sessions(&[]);
+        assert_eq!(sessions.percentile(50), None);
+        assert_eq!(sessions.risk_of_ruin(), 0.0);
+    }
+
+    #[rstest]
+    fn histogram_covers_every_session() {
+        let histogram = sessions(&[0, 180, 3, 120, 90, 60, 150, 100, 110, 70]).histogram(4);
+        let counts: Vec<usize> = histogram.iter().map(|bin| bin.sessions).collect();
+        assert_eq!(counts, vec![2, 3, 3, 2]);
+        assert_eq!((histogram[0].from, histogram[3].to), (0, 184));
+    }
+
+    #[rstest]
+    fn csv_has_a_line_per_round() {
+        let csv = sessions(&[0, 180]).to_csv();
+        assert_eq!(
+            csv,
+            "session,round,bankroll\n1,0,100\n1,1,0\n2,0,100\n2,1,180\n"
+        );
+    }
+
+    #[rstest]
+    fn sessions_are_reproducible() {
+        let seat = || {
+            Player::new("Ana".into(), 200, PlayerKind::Player)
+                .with_controller(Box::new(BasicStrategyController))
+        };
+        let config = TableConfig::default();
+        let first = Sessions::simulate(&config, 5, 50, 7, seat);
+        assert_eq!(first, Sessions::simulate(&config, 5, 50, 7, seat));
+        assert_eq!(first.trajectories.len(), 5);
+        assert!(first.trajectories.iter().all(|t| t.len() == 51));
+        assert!(first.trajectories.windows(2).any(|w| w[0] != w[1]));
+    }
+}
@@ -0,0 +1,180 @@
+use std::{fmt, str::FromStr};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// How much a winning hand is paid per unit bet, e.g. `3:2`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Payout {
+    pub numerator: u32,
+    pub denominator: u32,
+}
+
+impl Payout {
+    pub const fn new(numerator: u32, denominator: u32) -> Self {
+        Self {
+            numerator,
+            denominator,
+        }
+    }
+
+    /// Winnings for `bet`, rounded up in the player's favour.
+    pub fn winnings(&self, bet: u32) -> u32 {
+        (bet * self.numerator).div_ceil(self.denominator)
+    }
+}
+
+impl fmt::Display for Payout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.numerator, self.denominator)
+    }
+}
+
+impl FromStr for Payout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("{:?} is not a payout like \"3:2\"", s);
+        let (numerator, denominator) = s.split_once(':').ok_or_else(invalid)?;
+        let numerator = numerator.trim().parse().map_err(|_| invalid())?;
+        let denominator = denominator.trim().parse().map_err(|_| invalid())?;
+        if denominator == 0 {
+            return Err(invalid());
+        }
+        Ok(Payout::new(numerator, denominator))
+    }
+}
+
+impl Serialize for Payout {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Payout {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let payout = String::deserialize(deserializer)?;
+        payout.parse().map_err(de::Error::custom)
+    }
+}
+
+/// Which first two card totals may be doubled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DoubleOn {
+    #[default]
+    #[serde(rename = "any")]
+    Any,
+    #[serde(rename = "9-11")]
+    NineToEleven,
+    #[serde(rename = "10-11")]
+    TenToEleven,
+}
+
+impl DoubleOn {
+    pub fn allows(&self, total: u8) -> bool {
+        match self {
+            DoubleOn::Any => true,
+            DoubleOn::NineToEleven => (9..=11).contains(&total),
+            DoubleOn::TenToEleven => (10..=11).contains(&total),
+        }
+    }
+}
+
+impl fmt::Display for DoubleOn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DoubleOn::Any => write!(f, "any first two cards"),
+            DoubleOn::NineToEleven => write!(f, "9, 10 and 11 only"),
+            DoubleOn::TenToEleven => write!(f, "10 and 11 only"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TableRules {
+    pub dealer_hits_soft_17: bool,
+    /// Whether the dealer takes a hole card and checks it for a natural
+    /// before anyone plays. Without it, as in Europe, the second dealer card
+    /// is only drawn after every seat has played.
+    pub dealer_peeks: bool,
+    pub blackjack_payout: Payout,
+    pub double_on: DoubleOn,
+    pub double_after_split: bool,
+    pub surrender: bool,
+    /// How many hands a seat may end up with by splitting.
+    pub max_hands: u8,
+    /// Only matters when `hit_split_aces` is set, as split aces otherwise
+    /// stand on the one card they are dealt.
+    pub resplit_aces: bool,
+    pub hit_split_aces: bool,
+}
+
+impl Default for TableRules {
+    fn default() -> Self {
+        Self {
+            dealer_hits_soft_17: false,
+            dealer_peeks: true,
+            blackjack_payout: Payout::new(3, 2),
+            double_on: DoubleOn::Any,
+            double_after_split: true,
+            surrender: true,
+            max_hands: 4,
+            resplit_aces: false,
+            hit_split_aces: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    #[case::three_to_two(Payout::new(3, 2), 20, 30)]
+    #[case::six_to_five(Payout::new(6, 5), 20, 24)]
+    #[case::rounds_up(Payout::new(3, 2), 5, 8)]
+    #[case::even_money(Payout::new(1, 1), 20, 20)]
+    fn payout_winnings(#[case] payout: Payout, #[case] bet: u32, #[case] winnings: u32) {
+        assert_eq!(payout.winnings(bet), winnings);
+    }
+
+    #[rstest]
+    #[case::valid("6:5", Some(Payout::new(6, 5)))]
+    #[case::spaces(" 3 : 2 ", Some(Payout::new(3, 2)))]
+    #[case::missing_colon("1.5", None)]
+    #[case::zero_denominator("3:0", None)]
+    fn payout_from_str(#[case] input: &str, #[case] expected: Option<Payout>) {
+        assert_eq!(Payout::from_str(input).ok(), expected);
+    }
+
+    #[rstest]
+    fn rules_from_toml_fill_defaults() {
+        let rules: TableRules =
+            toml::from_str("dealer_hits_soft_17 = true\nblackjack_payout = \"6:5\"").unwrap();
+        assert_eq!(
+            rules,
+            TableRules {
+                dealer_hits_soft_17: true,
+                blackjack_payout: Payout::new(6, 5),
+                ..TableRules::default()
+            }
+        );
+    }
+
+    #[rstest]
+    #[case::any(DoubleOn::Any, 17, true)]
+    #[case::nine(DoubleOn::NineToEleven, 9, true)]
+    #[case::eight(DoubleOn::NineToEleven, 8, false)]
+    #[case::ten(DoubleOn::TenToEleven, 10, true)]
+    #[case::nine_on_ten_to_eleven(DoubleOn::TenToEleven, 9, false)]
+    fn double_on_allows(#[case] double_on: DoubleOn, #[case] total: u8, #[case] expected: bool) {
+        assert_eq!(double_on.allows(total), expected);
+    }
+
+    #[rstest]
+    fn rules_from_toml_reject_unknown_keys() {
+        assert!(toml::from_str::<TableRules>("hit_soft_17 = true").is_err());
+    }
+}
@@ -0,0 +1,69 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    betting::Betting, counting::CountingSystem, deck::ShoeState, error::Error, table::TableConfig,
+};
+
+/// Bumped whenever the layout of a save file changes.
+pub const SAVE_VERSION: u32 = 1;
+
+/// A paused session: the table it is played at with every seat's current
+/// bankroll, the rounds played so far and the shoe as it was left.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveFile {
+    pub version: u32,
+    pub played_rounds: u32,
+    pub table: TableConfig,
+    pub counting: Option<CountingSystem>,
+    #[serde(default)]
+    pub train: bool,
+    pub shoe: ShoeState,
+    /// Where each seat's betting progression stands, in seat order.
+    #[serde(default)]
+    pub betting: Vec<Option<Betting>>,
+}
+
+impl SaveFile {
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let file_error = |source| Error::File {
+            path: path.into(),
+            source,
+        };
+        let mut writer = BufWriter::new(File::create(path).map_err(file_error)?);
+        serde_json::to_writer_pretty(&mut writer, self).map_err(io::Error::from)?;
+        writeln!(writer)?;
+        Ok(writer.flush()?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path).map_err(|source| Error::File {
+            path: path.into(),
+            source,
+        })?;
+        let parse_error = |e: serde_json::Error| Error::Parse {
+            path: path.into(),
+            line: e.line(),
+            reason: e.to_string(),
+        };
+
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+        let Version { version } = serde_json::from_str(&contents).map_err(parse_error)?;
+        if version != SAVE_VERSION {
+            return Err(Error::UnsupportedVersion {
+                path: path.into(),
+                version: version.into(),
+                supported: SAVE_VERSION.into(),
+            });
+        }
+        serde_json::from_str(&contents).map_err(parse_error)
+    }
+}
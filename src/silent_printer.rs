@@ -0,0 +1,13 @@
+use crate::{
+    player::Player,
+    printer::{Message, Printer},
+};
+
+/// Discards every message, for simulations that only care about results.
+pub struct SilentPrinter;
+
+impl Printer for SilentPrinter {
+    fn set_round_settings(&mut self, _round: u32) {}
+
+    fn print_message(&self, _message: Message, _player: Option<&Player>) {}
+}
@@ -0,0 +1,348 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    action::Action, hand::Hand, history::RoundRecord, printer::Result as RoundResult,
+    strategy::HandCategory,
+};
+
+/// Running totals for one player, built from settled rounds.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerStats {
+    pub rounds: u32,
+    pub hands: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub pushes: u32,
+    pub surrenders: u32,
+    pub naturals: u32,
+    pub busts: u32,
+    pub doubles: u32,
+    pub doubles_won: u32,
+    pub splits: u32,
+    pub split_hands: u32,
+    pub split_hands_won: u32,
+    pub wagered: u64,
+    pub net: i64,
+    /// Best and worst single round, all hands together.
+    pub biggest_win: i64,
+    pub biggest_loss: i64,
+}
+
+impl PlayerStats {
+    pub fn win_rate(&self) -> Option<f64> {
+        rate(self.wins, self.hands)
+    }
+
+    pub fn double_win_rate(&self) -> Option<f64> {
+        rate(self.doubles_won, self.doubles)
+    }
+
+    pub fn split_win_rate(&self) -> Option<f64> {
+        rate(self.split_hands_won, self.split_hands)
+    }
+
+    /// Adds a session's totals to lifetime ones.
+    pub fn merge(&mut self, other: &PlayerStats) {
+        self.rounds += other.rounds;
+        self.hands += other.hands;
+        self.wins += other.wins;
+        self.losses += other.losses;
+        self.pushes += other.pushes;
+        self.surrenders += other.surrenders;
+        self.naturals += other.naturals;
+        self.busts += other.busts;
+        self.doubles += other.doubles;
+        self.doubles_won += other.doubles_won;
+        self.splits += other.splits;
+        self.split_hands += other.split_hands;
+        self.split_hands_won += other.split_hands_won;
+        self.wagered += other.wagered;
+        self.net += other.net;
+        self.biggest_win = self.biggest_win.max(other.biggest_win);
+        self.biggest_loss = self.biggest_loss.min(other.biggest_loss);
+    }
+}
+
+fn rate(part: u32, total: u32) -> Option<f64> {
+    (total > 0).then(|| part as f64 / total as f64)
+}
+
+/// How closely a seat followed basic strategy in train mode, with the
+/// mistakes split by the part of the chart they were made in.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrainingStats {
+    pub decisions: u32,
+    pub hard_mistakes: u32,
+    pub soft_mistakes: u32,
+    pub pair_mistakes: u32,
+}
+
+impl TrainingStats {
+    pub fn record(&mut self, category: HandCategory, correct: bool) {
+        self.decisions += 1;
+        if !correct {
+            match category {
+                HandCategory::Hard => self.hard_mistakes += 1,
+                HandCategory::Soft => self.soft_mistakes += 1,
+                HandCategory::Pair => self.pair_mistakes += 1,
+            }
+        }
+    }
+
+    pub fn mistakes(&self) -> u32 {
+        self.hard_mistakes + self.soft_mistakes + self.pair_mistakes
+    }
+
+    pub fn accuracy(&self) -> Option<f64> {
+        rate(self.decisions - self.mistakes(), self.decisions)
+    }
+}
+
+/// Statistics for every player seen this session, in seating order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Statistics {
+    players: Vec<(String, PlayerStats)>,
+}
+
+impl Statistics {
+    pub fn get(&self, name: &str) -> Option<&PlayerStats> {
+        self.players
+            .iter()
+            .find(|(player, _)| player == name)
+            .map(|(_, stats)| stats)
+    }
+
+    pub fn players(&self) -> &[(String, PlayerStats)] {
+        &self.players
+    }
+
+    fn entry(&mut self, name: &str) -> &mut PlayerStats {
+        let i = match self.players.iter().position(|(player, _)| player == name) {
+            Some(i) => i,
+            None => {
+                self.players.push((name.into(), PlayerStats::default()));
+                self.players.len() - 1
+            }
+        };
+        &mut self.players[i].1
+    }
+
+    pub fn record(&mut self, round: &RoundRecord) {
+        let mut dealer = Hand::new(0, None);
+        for card in &round.dealer_cards {
+            dealer.add_card_to_hand(card.clone());
+        }
+        for seat in &round.seats {
+            let payouts: Vec<_> = round
+                .payouts
+                .iter()
+                .filter(|p| p.player == seat.name)
+                .collect();
+            let decisions: Vec<_> = round
+                .decisions
+                .iter()
+                .filter(|d| d.player == seat.name)
+                .collect();
+            let split_round = payouts.len() > 1;
+            let stats = self.entry(&seat.name);
+
+            // Insurance pays 2:1 on a dealer natural and is lost otherwise.
+            let mut round_net = match seat.insurance as i64 {
+                stake if dealer.is_natural() => 2 * stake,
+                stake => -stake,
+            };
+            stats.wagered += seat.insurance as u64;
+            for payout in &payouts {
+                let won = matches!(
+                    payout.result,
+                    RoundResult::PlayerWon | RoundResult::Blackjack
+                );
+                let doubled = decisions
+                    .iter()
+                    .any(|d| d.hand == payout.hand && d.action == Action::DoubleDown);
+
+                stats.hands += 1;
+                match payout.result {
+                    RoundResult::PlayerWon | RoundResult::Blackjack => stats.wins += 1,
+                    RoundResult::DealerWon => stats.losses += 1,
+                    RoundResult::Tie => stats.pushes += 1,
+                    RoundResult::Surrendered => stats.surrenders += 1,
+                }
+                stats.naturals += payout.natural as u32;
+                stats.busts += (payout.total > 21) as u32;
+                if doubled {
+                    stats.doubles += 1;
+                    stats.doubles_won += won as u32;
+                }
+                if split_round {
+                    stats.split_hands += 1;
+                    stats.split_hands_won += won as u32;
+                }
+                stats.wagered += payout.bet as u64;
+                round_net += payout.payout as i64 - payout.bet as i64;
+            }
+            stats.splits += decisions
+                .iter()
+                .filter(|d| d.action == Action::Split)
+                .count() as u32;
+            stats.rounds += 1;
+            stats.net += round_net;
+            stats.biggest_win = stats.biggest_win.max(round_net);
+            stats.biggest_loss = stats.biggest_loss.min(round_net);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        history::{DecisionRecord, PayoutRecord, SeatRecord},
+        rules::TableRules,
+    };
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    fn payout(hand: usize, bet: u32, result: RoundResult, payout: u32, total: u8) -> PayoutRecord {
+        PayoutRecord {
+            player: "Ana".into(),
+            hand,
+            bet,
+            result,
+            payout,
+            cards: vec![],
+            total,
+            natural: false,
+        }
+    }
+
+    fn decision(hand: usize, action: Action) -> DecisionRecord {
+        DecisionRecord {
+            player: "Ana".into(),
+            hand,
+            action,
+            total: 0,
+            soft: false,
+        }
+    }
+
+    fn round(decisions: Vec<DecisionRecord>, payouts: Vec<PayoutRecord>) -> RoundRecord {
+        RoundRecord {
+            round: 1,
+            shoe_seed: 0,
+            shoe_position: 0,
+            decks: 1,
+            rules: TableRules::default(),
+            seats: vec![SeatRecord {
+                name: "Ana".into(),
+                bankroll: 100,
+                bet: 10,
+                insurance: 0,
+            }],
+            cards: vec![],
+            dealer_cards: vec![],
+            decisions,
+            payouts,
+        }
+    }
+
+    #[rstest]
+    fn records_a_split_with_a_double() {
+        let mut stats = Statistics::default();
+        stats.record(&round(
+            vec![
+                decision(0, Action::Split),
+                decision(0, Action::DoubleDown),
+                decision(1, Action::Hit),
+            ],
+            vec![
+                payout(0, 20, RoundResult::PlayerWon, 40, 20),
+                payout(1, 10, RoundResult::DealerWon, 0, 24),
+            ],
+        ));
+
+        let ana = stats.get("Ana").unwrap();
+        assert_eq!(ana.hands, 2);
+        assert_eq!((ana.wins, ana.losses, ana.busts), (1, 1, 1));
+        assert_eq!((ana.doubles, ana.doubles_won), (1, 1));
+        assert_eq!(
+            (ana.splits, ana.split_hands, ana.split_hands_won),
+            (1, 2, 1)
+        );
+        assert_eq!(ana.net, 10);
+        assert_eq!(ana.split_win_rate(), Some(0.5));
+    }
+
+    #[rstest]
+    fn tracks_the_largest_swings() {
+        let mut stats = Statistics::default();
+        for (result, paid) in [
+            (RoundResult::PlayerWon, 20),
+            (RoundResult::DealerWon, 0),
+            (RoundResult::Surrendered, 5),
+        ] {
+            stats.record(&round(vec![], vec![payout(0, 10, result, paid, 18)]));
+        }
+
+        let ana = stats.get("Ana").unwrap();
+        assert_eq!(ana.rounds, 3);
+        assert_eq!(ana.net, -5);
+        assert_eq!((ana.biggest_win, ana.biggest_loss), (10, -10));
+    }
+
+    #[rstest]
+    #[case::dealer_natural(&["Ah", "Kd"], 10)]
+    #[case::no_natural(&["Ah", "7d"], -5)]
+    fn insurance_pays_two_to_one(#[case] dealer: &[&str], #[case] net: i64) {
+        let mut round = round(vec![], vec![]);
+        round.seats[0].insurance = 5;
+        round.dealer_cards = dealer.iter().map(|c| c.parse().unwrap()).collect();
+        let mut stats = Statistics::default();
+        stats.record(&round);
+
+        let ana = stats.get("Ana").unwrap();
+        assert_eq!((ana.wagered, ana.net), (5, net));
+    }
+
+    #[rstest]
+    fn merge_adds_up_sessions() {
+        let session = PlayerStats {
+            hands: 3,
+            wins: 2,
+            net: 15,
+            biggest_win: 20,
+            biggest_loss: -10,
+            ..PlayerStats::default()
+        };
+        let mut lifetime = session.clone();
+        lifetime.merge(&PlayerStats {
+            biggest_loss: -30,
+            ..session
+        });
+
+        assert_eq!(lifetime.hands, 6);
+        assert_eq!(lifetime.net, 30);
+        assert_eq!((lifetime.biggest_win, lifetime.biggest_loss), (20, -30));
+    }
+
+    #[rstest]
+    fn training_counts_mistakes_by_category() {
+        let mut training = TrainingStats::default();
+        training.record(HandCategory::Hard, true);
+        training.record(HandCategory::Soft, false);
+        training.record(HandCategory::Pair, false);
+        training.record(HandCategory::Pair, true);
+
+        assert_eq!(
+            (
+                training.hard_mistakes,
+                training.soft_mistakes,
+                training.pair_mistakes
+            ),
+            (0, 1, 1)
+        );
+        assert_eq!(training.accuracy(), Some(0.5));
+    }
+}
@@ -0,0 +1,395 @@
+use std::{fmt, str::FromStr};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{action::Action, card::Card, hand::Hand, rules::TableRules};
+
+/// A basic strategy chart cell: the preferred play and what to fall back to
+/// when it is not allowed. Charts write each play as its short code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Play {
+    Hit,
+    Stand,
+    DoubleOrHit,
+    DoubleOrStand,
+    Split,
+    SurrenderOrHit,
+    SurrenderOrStand,
+}
+
+impl Play {
+    pub fn resolve(&self, possible_actions: &[Action]) -> Action {
+        let allowed = |action| possible_actions.contains(&action);
+        match self {
+            Play::Hit => Action::Hit,
+            Play::Stand => Action::Stand,
+            Play::DoubleOrHit if allowed(Action::DoubleDown) => Action::DoubleDown,
+            Play::DoubleOrHit => Action::Hit,
+            Play::DoubleOrStand if allowed(Action::DoubleDown) => Action::DoubleDown,
+            Play::DoubleOrStand => Action::Stand,
+            Play::Split => Action::Split,
+            Play::SurrenderOrHit if allowed(Action::Surrender) => Action::Surrender,
+            Play::SurrenderOrHit => Action::Hit,
+            Play::SurrenderOrStand if allowed(Action::Surrender) => Action::Surrender,
+            Play::SurrenderOrStand => Action::Stand,
+        }
+    }
+}
+
+impl fmt::Display for Play {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let code = match self {
+            Play::Hit => "H",
+            Play::Stand => "S",
+            Play::DoubleOrHit => "Dh",
+            Play::DoubleOrStand => "Ds",
+            Play::Split => "P",
+            Play::SurrenderOrHit => "Rh",
+            Play::SurrenderOrStand => "Rs",
+        };
+        write!(f, "{}", code)
+    }
+}
+
+/// Reads a chart code in any case. A bare `D` doubles or else hits, as on
+/// most printed charts.
+impl FromStr for Play {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_uppercase().as_str() {
+            "H" => Ok(Play::Hit),
+            "S" => Ok(Play::Stand),
+            "D" | "DH" => Ok(Play::DoubleOrHit),
+            "DS" => Ok(Play::DoubleOrStand),
+            "P" => Ok(Play::Split),
+            "RH" => Ok(Play::SurrenderOrHit),
+            "RS" => Ok(Play::SurrenderOrStand),
+            _ => Err(format!(
+                "{:?} is not a chart play: H, S, Dh, Ds, P, Rh or Rs",
+                s
+            )),
+        }
+    }
+}
+
+impl Serialize for Play {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Play {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        code.parse().map_err(de::Error::custom)
+    }
+}
+
+/// Which part of the chart a hand is played from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HandCategory {
+    Hard,
+    Soft,
+    Pair,
+}
+
+impl fmt::Display for HandCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            HandCategory::Hard => "hard",
+            HandCategory::Soft => "soft",
+            HandCategory::Pair => "pair",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The basic strategy play for a hand and why it is right.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Advice {
+    pub action: Action,
+    pub category: HandCategory,
+    pub total: u8,
+    pub reason: &'static str,
+}
+
+/// Multi-deck basic strategy with late surrender, adjusted for a dealer
+/// hitting soft 17, for tables without double after split, for a dealer
+/// without a hole card and for doubling restricted to some totals.
+pub fn basic_strategy(
+    hand: &Hand,
+    dealer_upcard: &Card,
+    possible_actions: &[Action],
+    rules: &TableRules,
+) -> Action {
+    advise(hand, dealer_upcard, possible_actions, rules).action
+}
+
+pub fn advise(
+    hand: &Hand,
+    dealer_upcard: &Card,
+    possible_actions: &[Action],
+    rules: &TableRules,
+) -> Advice {
+    let upcard = dealer_upcard.value(0);
+    let total = hand.sum_value();
+    let possible_actions: Vec<Action> = possible_actions
+        .iter()
+        .copied()
+        .filter(|a| *a != Action::DoubleDown || rules.double_on.allows(total))
+        .collect();
+    let possible_actions = &possible_actions[..];
+    let pair = hand.size() == 2 && hand.cards_are_equal();
+    let category = match (pair, hand.is_soft()) {
+        (true, _) => HandCategory::Pair,
+        (false, true) => HandCategory::Soft,
+        (false, false) => HandCategory::Hard,
+    };
+    if pair && possible_actions.contains(&Action::Split) {
+        let rank = hand.cards()[0].value(0);
+        if let Some(play) = pair_play(rank, upcard, rules) {
+            return Advice {
+                action: play.resolve(possible_actions),
+                category,
+                total,
+                reason: split_reason(rank, upcard),
+            };
+        }
+    }
+    let play = if hand.is_soft() {
+        soft_play(total, upcard, rules)
+    } else {
+        hard_play(total, upcard, rules)
+    };
+    let action = play.resolve(possible_actions);
+    Advice {
+        action,
+        category,
+        total,
+        reason: reason(action, total, hand.is_soft(), upcard),
+    }
+}
+
+/// Returns `None` when the pair should be played as a regular total.
+/// Without a hole card, aces and eights are not split against an upcard that
+/// could make a natural, as the split bet would be lost to it too.
+fn pair_play(rank: u8, upcard: u8, rules: &TableRules) -> Option<Play> {
+    let das = rules.double_after_split;
+    let split = match rank {
+        11 if !rules.dealer_peeks => upcard != 11,
+        8 if !rules.dealer_peeks => upcard < 10,
+        11 | 8 => true,
+        10 | 5 => false,
+        9 => !matches!(upcard, 7 | 10 | 11),
+        7 => upcard <= 7,
+        3 | 2 if das => upcard <= 7,
+        3 | 2 => (4..=7).contains(&upcard),
+        6 if das => upcard <= 6,
+        6 => (3..=6).contains(&upcard),
+        4 => das && matches!(upcard, 5 | 6),
+        _ => false,
+    };
+    split.then_some(Play::Split)
+}
+
+fn soft_play(total: u8, upcard: u8, rules: &TableRules) -> Play {
+    let h17 = rules.dealer_hits_soft_17;
+    match (total, upcard) {
+        (19, 6) if h17 => Play::DoubleOrStand,
+        (19..=21, _) => Play::Stand,
+        (18, 2) if h17 => Play::DoubleOrStand,
+        (18, 2 | 7 | 8) => Play::Stand,
+        (18, 3..=6) => Play::DoubleOrStand,
+        (18, _) => Play::Hit,
+        (17, 3..=6) => Play::DoubleOrHit,
+        (15 | 16, 4..=6) => Play::DoubleOrHit,
+        (13 | 14, 5 | 6) => Play::DoubleOrHit,
+        _ => Play::Hit,
+    }
+}
+
+/// Without a hole card, 11 is not doubled against a ten or an ace, which
+/// could still make a natural and take the doubled bet, and surrendering
+/// before the dealer looks saves half the bet against more hands.
+fn hard_play(total: u8, upcard: u8, rules: &TableRules) -> Play {
+    let h17 = rules.dealer_hits_soft_17;
+    let peeks = rules.dealer_peeks;
+    match (total, upcard) {
+        (11, 10 | 11) if !peeks => Play::Hit,
+        (5..=7 | 12..=16, 11) | (14, 10) if !peeks => Play::SurrenderOrHit,
+        (17, 11) if h17 || !peeks => Play::SurrenderOrStand,
+        (17.., _) => Play::Stand,
+        (16, 9..=11) | (15, 10) => Play::SurrenderOrHit,
+        (15, 11) if h17 => Play::SurrenderOrHit,
+        (13..=16, 2..=6) => Play::Stand,
+        (13..=16, _) => Play::Hit,
+        (12, 4..=6) => Play::Stand,
+        (12, _) => Play::Hit,
+        (11, 11) if h17 => Play::DoubleOrHit,
+        (11, 2..=10) => Play::DoubleOrHit,
+        (10, 2..=9) => Play::DoubleOrHit,
+        (9, 3..=6) => Play::DoubleOrHit,
+        _ => Play::Hit,
+    }
+}
+
+fn split_reason(rank: u8, upcard: u8) -> &'static str {
+    match rank {
+        11 => "A pair of aces is only soft 12, while each ace on its own starts a hand that can make 21",
+        8 => "Hard 16 is the worst total there is; two hands starting from an 8 lose less",
+        _ if upcard <= 6 => "The dealer is likely to bust, so put more money on the table with two hands",
+        _ => "Each card on its own starts a better hand than the pair's total",
+    }
+}
+
+fn reason(action: Action, total: u8, soft: bool, upcard: u8) -> &'static str {
+    match action {
+        Action::Surrender => {
+            "This hand wins less than a quarter of the time, so giving up half the bet loses less"
+        }
+        Action::DoubleDown if soft => {
+            "A soft hand cannot bust on one card and the dealer is weak, so double the bet"
+        }
+        Action::DoubleDown => {
+            "One card will often make a strong total against this upcard, so double the bet while ahead"
+        }
+        Action::Split => "Each card on its own starts a better hand than the pair's total",
+        Action::Stand if soft => "This soft total already beats what the dealer usually ends on",
+        Action::Stand if total >= 17 => "Hard 17 or more busts too often to be worth hitting",
+        Action::Stand => {
+            "The dealer shows a weak card and will often bust, so don't risk busting first"
+        }
+        Action::Hit if soft => "A soft hand cannot bust on the next card, so drawing can only help",
+        Action::Hit if total <= 11 => "No card can bust a total of 11 or less",
+        Action::Hit if upcard >= 7 => {
+            "The dealer will likely make 17 or more, so standing on this total loses more than hitting"
+        }
+        Action::Hit => "A total this low loses to most dealer hands, so hitting is worth the risk",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::DoubleOn;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    const ALL: [Action; 5] = [
+        Action::Hit,
+        Action::Stand,
+        Action::DoubleDown,
+        Action::Split,
+        Action::Surrender,
+    ];
+
+    fn hand(codes: &[&str]) -> Hand {
+        let mut hand = Hand::new(10, None);
+        for code in codes {
+            hand.add_card_to_hand(code.parse().unwrap());
+        }
+        hand
+    }
+
+    #[rstest]
+    #[case::hard_sixteen_vs_ten(&["10h", "6s"], "10d", &ALL, Action::Surrender)]
+    #[case::hard_sixteen_vs_ten_no_surrender(&["10h", "6s"], "10d", &[Action::Hit, Action::Stand], Action::Hit)]
+    #[case::hard_twelve_vs_four(&["10h", "2s"], "4d", &ALL, Action::Stand)]
+    #[case::hard_eleven_vs_six(&["5h", "6s"], "6d", &ALL, Action::DoubleDown)]
+    #[case::three_card_eleven(&["2h", "3s", "6c"], "6d", &[Action::Hit, Action::Stand], Action::Hit)]
+    #[case::soft_eighteen_vs_nine(&["Ah", "7s"], "9d", &ALL, Action::Hit)]
+    #[case::soft_eighteen_vs_six_no_double(&["Ah", "7s"], "6d", &[Action::Hit, Action::Stand], Action::Stand)]
+    #[case::aces(&["Ah", "As"], "10d", &ALL, Action::Split)]
+    #[case::tens(&["10h", "10s"], "6d", &ALL, Action::Stand)]
+    #[case::fives_vs_six(&["5h", "5s"], "6d", &ALL, Action::DoubleDown)]
+    #[case::eights_without_split(&["8h", "8s"], "10d", &[Action::Hit, Action::Stand, Action::Surrender], Action::Surrender)]
+    fn basic_strategy_cells(
+        #[case] codes: &[&str],
+        #[case] upcard: &str,
+        #[case] possible_actions: &[Action],
+        #[case] expected: Action,
+    ) {
+        let upcard = upcard.parse().unwrap();
+        assert_eq!(
+            basic_strategy(
+                &hand(codes),
+                &upcard,
+                possible_actions,
+                &TableRules::default()
+            ),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case::eleven_vs_ace_h17(&["6h", "5s"], "Ad", true, true, Action::DoubleDown)]
+    #[case::eleven_vs_ace_s17(&["6h", "5s"], "Ad", false, true, Action::Hit)]
+    #[case::seventeen_vs_ace_h17(&["10h", "7s"], "Ad", true, true, Action::Surrender)]
+    #[case::soft_nineteen_vs_six_h17(&["Ah", "8s"], "6d", true, true, Action::DoubleDown)]
+    #[case::twos_vs_two_das(&["2h", "2s"], "2d", false, true, Action::Split)]
+    #[case::twos_vs_two_no_das(&["2h", "2s"], "2d", false, false, Action::Hit)]
+    #[case::fours_vs_five_no_das(&["4h", "4s"], "5d", false, false, Action::Hit)]
+    fn adjusts_to_the_rules(
+        #[case] codes: &[&str],
+        #[case] upcard: &str,
+        #[case] dealer_hits_soft_17: bool,
+        #[case] double_after_split: bool,
+        #[case] expected: Action,
+    ) {
+        let rules = TableRules {
+            dealer_hits_soft_17,
+            double_after_split,
+            ..TableRules::default()
+        };
+        let upcard = upcard.parse().unwrap();
+        assert_eq!(
+            basic_strategy(&hand(codes), &upcard, &ALL, &rules),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case::eleven_vs_ten_no_peek(&["6h", "5s"], "10d", true, DoubleOn::Any, Action::Hit)]
+    #[case::eleven_vs_ten_peek(&["6h", "5s"], "10d", false, DoubleOn::Any, Action::DoubleDown)]
+    #[case::aces_vs_ace_no_peek(&["Ah", "As"], "Ad", true, DoubleOn::Any, Action::Hit)]
+    #[case::eights_vs_ten_no_peek(&["8h", "8s"], "10d", true, DoubleOn::Any, Action::Surrender)]
+    #[case::fourteen_vs_ace_no_peek(&["10h", "4s"], "Ad", true, DoubleOn::Any, Action::Surrender)]
+    #[case::nine_vs_four_ten_to_eleven(&["5h", "4s"], "4d", false, DoubleOn::TenToEleven, Action::Hit)]
+    #[case::soft_eighteen_vs_six_nine_to_eleven(&["Ah", "7s"], "6d", false, DoubleOn::NineToEleven, Action::Stand)]
+    fn adjusts_to_the_hole_card_and_doubling_rules(
+        #[case] codes: &[&str],
+        #[case] upcard: &str,
+        #[case] no_hole_card: bool,
+        #[case] double_on: DoubleOn,
+        #[case] expected: Action,
+    ) {
+        let rules = TableRules {
+            dealer_peeks: !no_hole_card,
+            double_on,
+            ..TableRules::default()
+        };
+        let upcard = upcard.parse().unwrap();
+        assert_eq!(
+            basic_strategy(&hand(codes), &upcard, &ALL, &rules),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case::hard(&["10h", "6s"], "10d", HandCategory::Hard)]
+    #[case::soft(&["Ah", "6s"], "10d", HandCategory::Soft)]
+    #[case::pair_played_as_a_total(&["10h", "10s"], "6d", HandCategory::Pair)]
+    #[case::split_aces(&["Ah", "As"], "6d", HandCategory::Pair)]
+    fn advice_names_the_chart(
+        #[case] codes: &[&str],
+        #[case] upcard: &str,
+        #[case] category: HandCategory,
+    ) {
+        let upcard = upcard.parse().unwrap();
+        let advice = advise(&hand(codes), &upcard, &ALL, &TableRules::default());
+        assert_eq!(advice.category, category);
+        assert!(!advice.reason.is_empty());
+    }
+}
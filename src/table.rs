@@ -0,0 +1,437 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    betting::BettingConfig,
+    bot::{BotConfig, BotController},
+    console_printer::Theme,
+    controller::{
+        BasicStrategyController, ChartController, ConsoleController, Controller, ControllerKind,
+    },
+    counter::{CounterConfig, CountingController},
+    error::Error,
+    q_table::QTableController,
+    rules::{DoubleOn, Payout, TableRules},
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShoeConfig {
+    pub decks: u8,
+    /// Fraction of the shoe dealt before it is reshuffled between rounds.
+    /// `0.0` reshuffles before every round.
+    pub penetration: f64,
+}
+
+impl Default for ShoeConfig {
+    fn default() -> Self {
+        Self {
+            decks: 1,
+            penetration: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+    pub min_bet: u32,
+    pub max_bet: u32,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            min_bet: 1,
+            max_bet: 500,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeatConfig {
+    pub name: String,
+    #[serde(default = "SeatConfig::default_bankroll")]
+    pub bankroll: u32,
+    #[serde(default)]
+    pub controller: ControllerKind,
+    /// Strategy chart file played by a `chart` controller.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chart: Option<PathBuf>,
+    /// How a `counter` controller counts and bets, its defaults if left out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counter: Option<CounterConfig>,
+    /// Q-table file played by a `q-table` controller.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub q_table: Option<PathBuf>,
+    /// The program a `bot` controller runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bot: Option<BotConfig>,
+    /// A betting system that sizes the seat's bets instead of its
+    /// controller.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub betting: Option<BettingConfig>,
+}
+
+impl SeatConfig {
+    fn default_bankroll() -> u32 {
+        100
+    }
+
+    /// Builds the seat's controller, reading its chart file if it has one.
+    pub fn controller(&self) -> Result<Box<dyn Controller>, Error> {
+        Ok(match (self.controller, &self.chart) {
+            (ControllerKind::Human, _) => Box::new(ConsoleController),
+            (ControllerKind::BasicStrategy, _) => Box::new(BasicStrategyController),
+            (ControllerKind::Chart, Some(path)) => Box::new(ChartController::load(path)?),
+            (ControllerKind::Counter, _) => Box::new(CountingController::new(
+                self.counter.clone().unwrap_or_default(),
+            )),
+            (ControllerKind::QTable, _) => match &self.q_table {
+                Some(path) => Box::new(QTableController::load(path)?),
+                None => return Err(self.invalid("q_table", "is needed by a q-table seat")),
+            },
+            (ControllerKind::Bot, _) => match &self.bot {
+                Some(bot) => Box::new(BotController::spawn(bot.clone())?),
+                None => return Err(self.invalid("bot", "is needed by a bot seat")),
+            },
+            (ControllerKind::Chart, None) => {
+                return Err(self.invalid("chart", "is needed by a chart seat"))
+            }
+        })
+    }
+
+    /// The error for one of the seat's settings, named after the seat.
+    fn invalid(&self, key: &str, reason: &str) -> Error {
+        Error::Config {
+            path: None,
+            key: format!("{}'s {}", self.name, key),
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Everything about a table that a session is started with, read from a
+/// TOML file or taken from one of the named casino presets.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TableConfig {
+    pub rules: TableRules,
+    pub shoe: ShoeConfig,
+    pub limits: Limits,
+    pub seats: Vec<SeatConfig>,
+    pub theme: Theme,
+}
+
+impl TableConfig {
+    pub const PRESETS: [&'static str; 6] = [
+        "standard",
+        "las-vegas-strip",
+        "downtown-vegas",
+        "atlantic-city",
+        "european-no-hole-card",
+        "single-deck-6-5",
+    ];
+
+    pub fn preset(name: &str) -> Option<Self> {
+        let (rules, decks, penetration, min_bet) = match name {
+            "standard" => return Some(Self::default()),
+            "las-vegas-strip" => (TableRules::default(), 6, 0.75, 10),
+            "downtown-vegas" => (
+                TableRules {
+                    dealer_hits_soft_17: true,
+                    surrender: false,
+                    ..TableRules::default()
+                },
+                2,
+                0.65,
+                5,
+            ),
+            "atlantic-city" => (TableRules::default(), 8, 0.75, 10),
+            "european-no-hole-card" => (
+                TableRules {
+                    dealer_peeks: false,
+                    double_on: DoubleOn::NineToEleven,
+                    surrender: false,
+                    max_hands: 2,
+                    ..TableRules::default()
+                },
+                6,
+                0.75,
+                5,
+            ),
+            "single-deck-6-5" => (
+                TableRules {
+                    dealer_hits_soft_17: true,
+                    blackjack_payout: Payout::new(6, 5),
+                    double_after_split: false,
+                    surrender: false,
+                    max_hands: 2,
+                    ..TableRules::default()
+                },
+                1,
+                0.5,
+                5,
+            ),
+            _ => return None,
+        };
+        Some(Self {
+            rules,
+            shoe: ShoeConfig { decks, penetration },
+            limits: Limits {
+                min_bet,
+                max_bet: min_bet * 100,
+            },
+            ..Self::default()
+        })
+    }
+
+    /// Resolves a `--table` value, which is either a preset name or the path
+    /// of a TOML table file.
+    pub fn load(preset_or_path: &str) -> Result<Self, Error> {
+        if let Some(config) = Self::preset(preset_or_path) {
+            return Ok(config);
+        }
+        let path = Path::new(preset_or_path);
+        if !path.exists() {
+            return Err(Error::UnknownPreset(preset_or_path.into()));
+        }
+        let contents = fs::read_to_string(path).map_err(|source| Error::File {
+            path: path.into(),
+            source,
+        })?;
+        Self::parse(&contents).map_err(|(key, reason)| Error::Config {
+            path: Some(path.into()),
+            key,
+            reason,
+        })
+    }
+
+    /// Parses and validates a table file, naming the offending key on error.
+    fn parse(contents: &str) -> Result<Self, (String, String)> {
+        let deserializer = toml::Deserializer::new(contents);
+        let config: Self = serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            let key = e.path().to_string();
+            (key, e.into_inner().message().to_string())
+        })?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), (String, String)> {
+        let invalid = |key: &str, reason: &str| Err((key.to_string(), reason.to_string()));
+        if !(1..=8).contains(&self.shoe.decks) {
+            return invalid("shoe.decks", "must be between 1 and 8");
+        }
+        if !(0.0..1.0).contains(&self.shoe.penetration) {
+            return invalid("shoe.penetration", "must be at least 0 and below 1");
+        }
+        if self.limits.min_bet == 0 {
+            return invalid("limits.min_bet", "must be at least 1");
+        }
+        if self.limits.max_bet < self.limits.min_bet {
+            return invalid("limits.max_bet", "must not be below limits.min_bet");
+        }
+        if self.rules.max_hands == 0 {
+            return invalid("rules.max_hands", "must be at least 1");
+        }
+        let mut names = HashSet::new();
+        for (i, seat) in self.seats.iter().enumerate() {
+            if !names.insert(&seat.name) {
+                return invalid(&format!("seats[{}].name", i), "is already taken");
+            }
+            let chart = seat.controller == ControllerKind::Chart;
+            if chart && seat.chart.is_none() {
+                return invalid(&format!("seats[{}].chart", i), "is needed by a chart seat");
+            }
+            if !chart && seat.chart.is_some() {
+                return invalid(
+                    &format!("seats[{}].chart", i),
+                    "is only read by chart seats",
+                );
+            }
+            let q_table = seat.controller == ControllerKind::QTable;
+            if q_table && seat.q_table.is_none() {
+                return invalid(
+                    &format!("seats[{}].q_table", i),
+                    "is needed by a q-table seat",
+                );
+            }
+            if !q_table && seat.q_table.is_some() {
+                return invalid(
+                    &format!("seats[{}].q_table", i),
+                    "is only read by q-table seats",
+                );
+            }
+            match &seat.bot {
+                Some(_) if seat.controller != ControllerKind::Bot => {
+                    return invalid(&format!("seats[{}].bot", i), "is only read by bot seats");
+                }
+                Some(bot) => {
+                    if let Err((key, reason)) = bot.validate() {
+                        return invalid(&format!("seats[{}].bot.{}", i, key), reason);
+                    }
+                }
+                None if seat.controller == ControllerKind::Bot => {
+                    return invalid(&format!("seats[{}].bot", i), "is needed by a bot seat");
+                }
+                None => (),
+            }
+            match &seat.counter {
+                Some(_) if seat.controller != ControllerKind::Counter => {
+                    return invalid(
+                        &format!("seats[{}].counter", i),
+                        "is only read by counter seats",
+                    );
+                }
+                Some(counter) => {
+                    if let Err((key, reason)) = counter.validate() {
+                        return invalid(&format!("seats[{}].counter.{}", i, key), reason);
+                    }
+                }
+                None => (),
+            }
+            if seat.betting.as_ref().and_then(|b| b.unit) == Some(0) {
+                return invalid(&format!("seats[{}].betting.unit", i), "must be at least 1");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    fn every_preset_is_valid() {
+        for name in TableConfig::PRESETS {
+            let config = TableConfig::preset(name).unwrap();
+            assert_eq!(config.validate(), Ok(()), "{}", name);
+        }
+    }
+
+    #[rstest]
+    fn parses_a_full_table() {
+        let config = TableConfig::parse(
+            r#"
+            theme = "ascii"
+
+            [rules]
+            dealer_hits_soft_17 = true
+            double_on = "10-11"
+
+            [shoe]
+            decks = 6
+            penetration = 0.8
+
+            [limits]
+            min_bet = 25
+            max_bet = 1000
+
+            [[seats]]
+            name = "Ana"
+            bankroll = 500
+
+            [[seats]]
+            name = "Bot"
+            controller = "basic-strategy"
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.rules.dealer_hits_soft_17);
+        assert_eq!(config.rules.double_on, DoubleOn::TenToEleven);
+        assert_eq!(config.shoe.decks, 6);
+        assert_eq!(config.limits.min_bet, 25);
+        assert_eq!(config.theme, Theme::Ascii);
+        assert_eq!(
+            config.seats[1],
+            SeatConfig {
+                name: "Bot".into(),
+                bankroll: 100,
+                controller: ControllerKind::BasicStrategy,
+                chart: None,
+                counter: None,
+                q_table: None,
+                bot: None,
+                betting: None,
+            }
+        );
+    }
+
+    #[rstest]
+    #[case::unknown_key("[shoe]\nsize = 6", "shoe.size")]
+    #[case::wrong_type("[rules]\nsurrender = \"yes\"", "rules.surrender")]
+    #[case::bad_payout("[rules]\nblackjack_payout = \"1.5\"", "rules.blackjack_payout")]
+    #[case::too_many_decks("[shoe]\ndecks = 9", "shoe.decks")]
+    #[case::limits_reversed("[limits]\nmin_bet = 50\nmax_bet = 10", "limits.max_bet")]
+    #[case::duplicate_seat("[[seats]]\nname = \"A\"\n[[seats]]\nname = \"A\"", "seats[1].name")]
+    #[case::chart_seat_without_chart(
+        "[[seats]]\nname = \"A\"\ncontroller = \"chart\"",
+        "seats[0].chart"
+    )]
+    #[case::q_table_seat_without_file(
+        "[[seats]]\nname = \"A\"\ncontroller = \"q-table\"",
+        "seats[0].q_table"
+    )]
+    #[case::bot_seat_without_program(
+        "[[seats]]\nname = \"A\"\ncontroller = \"bot\"",
+        "seats[0].bot"
+    )]
+    #[case::bot_without_command(
+        "[[seats]]\nname = \"A\"\ncontroller = \"bot\"\n[seats.bot]\ncommand = []",
+        "seats[0].bot.command"
+    )]
+    #[case::counter_settings_on_a_bot(
+        "[[seats]]\nname = \"A\"\n[seats.counter]\nunit = 5",
+        "seats[0].counter"
+    )]
+    #[case::ramp_out_of_order(
+        "[[seats]]\nname = \"A\"\ncontroller = \"counter\"\n[seats.counter]\nramp = [{ count = 3, units = 4 }, { count = 2, units = 2 }]",
+        "seats[0].counter.ramp"
+    )]
+    #[case::betting_unit_zero(
+        "[[seats]]\nname = \"A\"\n[seats.betting]\nsystem = \"martingale\"\nunit = 0",
+        "seats[0].betting.unit"
+    )]
+    #[case::unknown_betting_system(
+        "[[seats]]\nname = \"A\"\n[seats.betting]\nsystem = \"parlay\"",
+        "seats[0].betting.system"
+    )]
+    fn errors_name_the_offending_key(#[case] contents: &str, #[case] key: &str) {
+        let (error_key, _) = TableConfig::parse(contents).unwrap_err();
+        assert_eq!(error_key, key);
+    }
+
+    #[rstest]
+    #[case::chart(ControllerKind::Chart, "Ana's chart: is needed by a chart seat")]
+    #[case::q_table(ControllerKind::QTable, "Ana's q_table: is needed by a q-table seat")]
+    #[case::bot(ControllerKind::Bot, "Ana's bot: is needed by a bot seat")]
+    fn a_seat_missing_its_file_names_the_setting(
+        #[case] controller: ControllerKind,
+        #[case] message: &str,
+    ) {
+        let seat = SeatConfig {
+            name: "Ana".into(),
+            bankroll: 100,
+            controller,
+            chart: None,
+            counter: None,
+            q_table: None,
+            bot: None,
+            betting: None,
+        };
+        let error = seat.controller().unwrap_err();
+        assert!(matches!(error, Error::Config { path: None, .. }));
+        assert_eq!(error.to_string(), message);
+    }
+}